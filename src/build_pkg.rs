@@ -1,70 +1,506 @@
-use std::path::PathBuf;
+use std::{
+    env::consts::EXE_SUFFIX,
+    path::{Path, PathBuf},
+};
+
+use sha2::{Digest, Sha256};
+use toml::Table;
 
 use crate::{
+    cfg::Platform,
     cmake,
-    command::Command,
-    dependency_manager::resolve_dependencies,
+    command::{Command, parse_flag_value},
+    config::Config,
+    config_pkg::{resolve_config, write_header},
+    config_schema::config_header,
+    dependency::DependencyKind,
+    dependency_manager::{ResolvedDependency, fetch_build_dependencies, resolve_dependencies},
+    features::{FeatureSelection, ResolvedFeatures, resolve_features},
     fmt::{info, success},
     mode::Mode,
     package::{Package, PackageType},
+    sources::expand_sources,
+    target::Target,
+    workspace::Workspace,
 };
 
 #[derive(Default)]
 pub struct BuildPkg {
     path: PathBuf,
     mode: Mode,
+    target: Option<String>,
+    target_dir: Option<PathBuf>,
+    packages: Vec<String>,
+    workspace: bool,
+    exclude: Vec<String>,
+    features: FeatureSelection,
 }
 
 impl BuildPkg {
-    #[allow(unused)]
-    pub fn path(&self) -> &PathBuf {
-        &self.path
+    pub fn mode(&self) -> &Mode {
+        &self.mode
     }
 
-    fn create_cmake_lists(
-        &self,
-        content: &str,
-        sources_ext: Vec<String>,
-        includes_ext: Vec<String>,
-    ) -> Result<Package, String> {
+    /// The same build settings for the package at `path`, built into
+    /// `target_dir`, so it doesn't touch the build files of the selected
+    /// packages.
+    pub fn isolated(&self, path: &Path, target_dir: &Path) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            mode: self.mode,
+            target: self.target.clone(),
+            target_dir: Some(target_dir.to_path_buf()),
+            features: self.features.clone(),
+            ..Default::default()
+        }
+    }
+
+    /// Parses one argument shared by the commands that build the package,
+    /// returning how many arguments were consumed.
+    pub fn parse_arg(&mut self, args: &[String]) -> Option<usize> {
+        let arg = args.first()?;
+
+        if let Ok(mode) = arg.as_str().try_into() {
+            self.mode = mode;
+            return Some(1);
+        }
+
+        if let Some((target, consumed)) = parse_flag_value(args, &["--target"]) {
+            self.target = Some(target.to_string());
+            return Some(consumed);
+        }
+
+        if let Some((target_dir, consumed)) = parse_flag_value(args, &["--target-dir"]) {
+            self.target_dir = Some(PathBuf::from(target_dir));
+            return Some(consumed);
+        }
+
+        if let Some((package, consumed)) = parse_flag_value(args, &["-p", "--package"]) {
+            self.packages.push(package.to_string());
+            return Some(consumed);
+        }
+
+        if arg == "--workspace" {
+            self.workspace = true;
+            return Some(1);
+        }
+
+        if let Some((package, consumed)) = parse_flag_value(args, &["--exclude"]) {
+            self.exclude.push(package.to_string());
+            return Some(consumed);
+        }
+
+        if let Some((features, consumed)) = parse_flag_value(args, &["-F", "--features"]) {
+            self.features.features.extend(
+                features
+                    .split([',', ' '])
+                    .filter(|f| !f.is_empty())
+                    .map(String::from),
+            );
+            return Some(consumed);
+        }
+        if arg == "--all-features" {
+            self.features.all_features = true;
+            return Some(1);
+        }
+        if arg == "--no-default-features" {
+            self.features.no_default_features = true;
+            return Some(1);
+        }
+
+        if arg.starts_with('-') || !self.path.as_os_str().is_empty() {
+            return None;
+        }
+        self.path = PathBuf::from(arg);
+        Some(1)
+    }
+
+    /// Fills the defaults of arguments not given in the command line.
+    pub fn finish_args(&mut self) -> Option<()> {
+        if self.path.as_os_str().is_empty() {
+            self.path = std::env::current_dir().ok()?;
+        }
+
+        if self.target_dir.is_none() {
+            self.target_dir = std::env::var_os("TAILOR_TARGET_DIR").map(PathBuf::from);
+        }
+        if let Some(target_dir) = &self.target_dir {
+            self.target_dir = Some(std::env::current_dir().ok()?.join(target_dir));
+        }
+
+        Some(())
+    }
+
+    pub fn target_name(&self) -> Option<&str> {
+        self.target.as_deref()
+    }
+
+    /// The folders of the packages selected by the command line, with the
+    /// workspace they belong to. `-p` selects packages by name inside the
+    /// workspace. `--workspace`, or running at the workspace root, selects
+    /// all the members but the `--exclude`d ones. Otherwise, it's the package
+    /// at the given path.
+    pub fn selected_packages(&self) -> Result<(Option<Workspace>, Vec<PathBuf>), String> {
         let abs_path = self
             .path
             .canonicalize()
             .map_err(|e| format!("fail to canonicalize path: {}", e))?;
+        let workspace = Workspace::find(&abs_path)?;
 
-        if !abs_path.join("Tailor.toml").exists() {
-            return Err("Tailor.toml file does not exist".to_string());
+        if !self.packages.is_empty() && self.workspace {
+            return Err("`--package` and `--workspace` can't be used together".to_string());
         }
 
-        let pkg = Package::from_file(&abs_path.join("Tailor.toml"))?;
+        let all = self.workspace
+            || (self.packages.is_empty()
+                && workspace.as_ref().is_some_and(|ws| ws.root() == abs_path));
+        if !self.exclude.is_empty() && !all {
+            return Err("`--exclude` can only be used together with `--workspace`".to_string());
+        }
+
+        let paths = if !self.packages.is_empty() {
+            self.packages
+                .iter()
+                .map(|name| match &workspace {
+                    Some(ws) => ws
+                        .member(name)
+                        .map(|(path, _)| path.clone())
+                        .ok_or_else(|| {
+                            format!("package `{}` is not a member of the workspace", name)
+                        }),
+                    None if Package::from_file(&abs_path.join("Tailor.toml"))?.name() == name => {
+                        Ok(abs_path.clone())
+                    }
+                    None => Err(format!("package `{}` not found", name)),
+                })
+                .collect::<Result<Vec<PathBuf>, String>>()?
+        } else if all {
+            let ws = workspace
+                .as_ref()
+                .ok_or("`--workspace` can only be used inside a workspace")?;
+            if let Some(name) = self.exclude.iter().find(|name| ws.member(name).is_none()) {
+                return Err(format!(
+                    "excluded package `{}` is not a member of the workspace",
+                    name
+                ));
+            }
+            ws.members()
+                .iter()
+                .filter(|(_, pkg)| !self.exclude.iter().any(|name| name == pkg.name()))
+                .map(|(path, _)| path.clone())
+                .collect()
+        } else {
+            vec![abs_path]
+        };
+
+        Ok((workspace, paths))
+    }
+
+    /// The single binary package among the selected ones, for the commands
+    /// that execute it, such as `run` and `debug`.
+    pub fn selected_binary(
+        &self,
+        command: &str,
+    ) -> Result<(Option<Workspace>, PathBuf, Package), String> {
+        let (workspace, paths) = self.selected_packages()?;
+
+        let mut binaries = vec![];
+        for path in paths {
+            let pkg = Package::from_file(&path.join("Tailor.toml"))?;
+            if !pkg.is_virtual() && matches!(pkg.pkg_type(), PackageType::Binary) {
+                binaries.push((path, pkg));
+            }
+        }
+
+        match binaries.len() {
+            0 => Err(format!(
+                "It's not possible {} a library package, an sdk package or a virtual workspace",
+                command
+            )),
+            1 => {
+                let (path, pkg) = binaries.remove(0);
+                Ok((workspace, path, pkg))
+            }
+            _ => Err(format!(
+                "there are many binary packages to {}, select one with `-p <name>`: {}",
+                command,
+                binaries
+                    .iter()
+                    .map(|(_, pkg)| pkg.name())
+                    .collect::<Vec<&str>>()
+                    .join(", ")
+            )),
+        }
+    }
+
+    /// Looks up the selected target, first in the package manifest and then
+    /// in the global config. Returns `None` when building for the host.
+    pub fn target(&self, pkg: &Package, abs_path: &Path) -> Result<Option<Target>, String> {
+        let Some(name) = &self.target else {
+            return Ok(None);
+        };
+
+        if let Some(target) = pkg.targets().iter().find(|t| t.name() == name) {
+            return Ok(Some(target.relative_to(abs_path)));
+        }
+
+        Config::load()?
+            .target(name)
+            .cloned()
+            .map(Some)
+            .ok_or_else(|| {
+                format!(
+                    "target `{}` is not defined in Tailor.toml or in the config",
+                    name
+                )
+            })
+    }
+
+    /// The platform the package is built for: the selected target, or the
+    /// host when there is none.
+    pub fn platform(&self, pkg: &Package, abs_path: &Path) -> Result<Platform, String> {
+        Ok(self
+            .target(pkg, abs_path)?
+            .map_or_else(Platform::host, |target| Platform::from_target(&target)))
+    }
+
+    /// The folder holding the build files: `<output>/<mode>` for the host and
+    /// `<output>/<target>/<mode>` otherwise. The output folder is `build/`
+    /// inside the package, unless a target directory is given by
+    /// `--target-dir`, `TAILOR_TARGET_DIR` or the config, or the package is a
+    /// workspace member, which uses `build/` at the workspace root. As a target
+    /// directory may be shared between packages, it has a folder per package.
+    pub fn build_dir(&self, pkg: &Package, abs_path: &Path) -> Result<PathBuf, String> {
+        let output = self.output_dir(pkg, abs_path)?;
+
+        Ok(match &self.target {
+            Some(target) => output.join(target),
+            None => output,
+        }
+        .join(self.mode.to_string()))
+    }
+
+    /// The program built for the binary package `pkg`, with the executable
+    /// extension of the host, such as `.exe` on Windows.
+    pub fn binary_path(&self, pkg: &Package, abs_path: &Path) -> Result<PathBuf, String> {
+        Ok(self
+            .build_dir(pkg, abs_path)?
+            .join(format!("{}{}", pkg.name(), EXE_SUFFIX)))
+    }
+
+    /// The folder holding the build files of the tests, inside the build
+    /// folder.
+    pub fn test_dir(&self, pkg: &Package, abs_path: &Path) -> Result<PathBuf, String> {
+        Ok(self.build_dir(pkg, abs_path)?.join("tests"))
+    }
 
-        let sources = pkg
-            .sources()
+    /// The output folder of a package, holding the build files of all modes
+    /// and targets.
+    pub fn output_dir(&self, pkg: &Package, abs_path: &Path) -> Result<PathBuf, String> {
+        let target_dir = match &self.target_dir {
+            Some(target_dir) => Some(target_dir.clone()),
+            None => match Config::load()?.target_dir() {
+                Some(target_dir) => Some(target_dir.to_path_buf()),
+                None => Workspace::find(abs_path)?.map(|ws| ws.root().join("build")),
+            },
+        };
+
+        Ok(match target_dir {
+            Some(target_dir) => target_dir.join(pkg.name()),
+            None => abs_path.join("build"),
+        })
+    }
+
+    fn create_cmake_lists(
+        &self,
+        abs_path: &Path,
+        pkg: &Package,
+        content: String,
+        mut dependencies: Vec<ResolvedDependency>,
+        features: &ResolvedFeatures,
+        tests: &[PathBuf],
+    ) -> Result<(), String> {
+        let target = self.target(pkg, abs_path)?;
+        let build_dir = if tests.is_empty() {
+            self.build_dir(pkg, abs_path)?
+        } else {
+            self.test_dir(pkg, abs_path)?
+        };
+
+        // The features of the dependencies are public: the package sees them
+        // too, as it includes the headers of the dependencies.
+        let mut definitions = features.definitions();
+        for definition in dependencies.iter().flat_map(|d| d.definitions.iter()) {
+            if !definitions.contains(definition) {
+                definitions.push(definition.clone());
+            }
+        }
+
+        // Each test has its own `main`, so the file holding the `main` of the
+        // package is left out of the tests.
+        let main = pkg
+            .main()
+            .filter(|_| !tests.is_empty())
+            .map(|main| abs_path.join(main));
+        let mut own = ResolvedDependency {
+            sources: expand_sources(abs_path, pkg.sources())?
+                .iter()
+                .filter(|s| main.as_ref() != Some(s))
+                .map(|s| s.to_string_lossy().to_string())
+                .collect(),
+            includes: pkg
+                .includes()
+                .iter()
+                .map(|s| abs_path.join(s).to_string_lossy().to_string())
+                .collect(),
+            definitions: definitions.clone(),
+            ..Default::default()
+        };
+        // The `autoconf.h` of the Kconfig options and the `<name>_config.h`
+        // of the `[config]` options are shared by the package and its tests.
+        if let Some((kconfig, values)) = resolve_config(pkg, abs_path, &[])? {
+            own.headers
+                .push(("autoconf.h".to_string(), kconfig.header(&values)));
+        }
+        own.headers.extend(config_header(pkg, &Table::new())?);
+        let pkg_build_dir = self.build_dir(pkg, abs_path)?;
+        for unit in dependencies.iter_mut().chain([&mut own]) {
+            let mut generated = None;
+            for (file_name, header) in &unit.headers {
+                generated = Some(write_header(&pkg_build_dir, file_name, header)?);
+            }
+            unit.includes
+                .extend(generated.map(|dir| dir.to_string_lossy().to_string()));
+        }
+        let tests = ResolvedDependency {
+            sources: tests
+                .iter()
+                .map(|s| s.to_string_lossy().to_string())
+                .collect(),
+            includes: vec![],
+            definitions,
+            ..Default::default()
+        };
+        // The CMake target of the package, which is created by the SDK for
+        // some `sdk` packages.
+        let target_name = pkg.sdk().map_or(pkg.name(), |sdk| sdk.target(pkg.name()));
+        let package_sources = source_list(&own.sources);
+        let package_include = quote_list(&own.includes);
+        let dependency_library = dependency_library(pkg.name(), target_name, &dependencies);
+        let mut units = dependencies;
+        units.push(own);
+
+        let sources = units
+            .iter()
+            .flat_map(|u| u.sources.iter().cloned())
+            .collect::<Vec<String>>();
+        if sources.is_empty() && tests.sources.is_empty() {
+            return Err(format!(
+                "no source files found for package `{}`",
+                pkg.name()
+            ));
+        }
+        let includes = units
+            .iter()
+            .flat_map(|u| u.includes.iter().cloned())
+            .collect::<Vec<String>>();
+        // Feature definitions are set on the sources of the package that
+        // declares the features and on the sources of the packages using it.
+        let definitions = units
+            .iter()
+            .chain([&tests])
+            .filter(|u| !u.definitions.is_empty() && !u.sources.is_empty())
+            .map(|u| {
+                format!(
+                    "set_property(SOURCE {} APPEND PROPERTY COMPILE_DEFINITIONS {})\n",
+                    u.sources
+                        .iter()
+                        .map(|s| cmake::quote(s))
+                        .collect::<Vec<String>>()
+                        .join(" "),
+                    u.definitions.join(" ")
+                )
+            })
+            .collect::<String>();
+        let compile_options = units
             .iter()
-            .map(|s| abs_path.join(s).to_string_lossy().to_string())
+            .flat_map(|u| u.compile_options.iter().cloned())
             .collect::<Vec<String>>();
-        let sources = [sources_ext, sources].concat();
-        let includes = pkg
-            .includes()
+        let link_libraries = units
             .iter()
-            .map(|s| abs_path.join(s).to_string_lossy().to_string())
+            .flat_map(|u| u.link_libraries.iter().cloned())
             .collect::<Vec<String>>();
-        let includes = [includes_ext, includes].concat();
-
-        if let Ok(tailor_cache) = std::fs::read(
-            abs_path
-                .join("build")
-                .join(self.mode.to_string())
-                .join("TailorCache"),
-        ) {
-            if tailor_cache == pkg.hash() {
-                return Ok(pkg);
+        let variables = units
+            .iter()
+            .flat_map(|u| u.variables.iter())
+            .map(|(name, value)| format!("-D{}={}", name, value))
+            .collect::<Vec<String>>();
+        // CMake projects are only built for the targets the package links.
+        let subdirectories = units
+            .iter()
+            .flat_map(|u| u.subdirectories.iter())
+            .map(|(path, name)| {
+                format!(
+                    "add_subdirectory({} {} EXCLUDE_FROM_ALL)\n",
+                    cmake::quote(path),
+                    cmake::quote(&format!("deps/{}", name))
+                )
+            })
+            .collect::<String>();
+
+        let cmake_content = content
+            .replace(
+                "$tests\n",
+                &tests
+                    .sources
+                    .iter()
+                    .map(|test| {
+                        let name = test_name(Path::new(test));
+                        format!(
+                            "add_executable({name} {} ${{src_files}})\n\
+                             target_include_directories({name} PRIVATE $include)\n{}",
+                            cmake::quote(test),
+                            link_options(&name, &compile_options, &link_libraries)
+                        )
+                    })
+                    .collect::<String>(),
+            )
+            .replace("$subdirectories\n", &subdirectories)
+            .replace(
+                "$link\n",
+                &link_options(target_name, &compile_options, &link_libraries),
+            )
+            .replace("$dependency_library\n", &dependency_library)
+            .replace("$package_sources", &package_sources)
+            .replace("$package_include", &package_include)
+            .replace("$pkg_name", pkg.name())
+            .replace("$c_standard", pkg.edition().c_standard())
+            .replace("$sources", &source_list(&sources))
+            .replace("$include", &quote_list(&includes))
+            .replace("$definitions\n", &definitions);
+        let mut cmake_args = target.as_ref().map_or(vec![], Target::cmake_args);
+        if let Some(sdk) = pkg.sdk() {
+            cmake_args.extend(sdk.cmake_args());
+        }
+        cmake_args.extend(variables);
+        let sdk_entry = pkg.sdk().map(|sdk| sdk.entry(abs_path)).transpose()?;
+        let cache_key = cache_key(
+            pkg,
+            target.as_ref(),
+            sdk_entry.as_deref(),
+            &cmake_args,
+            &cmake_content,
+        );
+
+        if let Ok(tailor_cache) = std::fs::read(build_dir.join("TailorCache")) {
+            if tailor_cache == cache_key {
+                return Ok(());
             } else {
                 println!(
                     "{} CMakeLists for package `{}` in {} mode",
                     info("Updating"),
                     pkg.name(),
-                    self.mode.to_string()
+                    self.mode
                 );
             }
         } else {
@@ -72,115 +508,317 @@ impl BuildPkg {
                 "{} CMakeLists.txt for package `{}` in {} mode",
                 success("Creating"),
                 pkg.name(),
-                self.mode.to_string()
+                self.mode
             );
         }
 
-        let cmake_content = content
-            .replace("$pkg_name", &pkg.name())
-            .replace("$sources", &sources.join(" "))
-            .replace("$include", &includes.join(" "));
-        std::fs::create_dir_all(abs_path.join("build").join(self.mode.to_string()))
+        std::fs::create_dir_all(&build_dir)
             .map_err(|e| format!("Failed to create build directory: {}", e))?;
-        std::fs::write(
-            abs_path
-                .join("build")
-                .join(self.mode.to_string())
-                .join("CMakeLists.txt"),
-            cmake_content,
-        )
-        .map_err(|e| format!("Failed to write CMakeLists.txt: {}", e))?;
+        std::fs::write(build_dir.join("CMakeLists.txt"), cmake_content)
+            .map_err(|e| format!("Failed to write CMakeLists.txt: {}", e))?;
 
         println!(
             "{} CMake for `{}` in {} mode",
             info("Generating"),
             pkg.name(),
-            self.mode.to_string()
+            self.mode
         );
 
-        cmake::gen_cmake(&self.mode, &self.path)?;
+        cmake::gen_cmake(&self.mode, &build_dir, &cmake_args)?;
 
-        std::fs::write(
-            abs_path
-                .join("build")
-                .join(self.mode.to_string())
-                .join("TailorCache"),
-            pkg.hash(),
-        )
-        .map_err(|e| format!("Failed to write TailorCache: {}", e))?;
+        std::fs::write(build_dir.join("TailorCache"), cache_key)
+            .map_err(|e| format!("Failed to write TailorCache: {}", e))?;
 
-        Ok(pkg)
+        Ok(())
     }
-}
 
-impl Command for BuildPkg {
-    fn parse_args(&mut self, args: &[String]) -> Option<()>
-    where
-        Self: Sized,
-    {
-        if args.is_empty() || args[0] != "build" {
-            return None;
-        }
+    fn resolve_features(&self, pkg: &Package) -> Result<ResolvedFeatures, String> {
+        resolve_features(
+            pkg,
+            &self.features.features,
+            self.features.all_features,
+            !self.features.no_default_features,
+        )
+    }
 
-        match args.len() {
-            1 => {
-                self.mode = Mode::Debug;
-                self.path = std::env::current_dir().ok()?;
+    /// Builds the `[build-dependencies]` of the package for the host, so the
+    /// tools are ready before the package is built. They are given to the
+    /// package as CMake variables: `<NAME>_BUILD_DIR`, the build folder of
+    /// each tool, and `<NAME>_EXECUTABLE` for binaries.
+    fn build_tools(
+        &self,
+        pkg: &Package,
+        abs_path: &Path,
+        workspace: Option<&Workspace>,
+        features: &ResolvedFeatures,
+        platform: &Platform,
+    ) -> Result<Vec<ResolvedDependency>, String> {
+        let mut tools = vec![];
+        for path in fetch_build_dependencies(pkg, abs_path, workspace, features, platform)? {
+            let host = BuildPkg {
+                path: path.clone(),
+                mode: self.mode,
+                target_dir: self.target_dir.clone(),
+                ..Default::default()
+            };
+            host.build_package(&path, Workspace::find(&path)?.as_ref())?;
 
-                Some(())
+            let tool = Package::from_file(&path.join("Tailor.toml"))?;
+            let build_dir = host.build_dir(&tool, &path)?;
+            let variable = cmake_variable(tool.name());
+            let mut variables = vec![(
+                format!("{}_BUILD_DIR", variable),
+                build_dir.to_string_lossy().to_string(),
+            )];
+            if matches!(tool.pkg_type(), PackageType::Binary) {
+                let executable = build_dir.join(format!("{}{}", tool.name(), EXE_SUFFIX));
+                variables.push((
+                    format!("{}_EXECUTABLE", variable),
+                    executable.to_string_lossy().to_string(),
+                ));
             }
-            2 => {
-                match args[1].as_str().try_into() {
-                    Ok(mode) => {
-                        self.mode = mode;
-                        self.path = std::env::current_dir().ok()?;
-                    }
-                    Err(_) => {
-                        self.mode = Mode::Debug;
-                        self.path = PathBuf::from(&args[1]);
-                    }
-                }
+            tools.push(ResolvedDependency {
+                variables,
+                ..Default::default()
+            });
+        }
 
-                Some(())
-            }
-            3 => {
-                let mode = match args[1].as_str().try_into() {
-                    Ok(mode) => mode,
-                    Err(_) => return None,
-                };
+        Ok(tools)
+    }
 
-                self.mode = mode;
-                self.path = PathBuf::from(&args[2]);
+    /// Builds the package at `abs_path`, which belongs to `workspace` if any.
+    pub fn build_package(
+        &self,
+        abs_path: &Path,
+        workspace: Option<&Workspace>,
+    ) -> Result<(), String> {
+        if !abs_path.join("Tailor.toml").exists() {
+            return Err("Tailor.toml file does not exist".to_string());
+        }
 
-                Some(())
-            }
-            _ => None,
+        let pkg = Package::from_file(&abs_path.join("Tailor.toml"))?;
+
+        let features = self.resolve_features(&pkg)?;
+        let platform = self.platform(&pkg, abs_path)?;
+        let tools = self.build_tools(&pkg, abs_path, workspace, &features, &platform)?;
+        let mut dependencies = resolve_dependencies(
+            &pkg,
+            abs_path,
+            workspace,
+            &features,
+            &platform,
+            &[DependencyKind::Normal],
+        )?;
+        dependencies.extend(tools);
+
+        let build_dir = self.build_dir(&pkg, abs_path)?;
+        let template = match (pkg.pkg_type(), pkg.sdk()) {
+            (PackageType::Sdk, Some(sdk)) => sdk.cmake_lists(abs_path, pkg.name())?,
+            (PackageType::Library, _) => lib::CMAKE_LISTS.to_string(),
+            _ => bin::CMAKE_LISTS.to_string(),
+        };
+        self.create_cmake_lists(abs_path, &pkg, template, dependencies, &features, &[])?;
+
+        match &self.target {
+            Some(target) => println!(
+                "{} `{}` for `{}` in {} mode",
+                success("Building"),
+                pkg.name(),
+                target,
+                self.mode
+            ),
+            None => println!(
+                "{} `{}` in {} mode",
+                success("Building"),
+                pkg.name(),
+                self.mode
+            ),
         }
+
+        cmake::build(&build_dir)
     }
 
-    fn execute(&self) -> Result<(), String> {
-        let mode_name = self.mode.to_string();
-        let pkg = Package::from_file(&self.path.join("Tailor.toml"))?;
+    /// Builds an executable for each `tests/*.c` file of the package, linked
+    /// with its sources, its dependencies and its dev-dependencies. Returns
+    /// the path of the test executables.
+    pub fn build_tests(
+        &self,
+        abs_path: &Path,
+        workspace: Option<&Workspace>,
+    ) -> Result<Vec<PathBuf>, String> {
+        let pkg = Package::from_file(&abs_path.join("Tailor.toml"))?;
+        let tests = expand_sources(abs_path, &["tests/*.c".to_string()])?;
+        if tests.is_empty() {
+            return Ok(vec![]);
+        }
 
-        let (sources_ext, include_ext) = resolve_dependencies(&pkg, &self.path)?;
+        let features = self.resolve_features(&pkg)?;
+        let platform = self.platform(&pkg, abs_path)?;
+        let tools = self.build_tools(&pkg, abs_path, workspace, &features, &platform)?;
+        let mut dependencies = resolve_dependencies(
+            &pkg,
+            abs_path,
+            workspace,
+            &features,
+            &platform,
+            &[DependencyKind::Normal, DependencyKind::Dev],
+        )?;
+        dependencies.extend(tools);
 
-        let pkg = match pkg.pkg_type() {
-            PackageType::Binary => {
-                self.create_cmake_lists(bin::CMAKE_LISTS, sources_ext, include_ext)?
-            }
-            PackageType::Library => {
-                self.create_cmake_lists(lib::CMAKE_LISTS, sources_ext, include_ext)?
-            }
-        };
+        self.create_cmake_lists(
+            abs_path,
+            &pkg,
+            test::CMAKE_LISTS.to_string(),
+            dependencies,
+            &features,
+            &tests,
+        )?;
 
         println!(
-            "{} `{}` in {} mode",
+            "{} `{}` tests in {} mode",
             success("Building"),
             pkg.name(),
-            mode_name
+            self.mode
         );
+        let test_dir = self.test_dir(&pkg, abs_path)?;
+        cmake::build(&test_dir)?;
 
-        cmake::build(&self.mode, &self.path)?;
+        Ok(tests
+            .iter()
+            .map(|test| test_dir.join(format!("{}{}", test_name(test), EXE_SUFFIX)))
+            .collect())
+    }
+}
+
+/// The sources of a CMake list, one per line.
+fn source_list(sources: &[String]) -> String {
+    sources
+        .iter()
+        .map(|s| format!("\n  {}", cmake::quote(s)))
+        .collect()
+}
+
+/// The quoted paths of a CMake command, such as the include folders.
+fn quote_list(paths: &[String]) -> String {
+    paths
+        .iter()
+        .map(|path| cmake::quote(path))
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+/// The static library `<pkg>_deps` compiling the Tailor dependencies of an
+/// `sdk` package, linked to the SDK target `target`.
+fn dependency_library(pkg_name: &str, target: &str, dependencies: &[ResolvedDependency]) -> String {
+    let sources = dependencies
+        .iter()
+        .flat_map(|d| d.sources.iter().cloned())
+        .collect::<Vec<String>>();
+    if sources.is_empty() {
+        return String::new();
+    }
+
+    let includes = dependencies
+        .iter()
+        .flat_map(|d| d.includes.iter().cloned())
+        .collect::<Vec<String>>();
+    format!(
+        "add_library({pkg_name}_deps STATIC{}\n)\n\
+         target_include_directories({pkg_name}_deps PUBLIC {})\n\
+         target_link_libraries({target} PRIVATE {pkg_name}_deps)\n",
+        source_list(&sources),
+        quote_list(&includes)
+    )
+}
+
+/// The compile options and the libraries of the system dependencies of the
+/// CMake target `name`.
+fn link_options(name: &str, compile_options: &[String], link_libraries: &[String]) -> String {
+    let mut options = String::new();
+    if !compile_options.is_empty() {
+        options += &format!(
+            "target_compile_options({} PRIVATE {})\n",
+            name,
+            compile_options.join(" ")
+        );
+    }
+    if !link_libraries.is_empty() {
+        options += &format!(
+            "target_link_libraries({} PRIVATE {})\n",
+            name,
+            link_libraries.join(" ")
+        );
+    }
+    options
+}
+
+/// The executable of a test: its file name without the extension.
+fn test_name(test: &Path) -> String {
+    test.file_stem()
+        .map_or(String::new(), |s| s.to_string_lossy().to_string())
+}
+
+/// The CMake variable named after a package, in upper case with any character
+/// not valid in a variable name replaced by `_`.
+fn cmake_variable(name: &str) -> String {
+    name.chars()
+        .map(|c| match c {
+            'a'..='z' | 'A'..='Z' | '0'..='9' => c.to_ascii_uppercase(),
+            _ => '_',
+        })
+        .collect()
+}
+
+/// The `TailorCache` content: the manifest hash plus the generated
+/// CMakeLists, which holds the expanded source list and the enabled features,
+/// so adding or removing a source file regenerates the build. The arguments
+/// of CMake, such as the `[sdk.parameters]` and the outputs of the
+/// build-dependencies, are hashed too, as is the SDK found in the environment.
+fn cache_key(
+    pkg: &Package,
+    target: Option<&Target>,
+    sdk_entry: Option<&Path>,
+    cmake_args: &[String],
+    cmake_content: &str,
+) -> Vec<u8> {
+    let mut hasher = Sha256::new();
+    hasher.update(pkg.hash());
+    if let Some(target) = target {
+        hasher.update(target.hash());
+    }
+    if let Some(sdk_entry) = sdk_entry {
+        hasher.update(sdk_entry.to_string_lossy().as_bytes());
+    }
+    for arg in cmake_args {
+        hasher.update(arg.as_bytes());
+    }
+    hasher.update(cmake_content.as_bytes());
+    hasher.finalize().to_vec()
+}
+
+impl Command for BuildPkg {
+    fn parse_args(&mut self, args: &[String]) -> Option<()>
+    where
+        Self: Sized,
+    {
+        if args.is_empty() || args[0] != "build" {
+            return None;
+        }
+
+        let mut i = 1;
+        while i < args.len() {
+            i += self.parse_arg(&args[i..])?;
+        }
+
+        self.finish_args()
+    }
+
+    fn execute(&self) -> Result<(), String> {
+        let (workspace, paths) = self.selected_packages()?;
+
+        for path in paths {
+            self.build_package(&path, workspace.as_ref())?;
+        }
 
         Ok(())
     }
@@ -189,10 +827,14 @@ impl Command for BuildPkg {
 mod bin {
     pub const CMAKE_LISTS: &str = "cmake_minimum_required(VERSION 3.10)
 project($pkg_name C)
-set(CMAKE_C_STANDARD 99)
-file(GLOB src_files $sources)
+set(CMAKE_C_STANDARD $c_standard)
+set(src_files$sources
+)
+$subdirectories
 add_executable($pkg_name ${src_files})
+$definitions
 target_include_directories($pkg_name PRIVATE $include)
+$link
 if (CMAKE_BUILD_TYPE STREQUAL \"Debug\")
   target_compile_definitions($pkg_name PRIVATE DEBUG)
 else()
@@ -204,10 +846,14 @@ endif()
 mod lib {
     pub const CMAKE_LISTS: &str = "cmake_minimum_required(VERSION 3.10)
 project($pkg_name C)
-set(CMAKE_C_STANDARD 99)
-file(GLOB src_files $sources)
+set(CMAKE_C_STANDARD $c_standard)
+set(src_files$sources
+)
+$subdirectories
 add_library($pkg_name STATIC ${src_files})
+$definitions
 target_include_directories($pkg_name PRIVATE $include)
+$link
 if (CMAKE_BUILD_TYPE STREQUAL \"Debug\")
   target_compile_definitions($pkg_name PRIVATE DEBUG)
 else()
@@ -215,3 +861,20 @@ else()
 endif()
 ";
 }
+
+mod test {
+    pub const CMAKE_LISTS: &str = "cmake_minimum_required(VERSION 3.10)
+project($pkg_name_tests C)
+set(CMAKE_C_STANDARD $c_standard)
+set(src_files$sources
+)
+$subdirectories
+if (CMAKE_BUILD_TYPE STREQUAL \"Debug\")
+  add_compile_definitions(DEBUG)
+else()
+  add_compile_definitions(RELEASE)
+endif()
+$tests
+$definitions
+";
+}
@@ -0,0 +1,298 @@
+use std::fmt::Display;
+
+use crate::target::Target;
+
+/// The platform a package is built for, used to evaluate conditions.
+#[derive(Debug, Clone)]
+pub struct Platform {
+    target: Option<String>,
+    os: String,
+    arch: String,
+    family: String,
+}
+
+impl Platform {
+    pub fn host() -> Self {
+        Self {
+            target: None,
+            os: std::env::consts::OS.to_string(),
+            arch: std::env::consts::ARCH.to_string(),
+            family: std::env::consts::FAMILY.to_string(),
+        }
+    }
+
+    /// The platform of a cross-compilation target. The OS is `none` unless
+    /// the target sets `os`, as most cross targets are bare metal.
+    pub fn from_target(target: &Target) -> Self {
+        let os = target.os().unwrap_or("none").to_string();
+        let family = target
+            .family()
+            .map(String::from)
+            .unwrap_or_else(|| match os.as_str() {
+                "linux" | "macos" | "ios" | "android" | "freebsd" | "netbsd" | "openbsd" => {
+                    "unix".to_string()
+                }
+                "windows" => "windows".to_string(),
+                _ => "none".to_string(),
+            });
+
+        Self {
+            target: Some(target.name().to_string()),
+            os,
+            arch: target.arch().unwrap_or("unknown").to_string(),
+            family,
+        }
+    }
+}
+
+/// The condition of a `[target.<condition>.dependencies]` table: either the
+/// name of a target or a `cfg(...)` expression.
+#[derive(Debug, Clone)]
+pub enum Condition {
+    Target(String),
+    Cfg(String, CfgExpr),
+}
+
+impl Condition {
+    pub fn parse(content: &str) -> Result<Self, String> {
+        match content
+            .strip_prefix("cfg(")
+            .and_then(|c| c.strip_suffix(')'))
+        {
+            Some(expr) => Ok(Self::Cfg(
+                content.to_string(),
+                CfgExpr::parse(expr).map_err(|e| format!("invalid `{}`: {}", content, e))?,
+            )),
+            None => Ok(Self::Target(content.to_string())),
+        }
+    }
+
+    pub fn matches(&self, platform: &Platform) -> bool {
+        match self {
+            Self::Target(name) => platform.target.as_deref() == Some(name),
+            Self::Cfg(_, expr) => expr.matches(platform),
+        }
+    }
+}
+
+impl Display for Condition {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Target(name) => write!(f, "{}", name),
+            Self::Cfg(content, _) => write!(f, "{}", content),
+        }
+    }
+}
+
+/// A `cfg` expression. A bare name, such as `linux` or `unix`, matches the OS
+/// or the family of the platform. `target_os`, `target_arch`, `target_family`
+/// and `target` (the target name) can be compared with `key = "value"`, and
+/// the expressions combined with `not(...)`, `all(...)` and `any(...)`.
+#[derive(Debug, Clone)]
+pub enum CfgExpr {
+    Name(String),
+    KeyValue(String, String),
+    Not(Box<CfgExpr>),
+    All(Vec<CfgExpr>),
+    Any(Vec<CfgExpr>),
+}
+
+impl CfgExpr {
+    pub fn parse(content: &str) -> Result<Self, String> {
+        let mut parser = CfgParser {
+            rest: content.trim(),
+        };
+        let expr = parser.expr()?;
+        if !parser.rest.is_empty() {
+            return Err(format!("unexpected `{}`", parser.rest));
+        }
+        Ok(expr)
+    }
+
+    pub fn matches(&self, platform: &Platform) -> bool {
+        match self {
+            Self::Name(name) => *name == platform.os || *name == platform.family,
+            Self::KeyValue(key, value) => match key.as_str() {
+                "target_os" => *value == platform.os,
+                "target_arch" => *value == platform.arch,
+                "target_family" => *value == platform.family,
+                "target" => platform.target.as_deref() == Some(value),
+                _ => false,
+            },
+            Self::Not(expr) => !expr.matches(platform),
+            Self::All(exprs) => exprs.iter().all(|e| e.matches(platform)),
+            Self::Any(exprs) => exprs.iter().any(|e| e.matches(platform)),
+        }
+    }
+}
+
+struct CfgParser<'a> {
+    rest: &'a str,
+}
+
+impl CfgParser<'_> {
+    fn eat(&mut self, token: char) -> bool {
+        match self.rest.strip_prefix(token) {
+            Some(rest) => {
+                self.rest = rest.trim_start();
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn ident(&mut self) -> Result<String, String> {
+        let end = self
+            .rest
+            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_' || c == '-'))
+            .unwrap_or(self.rest.len());
+        if end == 0 {
+            return Err(format!("expected a name at `{}`", self.rest));
+        }
+
+        let ident = self.rest[..end].to_string();
+        self.rest = self.rest[end..].trim_start();
+        Ok(ident)
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        if !self.eat('"') {
+            return Err(format!("expected a string at `{}`", self.rest));
+        }
+        let end = self.rest.find('"').ok_or("unterminated string")?;
+        let value = self.rest[..end].to_string();
+        self.rest = self.rest[end + 1..].trim_start();
+        Ok(value)
+    }
+
+    fn list(&mut self) -> Result<Vec<CfgExpr>, String> {
+        if !self.eat('(') {
+            return Err(format!("expected `(` at `{}`", self.rest));
+        }
+
+        let mut exprs = vec![];
+        while !self.eat(')') {
+            exprs.push(self.expr()?);
+            if !self.eat(',') && !self.rest.starts_with(')') {
+                return Err(format!("expected `,` or `)` at `{}`", self.rest));
+            }
+        }
+        Ok(exprs)
+    }
+
+    fn expr(&mut self) -> Result<CfgExpr, String> {
+        let ident = self.ident()?;
+
+        match ident.as_str() {
+            "all" => Ok(CfgExpr::All(self.list()?)),
+            "any" => Ok(CfgExpr::Any(self.list()?)),
+            "not" => {
+                let mut exprs = self.list()?;
+                if exprs.len() != 1 {
+                    return Err("`not` takes exactly one expression".to_string());
+                }
+                Ok(CfgExpr::Not(Box::new(exprs.remove(0))))
+            }
+            _ if self.eat('=') => Ok(CfgExpr::KeyValue(ident, self.string()?)),
+            _ => Ok(CfgExpr::Name(ident)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The platform of a target declared with the keys `keys`.
+    fn platform(name: &str, keys: &str) -> Platform {
+        let content = keys.parse::<toml::Value>().unwrap();
+        Platform::from_target(&Target::from_content(name, &content).unwrap())
+    }
+
+    fn matches(condition: &str, platform: &Platform) -> bool {
+        Condition::parse(condition).unwrap().matches(platform)
+    }
+
+    #[test]
+    fn names_match_the_os_or_the_family() {
+        let linux = platform("pi", "os = \"linux\"\narch = \"aarch64\"");
+        assert!(matches("cfg(linux)", &linux));
+        assert!(matches("cfg(unix)", &linux));
+        assert!(!matches("cfg(windows)", &linux));
+        assert!(matches("cfg(target_arch = \"aarch64\")", &linux));
+        assert!(matches("cfg(target_family = \"unix\")", &linux));
+        assert!(!matches("cfg(target_os = \"unix\")", &linux));
+
+        let bare = platform("cortex-m4", "arch = \"arm\"");
+        assert!(matches("cfg(none)", &bare));
+        assert!(!matches("cfg(unix)", &bare));
+        assert!(matches("cfg(target = \"cortex-m4\")", &bare));
+        assert!(!matches("cfg(target_vendor = \"arm\")", &bare));
+
+        let host = Platform::host();
+        assert!(matches(
+            &format!("cfg(target_os = \"{}\")", std::env::consts::OS),
+            &host
+        ));
+        assert!(!matches("cfg(target = \"cortex-m4\")", &host));
+    }
+
+    #[test]
+    fn expressions_combine_conditions() {
+        let bare = platform("cortex-m4", "arch = \"arm\"");
+        assert!(matches(
+            "cfg(all(target_os = \"none\", target_arch = \"arm\"))",
+            &bare
+        ));
+        assert!(!matches("cfg(all(none, unix))", &bare));
+        assert!(matches(
+            "cfg(any(unix, not(target_arch = \"x86\"),))",
+            &bare
+        ));
+        assert!(!matches("cfg(not(any(none, unix)))", &bare));
+        assert!(matches("cfg(all())", &bare));
+        assert!(!matches("cfg(any())", &bare));
+    }
+
+    #[test]
+    fn target_names_match_the_selected_target() {
+        let bare = platform("cortex-m4", "");
+        assert!(matches("cortex-m4", &bare));
+        assert!(!matches("riscv", &bare));
+        assert!(!matches("cortex-m4", &Platform::host()));
+        assert_eq!(
+            Condition::parse("cfg( unix )").unwrap().to_string(),
+            "cfg( unix )"
+        );
+    }
+
+    #[test]
+    fn invalid_expressions() {
+        for (condition, error) in [
+            ("cfg()", "invalid `cfg()`: expected a name at ``"),
+            (
+                "cfg(unix windows)",
+                "invalid `cfg(unix windows)`: unexpected `windows`",
+            ),
+            (
+                "cfg(all(unix windows))",
+                "invalid `cfg(all(unix windows))`: expected `,` or `)` at `windows)`",
+            ),
+            (
+                "cfg(not(unix, linux))",
+                "invalid `cfg(not(unix, linux))`: `not` takes exactly one expression",
+            ),
+            (
+                "cfg(target_os = linux)",
+                "invalid `cfg(target_os = linux)`: expected a string at `linux`",
+            ),
+            (
+                "cfg(target_os = \"linux)",
+                "invalid `cfg(target_os = \"linux)`: unterminated string",
+            ),
+            ("cfg(any)", "invalid `cfg(any)`: expected `(` at ``"),
+        ] {
+            assert_eq!(Condition::parse(condition).err().as_deref(), Some(error));
+        }
+    }
+}
@@ -0,0 +1,64 @@
+use crate::{build_pkg::BuildPkg, command::Command, fmt::success, mode::Mode, package::Package};
+
+#[derive(Default)]
+pub struct CleanPkg {
+    build: BuildPkg,
+    mode_only: bool,
+}
+
+impl Command for CleanPkg {
+    fn parse_args(&mut self, args: &[String]) -> Option<()> {
+        if args.is_empty() || args[0] != "clean" {
+            return None;
+        }
+
+        let mut i = 1;
+        while i < args.len() {
+            if Mode::try_from(args[i].as_str()).is_ok() {
+                self.mode_only = true;
+            }
+            i += self.build.parse_arg(&args[i..])?;
+        }
+
+        self.build.finish_args()
+    }
+
+    fn execute(&self) -> Result<(), String> {
+        let (_, paths) = self.build.selected_packages()?;
+
+        for path in paths {
+            let pkg = Package::from_file(&path.join("Tailor.toml"))?;
+            if pkg.is_virtual() {
+                continue;
+            }
+
+            // Without `--debug`/`--release` all modes are removed, and without
+            // `--target` all targets as well.
+            let dir = if self.mode_only {
+                self.build.build_dir(&pkg, &path)?
+            } else {
+                let output = self.build.output_dir(&pkg, &path)?;
+                match self.build.target_name() {
+                    Some(target) => output.join(target),
+                    None => output,
+                }
+            };
+
+            if !dir.exists() {
+                continue;
+            }
+
+            std::fs::remove_dir_all(&dir)
+                .map_err(|e| format!("fail to remove {}: {}", dir.display(), e))?;
+
+            println!(
+                "{} `{}` build files at {}",
+                success("Removed"),
+                pkg.name(),
+                dir.display()
+            );
+        }
+
+        Ok(())
+    }
+}
@@ -2,23 +2,49 @@ use std::{path::Path, process::Command};
 
 use crate::mode::Mode;
 
-pub fn gen_cmake(mode: &Mode, path: &Path) -> Result<(), String> {
-    Command::new("cmake")
+/// `value` as a quoted CMake argument, with `\`, `"` and `$` escaped, so
+/// paths are written as they are.
+pub fn quote(value: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in value.chars() {
+        if matches!(c, '\\' | '"' | '$') {
+            quoted.push('\\');
+        }
+        quoted.push(c);
+    }
+    quoted.push('"');
+    quoted
+}
+
+pub fn gen_cmake(mode: &Mode, build_dir: &Path, args: &[String]) -> Result<(), String> {
+    let status = Command::new("cmake")
         .arg("-S")
-        .arg(path.join("build").join(mode.to_string()))
+        .arg(build_dir)
         .arg("-B")
-        .arg(path.join("build").join(mode.to_string()))
-        .arg(format!("-DCMAKE_BUILD_TYPE={}", mode.to_string()))
+        .arg(build_dir)
+        .arg(format!("-DCMAKE_BUILD_TYPE={}", mode))
+        .args(args)
         .status()
-        .map(|_| ())
-        .map_err(|e| format!("fail to build: {}", e))
+        .map_err(|e| format!("fail to build: {}", e))?;
+
+    if !status.success() {
+        return Err(format!(
+            "fail to generate the build files in `{}`",
+            build_dir.display()
+        ));
+    }
+    Ok(())
 }
 
-pub fn build(mode: &Mode, path: &Path) -> Result<(), String> {
-    Command::new("cmake")
+pub fn build(build_dir: &Path) -> Result<(), String> {
+    let status = Command::new("cmake")
         .arg("--build")
-        .arg(path.join("build").join(mode.to_string()))
+        .arg(build_dir)
         .status()
-        .map(|_| ())
-        .map_err(|e| format!("fail to run: {}", e))
+        .map_err(|e| format!("fail to run: {}", e))?;
+
+    if !status.success() {
+        return Err(format!("fail to build `{}`", build_dir.display()));
+    }
+    Ok(())
 }
@@ -3,3 +3,20 @@ pub trait Command {
 
     fn execute(&self) -> Result<(), String>;
 }
+
+/// Parses a flag with a value, given either as `--flag value` or as
+/// `--flag=value`. Returns the value and how many arguments were consumed.
+pub fn parse_flag_value<'a>(args: &'a [String], names: &[&str]) -> Option<(&'a str, usize)> {
+    let arg = args.first()?;
+
+    for name in names {
+        if arg == name {
+            return Some((args.get(1)?, 2));
+        }
+        if let Some(value) = arg.strip_prefix(name).and_then(|v| v.strip_prefix('=')) {
+            return Some((value, 1));
+        }
+    }
+
+    None
+}
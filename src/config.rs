@@ -0,0 +1,388 @@
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use dirs::config_dir;
+use toml::Table;
+
+use crate::{
+    mode::Mode,
+    profile::{Profile, parse_profiles},
+    target::Target,
+};
+
+/// A registry named in the `[registries]` table of the config.
+#[derive(Debug, Clone)]
+pub struct RegistryConfig {
+    url: String,
+    credential_helper: Option<String>,
+}
+
+impl RegistryConfig {
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    pub fn credential_helper(&self) -> Option<&str> {
+        self.credential_helper.as_deref()
+    }
+
+    fn from_content(name: &str, content: &toml::Value) -> Result<Self, String> {
+        let table = content
+            .as_table()
+            .ok_or_else(|| format!("registries.{} must be a table", name))?;
+
+        Ok(Self {
+            url: table
+                .get("url")
+                .and_then(|v| v.as_str())
+                .map(String::from)
+                .ok_or_else(|| format!("registries.{}.url must be a string", name))?,
+            credential_helper: table
+                .get("credential-helper")
+                .map(|v| {
+                    v.as_str().map(String::from).ok_or_else(|| {
+                        format!("registries.{}.credential-helper must be a string", name)
+                    })
+                })
+                .transpose()?,
+        })
+    }
+}
+
+/// A source of packages named in the `[source]` table of the config: a
+/// registry, the Git repositories under a URL prefix or a local directory,
+/// which may be replaced by another source.
+#[derive(Debug, Clone)]
+pub struct SourceConfig {
+    registry: Option<String>,
+    git: Option<String>,
+    directory: Option<PathBuf>,
+    replace_with: Option<String>,
+}
+
+impl SourceConfig {
+    pub fn registry(&self) -> Option<&str> {
+        self.registry.as_deref()
+    }
+
+    /// The URL prefix of the Git repositories of the source.
+    pub fn git(&self) -> Option<&str> {
+        self.git.as_deref()
+    }
+
+    pub fn directory(&self) -> Option<&Path> {
+        self.directory.as_deref()
+    }
+
+    /// The name of the source fetched instead of this one.
+    pub fn replace_with(&self) -> Option<&str> {
+        self.replace_with.as_deref()
+    }
+
+    fn from_content(name: &str, content: &toml::Value, dir: &Path) -> Result<Self, String> {
+        let table = content
+            .as_table()
+            .ok_or_else(|| format!("source.{} must be a table", name))?;
+        let field = |key: &str| {
+            table
+                .get(key)
+                .map(|v| {
+                    v.as_str()
+                        .map(String::from)
+                        .ok_or_else(|| format!("source.{}.{} must be a string", name, key))
+                })
+                .transpose()
+        };
+
+        let source = Self {
+            registry: field("registry")?,
+            git: field("git")?,
+            directory: field("directory")?.map(|directory| dir.join(directory)),
+            replace_with: field("replace-with")?,
+        };
+        let kinds = [
+            source.registry.is_some(),
+            source.git.is_some(),
+            source.directory.is_some(),
+        ];
+        if kinds.iter().filter(|kind| **kind).count() > 1 {
+            return Err(format!(
+                "source.{} must have only one of `registry`, `git` and `directory`",
+                name
+            ));
+        }
+
+        Ok(source)
+    }
+}
+
+/// The `[http]` table of the config, used by all the network operations.
+#[derive(Debug, Clone)]
+pub struct HttpConfig {
+    proxy: Option<String>,
+    no_proxy: Vec<String>,
+    cainfo: Vec<PathBuf>,
+    connect_timeout: Duration,
+    timeout: Duration,
+    retries: u32,
+}
+
+impl Default for HttpConfig {
+    fn default() -> Self {
+        Self {
+            proxy: None,
+            no_proxy: vec![],
+            cainfo: vec![],
+            connect_timeout: Duration::from_secs(30),
+            timeout: Duration::from_secs(30),
+            retries: 3,
+        }
+    }
+}
+
+impl HttpConfig {
+    /// The proxy of the HTTP and HTTPS requests. The `http_proxy` and
+    /// `https_proxy` environment variables are used when it's not set.
+    pub fn proxy(&self) -> Option<&str> {
+        self.proxy.as_deref()
+    }
+
+    /// The hosts reached without the proxy.
+    pub fn no_proxy(&self) -> &[String] {
+        &self.no_proxy
+    }
+
+    /// The PEM bundles of the certificate authorities trusted besides the
+    /// ones of the system.
+    pub fn cainfo(&self) -> &[PathBuf] {
+        &self.cainfo
+    }
+
+    pub fn connect_timeout(&self) -> Duration {
+        self.connect_timeout
+    }
+
+    /// The time to wait for each read or write of a request.
+    pub fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    /// How many times a request failing with a network error, or with a
+    /// server error, is sent again.
+    pub fn retries(&self) -> u32 {
+        self.retries
+    }
+
+    fn from_content(content: &toml::Value, dir: &Path) -> Result<Self, String> {
+        let table = content.as_table().ok_or("'http' must be a table")?;
+        let default = Self::default();
+
+        let string_list = |key: &str| {
+            table
+                .get(key)
+                .map(|v| {
+                    v.as_array()
+                        .and_then(|arr| {
+                            arr.iter()
+                                .map(|v| v.as_str().map(String::from))
+                                .collect::<Option<Vec<String>>>()
+                        })
+                        .ok_or(format!("http.{} must be a list of strings", key))
+                })
+                .transpose()
+                .map(Option::unwrap_or_default)
+        };
+        let seconds = |key: &str, default: Duration| {
+            table
+                .get(key)
+                .map(|v| {
+                    v.as_integer()
+                        .and_then(|secs| u64::try_from(secs).ok())
+                        .map(Duration::from_secs)
+                        .ok_or(format!("http.{} must be a number of seconds", key))
+                })
+                .transpose()
+                .map(|timeout| timeout.unwrap_or(default))
+        };
+
+        Ok(Self {
+            proxy: table
+                .get("proxy")
+                .map(|v| {
+                    v.as_str()
+                        .map(String::from)
+                        .ok_or("http.proxy must be a string")
+                })
+                .transpose()?,
+            no_proxy: string_list("no-proxy")?,
+            cainfo: string_list("cainfo")?
+                .iter()
+                .map(|path| dir.join(path))
+                .collect(),
+            connect_timeout: seconds("connect-timeout", default.connect_timeout)?,
+            timeout: seconds("timeout", default.timeout)?,
+            retries: table
+                .get("retries")
+                .map(|v| {
+                    v.as_integer()
+                        .and_then(|retries| u32::try_from(retries).ok())
+                        .ok_or("http.retries must be a positive number")
+                })
+                .transpose()?
+                .unwrap_or(default.retries),
+        })
+    }
+}
+
+/// Global Tailor configuration, read from `<config dir>/tailor/config.toml`.
+#[derive(Debug, Default)]
+pub struct Config {
+    targets: Vec<Target>,
+    profiles: Vec<Profile>,
+    debugger: Option<String>,
+    target_dir: Option<PathBuf>,
+    registry: Option<String>,
+    registries: BTreeMap<String, RegistryConfig>,
+    credential_helper: Option<String>,
+    http: HttpConfig,
+    sources: BTreeMap<String, SourceConfig>,
+}
+
+impl Config {
+    pub fn dir() -> PathBuf {
+        config_dir()
+            .expect("fail to get config directory")
+            .join("tailor")
+    }
+
+    pub fn target(&self, name: &str) -> Option<&Target> {
+        self.targets.iter().find(|t| t.name() == name)
+    }
+
+    pub fn profile(&self, mode: &Mode) -> Option<&Profile> {
+        self.profiles.iter().find(|p| p.name() == mode.to_string())
+    }
+
+    pub fn debugger(&self) -> Option<&str> {
+        self.debugger.as_deref()
+    }
+
+    pub fn target_dir(&self) -> Option<&Path> {
+        self.target_dir.as_deref()
+    }
+
+    pub fn registry(&self) -> Option<&str> {
+        self.registry.as_deref()
+    }
+
+    pub fn named_registry(&self, name: &str) -> Option<&RegistryConfig> {
+        self.registries.get(name)
+    }
+
+    /// The program storing the registry tokens, instead of
+    /// `credentials.toml`.
+    pub fn credential_helper(&self) -> Option<&str> {
+        self.credential_helper.as_deref()
+    }
+
+    pub fn http(&self) -> &HttpConfig {
+        &self.http
+    }
+
+    /// The sources of the `[source]` table, by name.
+    pub fn sources(&self) -> &BTreeMap<String, SourceConfig> {
+        &self.sources
+    }
+
+    /// Loads the global config. A missing file is the same as an empty one.
+    pub fn load() -> Result<Self, String> {
+        let filepath = Self::dir().join("config.toml");
+        if !filepath.exists() {
+            return Ok(Self::default());
+        }
+
+        let content = std::fs::read_to_string(&filepath)
+            .map_err(|e| format!("fail to read file {}: {}", filepath.display(), e))?;
+
+        Self::from_content(&content)
+            .map_err(|e| format!("invalid config {}: {}", filepath.display(), e))
+    }
+
+    pub fn from_content(content: &str) -> Result<Self, String> {
+        let parsed = content
+            .parse::<Table>()
+            .map_err(|_| "Invalid Toml format".to_string())?;
+
+        let dir = Self::dir();
+        let targets = match parsed.get("target").and_then(|v| v.as_table()) {
+            Some(targets) => targets
+                .iter()
+                .map(|(k, v)| Ok(Target::from_content(k, v)?.relative_to(&dir)))
+                .collect::<Result<Vec<Target>, String>>()?,
+            None => vec![],
+        };
+
+        Ok(Self {
+            targets,
+            profiles: parse_profiles(parsed.get("profile"))?,
+            debugger: parsed
+                .get("debugger")
+                .map(|v| {
+                    v.as_str()
+                        .map(String::from)
+                        .ok_or("'debugger' must be a string")
+                })
+                .transpose()?,
+            target_dir: parsed
+                .get("target-dir")
+                .map(|v| {
+                    v.as_str()
+                        .map(|target_dir| dir.join(target_dir))
+                        .ok_or("'target-dir' must be a string")
+                })
+                .transpose()?,
+            registry: parsed
+                .get("registry")
+                .map(|v| {
+                    v.as_str()
+                        .map(String::from)
+                        .ok_or("'registry' must be a string")
+                })
+                .transpose()?,
+            registries: match parsed.get("registries") {
+                Some(registries) => registries
+                    .as_table()
+                    .ok_or("'registries' must be a table")?
+                    .iter()
+                    .map(|(name, v)| Ok((name.clone(), RegistryConfig::from_content(name, v)?)))
+                    .collect::<Result<BTreeMap<String, RegistryConfig>, String>>()?,
+                None => BTreeMap::new(),
+            },
+            credential_helper: parsed
+                .get("credential-helper")
+                .map(|v| {
+                    v.as_str()
+                        .map(String::from)
+                        .ok_or("'credential-helper' must be a string")
+                })
+                .transpose()?,
+            http: parsed
+                .get("http")
+                .map(|http| HttpConfig::from_content(http, &dir))
+                .transpose()?
+                .unwrap_or_default(),
+            sources: match parsed.get("source") {
+                Some(sources) => sources
+                    .as_table()
+                    .ok_or("'source' must be a table")?
+                    .iter()
+                    .map(|(name, v)| Ok((name.clone(), SourceConfig::from_content(name, v, &dir)?)))
+                    .collect::<Result<BTreeMap<String, SourceConfig>, String>>()?,
+                None => BTreeMap::new(),
+            },
+        })
+    }
+}
@@ -0,0 +1,168 @@
+use std::path::{Path, PathBuf};
+
+use tailor_rs::kconfig::{Kconfig, Values, parse_config};
+use toml::Table;
+
+use crate::{
+    build_pkg::BuildPkg,
+    command::{Command, parse_flag_value},
+    config_schema::config_header,
+    fmt::success,
+    package::Package,
+};
+
+#[derive(Default)]
+pub struct ConfigPkg {
+    build: BuildPkg,
+    defconfigs: Vec<PathBuf>,
+    export_kconfig: Option<PathBuf>,
+}
+
+/// Parses the Kconfig of the package and resolves the values of its symbols
+/// from the fragments of the manifest followed by `.config`. When
+/// `defconfigs` are given, they replace `.config`. Returns `None` when the
+/// package has no Kconfig file.
+pub fn resolve_config(
+    pkg: &Package,
+    abs_path: &Path,
+    defconfigs: &[PathBuf],
+) -> Result<Option<(Kconfig, Values)>, String> {
+    let kconfig_path = abs_path.join(pkg.kconfig());
+    if !kconfig_path.exists() {
+        return Ok(None);
+    }
+    let kconfig = Kconfig::from_file(&kconfig_path)?;
+
+    let mut fragments = pkg
+        .kconfig_fragments()
+        .iter()
+        .map(|f| abs_path.join(f))
+        .collect::<Vec<PathBuf>>();
+    let dot_config = abs_path.join(".config");
+    if !defconfigs.is_empty() {
+        fragments.extend(defconfigs.iter().cloned());
+    } else if dot_config.exists() {
+        fragments.push(dot_config);
+    }
+
+    let mut user = Values::new();
+    for fragment in fragments {
+        let content = std::fs::read_to_string(&fragment)
+            .map_err(|e| format!("fail to read file {}: {}", fragment.display(), e))?;
+        user.extend(parse_config(&content).map_err(|e| format!("{}: {}", fragment.display(), e))?);
+    }
+
+    let values = kconfig.resolve(&user);
+    Ok(Some((kconfig, values)))
+}
+
+/// Writes a generated header, such as `autoconf.h`, into
+/// `<build_dir>/generated`, and returns that folder. The header is only
+/// rewritten when it changes, so the sources including it aren't rebuilt for
+/// nothing.
+pub fn write_header(build_dir: &Path, file_name: &str, header: &str) -> Result<PathBuf, String> {
+    let dir = build_dir.join("generated");
+    let path = dir.join(file_name);
+
+    if std::fs::read_to_string(&path).is_ok_and(|old| old == header) {
+        return Ok(dir);
+    }
+
+    std::fs::create_dir_all(&dir)
+        .map_err(|e| format!("fail to create directory {}: {}", dir.display(), e))?;
+    std::fs::write(&path, header)
+        .map_err(|e| format!("fail to write {}: {}", path.display(), e))?;
+
+    Ok(dir)
+}
+
+impl Command for ConfigPkg {
+    fn parse_args(&mut self, args: &[String]) -> Option<()> {
+        if args.is_empty() || args[0] != "config" {
+            return None;
+        }
+
+        let mut i = 1;
+        while i < args.len() {
+            if let Some((defconfig, consumed)) = parse_flag_value(&args[i..], &["--defconfig"]) {
+                self.defconfigs
+                    .push(std::env::current_dir().ok()?.join(defconfig));
+                i += consumed;
+                continue;
+            }
+            if let Some((file, consumed)) = parse_flag_value(&args[i..], &["--export-kconfig"]) {
+                self.export_kconfig = Some(std::env::current_dir().ok()?.join(file));
+                i += consumed;
+                continue;
+            }
+            i += self.build.parse_arg(&args[i..])?;
+        }
+
+        self.build.finish_args()
+    }
+
+    fn execute(&self) -> Result<(), String> {
+        let (_, paths) = self.build.selected_packages()?;
+
+        let mut configured = 0;
+        let mut exported = String::new();
+        for path in paths {
+            let pkg = Package::from_file(&path.join("Tailor.toml"))?;
+            if pkg.is_virtual() {
+                continue;
+            }
+
+            if self.export_kconfig.is_some() {
+                if !pkg.config().is_empty() {
+                    exported += &pkg.config().kconfig(pkg.name());
+                    configured += 1;
+                }
+                continue;
+            }
+
+            let build_dir = self.build.build_dir(&pkg, &path)?;
+            if let Some((kconfig, values)) = resolve_config(&pkg, &path, &self.defconfigs)? {
+                let dot_config = path.join(".config");
+                std::fs::write(&dot_config, kconfig.config_file(&values))
+                    .map_err(|e| format!("fail to write {}: {}", dot_config.display(), e))?;
+                write_header(&build_dir, "autoconf.h", &kconfig.header(&values))?;
+
+                println!(
+                    "{} `{}` ({} options enabled)",
+                    success("Configured"),
+                    pkg.name(),
+                    values.values().filter(|v| *v != "n").count()
+                );
+                configured += 1;
+            }
+
+            if let Some((file_name, header)) = config_header(&pkg, &Table::new())? {
+                write_header(&build_dir, &file_name, &header)?;
+                println!(
+                    "{} {} for `{}`",
+                    success("Generated"),
+                    file_name,
+                    pkg.name()
+                );
+                configured += 1;
+            }
+        }
+
+        if let Some(file) = &self.export_kconfig {
+            if configured == 0 {
+                return Err("no [config] table found in the selected packages".to_string());
+            }
+            std::fs::write(file, exported)
+                .map_err(|e| format!("fail to write {}: {}", file.display(), e))?;
+            println!("{} Kconfig to {}", success("Exported"), file.display());
+            return Ok(());
+        }
+
+        if configured == 0 {
+            return Err(
+                "no Kconfig file nor [config] table found in the selected packages".to_string(),
+            );
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,626 @@
+use std::collections::BTreeMap;
+
+use toml::{Table, Value};
+
+use crate::package::Package;
+
+/// The type of a `[config]` option.
+#[derive(Debug, Clone, PartialEq)]
+pub enum OptionType {
+    Bool,
+    Int,
+    String,
+    /// One of the listed values.
+    Enum(Vec<String>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum OptionValue {
+    Bool(bool),
+    Int(i64),
+    /// The value of `string` and `enum` options.
+    String(String),
+}
+
+/// A dependency of an option on a `bool` option, negated by a leading `!`.
+#[derive(Debug, Clone)]
+struct OptionDependency {
+    name: String,
+    negated: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct ConfigOption {
+    name: String,
+    option_type: OptionType,
+    default: OptionValue,
+    range: Option<(i64, i64)>,
+    depends: Vec<OptionDependency>,
+    prompt: Option<String>,
+    help: String,
+}
+
+impl ConfigOption {
+    /// Checks a value given to the option, by its default or by a package.
+    fn validate(&self, pkg_name: &str, value: &Value) -> Result<OptionValue, String> {
+        let invalid = |expected: &str| {
+            format!(
+                "config option `{}` of `{}` must be {}",
+                self.name, pkg_name, expected
+            )
+        };
+
+        match &self.option_type {
+            OptionType::Bool => value
+                .as_bool()
+                .map(OptionValue::Bool)
+                .ok_or_else(|| invalid("a boolean")),
+            OptionType::Int => {
+                let value = value.as_integer().ok_or_else(|| invalid("an integer"))?;
+                match self.range {
+                    Some((low, high)) if value < low || value > high => {
+                        Err(invalid(&format!("between {} and {}", low, high)))
+                    }
+                    _ => Ok(OptionValue::Int(value)),
+                }
+            }
+            OptionType::String => value
+                .as_str()
+                .map(|v| OptionValue::String(v.to_string()))
+                .ok_or_else(|| invalid("a string")),
+            OptionType::Enum(values) => match value.as_str() {
+                Some(value) if values.iter().any(|v| v == value) => {
+                    Ok(OptionValue::String(value.to_string()))
+                }
+                _ => Err(invalid(&format!("one of: {}", values.join(", ")))),
+            },
+        }
+    }
+
+    fn from_content(pkg_name: &str, name: &str, content: &Value) -> Result<Self, String> {
+        let table = content
+            .as_table()
+            .ok_or_else(|| format!("config option `{}` must be a table", name))?;
+        let string = |key: &str| -> Result<Option<String>, String> {
+            table
+                .get(key)
+                .map(|v| {
+                    v.as_str().map(String::from).ok_or_else(|| {
+                        format!("{} of config option `{}` must be a string", key, name)
+                    })
+                })
+                .transpose()
+        };
+
+        let option_type = match string("type")?.as_deref() {
+            Some("bool") => OptionType::Bool,
+            Some("int") => OptionType::Int,
+            Some("string") => OptionType::String,
+            Some("enum") => OptionType::Enum(
+                table
+                    .get("values")
+                    .and_then(Value::as_array)
+                    .and_then(|arr| {
+                        arr.iter()
+                            .map(|v| v.as_str().map(String::from))
+                            .collect::<Option<Vec<String>>>()
+                    })
+                    .filter(|values| !values.is_empty())
+                    .ok_or_else(|| {
+                        format!(
+                            "values of enum config option `{}` must be a list of strings",
+                            name
+                        )
+                    })?,
+            ),
+            _ => {
+                return Err(format!(
+                    "type of config option `{}` must be \"bool\", \"int\", \"string\" or \"enum\"",
+                    name
+                ));
+            }
+        };
+
+        let range = match table.get("range") {
+            Some(_) if option_type != OptionType::Int => {
+                return Err(format!(
+                    "config option `{}` has a range, but it's not an int",
+                    name
+                ));
+            }
+            Some(range) => match range.as_array().map(Vec::as_slice) {
+                Some([low, high]) => match (low.as_integer(), high.as_integer()) {
+                    (Some(low), Some(high)) if low <= high => Some((low, high)),
+                    _ => None,
+                },
+                _ => None,
+            }
+            .map(Some)
+            .ok_or_else(|| format!("range of config option `{}` must be [<min>, <max>]", name))?,
+            None => None,
+        };
+
+        let depends = match table.get("depends") {
+            None => vec![],
+            Some(Value::String(name)) => vec![name.clone()],
+            Some(Value::Array(names)) => names
+                .iter()
+                .map(|v| v.as_str().map(String::from))
+                .collect::<Option<Vec<String>>>()
+                .ok_or_else(|| {
+                    format!(
+                        "depends of config option `{}` must be a list of options",
+                        name
+                    )
+                })?,
+            Some(_) => {
+                return Err(format!(
+                    "depends of config option `{}` must be a list of options",
+                    name
+                ));
+            }
+        }
+        .iter()
+        .map(|dep| OptionDependency {
+            name: dep.trim_start_matches('!').to_string(),
+            negated: dep.starts_with('!'),
+        })
+        .collect();
+
+        let mut option = Self {
+            name: name.to_string(),
+            default: match &option_type {
+                OptionType::Bool => OptionValue::Bool(false),
+                OptionType::Int => OptionValue::Int(range.map_or(0, |(low, _)| low)),
+                OptionType::String => OptionValue::String(String::new()),
+                OptionType::Enum(values) => OptionValue::String(values[0].clone()),
+            },
+            option_type,
+            range,
+            depends,
+            prompt: string("prompt")?,
+            help: string("help")?.unwrap_or_default(),
+        };
+        if let Some(default) = table.get("default") {
+            option.default = option.validate(pkg_name, default)?;
+        }
+
+        Ok(option)
+    }
+}
+
+/// The `[config]` table of a package: typed options compiled into the
+/// `<name>_config.h` header, whose values can be set by the packages that
+/// depend on it.
+#[derive(Debug, Clone, Default)]
+pub struct ConfigSchema {
+    options: Vec<ConfigOption>,
+}
+
+/// The name of a package or an option in a C macro or a Kconfig symbol.
+fn identifier(name: &str) -> String {
+    name.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_uppercase()
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+impl ConfigSchema {
+    pub fn is_empty(&self) -> bool {
+        self.options.is_empty()
+    }
+
+    /// The value of each option whose dependencies are met: the one set in
+    /// `values`, or its default.
+    pub fn resolve(
+        &self,
+        pkg_name: &str,
+        values: &Table,
+    ) -> Result<BTreeMap<String, OptionValue>, String> {
+        let mut user = BTreeMap::new();
+        for (name, value) in values {
+            let option = self
+                .options
+                .iter()
+                .find(|o| &o.name == name)
+                .ok_or_else(|| format!("`{}` has no config option `{}`", pkg_name, name))?;
+            user.insert(name.as_str(), option.validate(pkg_name, value)?);
+        }
+
+        // Options may depend on options declared after them, so the values
+        // are computed again until nothing changes.
+        let mut resolved = BTreeMap::new();
+        for _ in 0..=self.options.len() {
+            let mut next = BTreeMap::new();
+            for option in &self.options {
+                let active = option.depends.iter().all(|dep| {
+                    (resolved.get(&dep.name) == Some(&OptionValue::Bool(true))) != dep.negated
+                });
+                if active {
+                    let value = user.get(option.name.as_str()).unwrap_or(&option.default);
+                    next.insert(option.name.clone(), value.clone());
+                }
+            }
+            if next == resolved {
+                break;
+            }
+            resolved = next;
+        }
+
+        if let Some(name) = user.keys().find(|name| !resolved.contains_key(**name)) {
+            return Err(format!(
+                "config option `{}` of `{}` is set, but its dependencies aren't met",
+                name, pkg_name
+            ));
+        }
+
+        Ok(resolved)
+    }
+
+    /// The `<name>_config.h` header defining the resolved values, with the
+    /// macro names that the Kconfig from [`ConfigSchema::kconfig`] gives.
+    pub fn header(&self, pkg_name: &str, values: &BTreeMap<String, OptionValue>) -> String {
+        let guard = format!("{}_CONFIG_H", identifier(pkg_name));
+        let mut content = format!(
+            "/* Automatically generated by Tailor from the [config] of `{}`, do not edit */\n\
+             #ifndef {guard}\n\
+             #define {guard}\n\n",
+            pkg_name
+        );
+
+        for option in &self.options {
+            let name = format!(
+                "CONFIG_{}_{}",
+                identifier(pkg_name),
+                identifier(&option.name)
+            );
+            content += &match (&option.option_type, values.get(&option.name)) {
+                (OptionType::Bool, Some(OptionValue::Bool(true))) => {
+                    format!("#define {} 1\n", name)
+                }
+                (OptionType::Int, Some(OptionValue::Int(value))) => {
+                    format!("#define {} {}\n", name, value)
+                }
+                (OptionType::String, Some(OptionValue::String(value))) => {
+                    format!("#define {} \"{}\"\n", name, escape(value))
+                }
+                (OptionType::Enum(_), Some(OptionValue::String(value))) => {
+                    format!("#define {}_{} 1\n", name, identifier(value))
+                }
+                _ => continue,
+            };
+        }
+
+        content + "\n#endif\n"
+    }
+
+    /// The options as a Kconfig menu, for projects configured with Kconfig.
+    /// `enum` options become a `choice` of `bool` symbols.
+    pub fn kconfig(&self, pkg_name: &str) -> String {
+        let symbol = |name: &str| format!("{}_{}", identifier(pkg_name), identifier(name));
+
+        let mut content = format!(
+            "# Generated by Tailor from the [config] of `{}`\n\nmenu \"{}\"\n",
+            pkg_name,
+            escape(pkg_name)
+        );
+
+        for option in &self.options {
+            let prompt = escape(option.prompt.as_deref().unwrap_or(&option.name));
+            let depends = option
+                .depends
+                .iter()
+                .map(|dep| {
+                    format!(
+                        "{}{}",
+                        if dep.negated { "!" } else { "" },
+                        symbol(&dep.name)
+                    )
+                })
+                .collect::<Vec<String>>()
+                .join(" && ");
+            let depends = if depends.is_empty() {
+                String::new()
+            } else {
+                format!("\tdepends on {}\n", depends)
+            };
+            let help = if option.help.is_empty() {
+                String::new()
+            } else {
+                format!(
+                    "\thelp\n{}",
+                    option
+                        .help
+                        .lines()
+                        .map(|line| format!("\t  {}\n", line))
+                        .collect::<String>()
+                )
+            };
+
+            content += "\n";
+            content += &match (&option.option_type, &option.default) {
+                (OptionType::Enum(values), OptionValue::String(default)) => {
+                    let mut choice = format!(
+                        "choice {}\n\tprompt \"{}\"\n\tdefault {}_{}\n{}{}",
+                        symbol(&option.name),
+                        prompt,
+                        symbol(&option.name),
+                        identifier(default),
+                        depends,
+                        help
+                    );
+                    for value in values {
+                        choice += &format!(
+                            "\nconfig {}_{}\n\tbool \"{}\"\n",
+                            symbol(&option.name),
+                            identifier(value),
+                            escape(value)
+                        );
+                    }
+                    choice + "\nendchoice\n"
+                }
+                (option_type, default) => {
+                    let (kind, default) = match (option_type, default) {
+                        (_, OptionValue::Bool(value)) => {
+                            ("bool", if *value { "y" } else { "n" }.to_string())
+                        }
+                        (_, OptionValue::Int(value)) => ("int", value.to_string()),
+                        (_, OptionValue::String(value)) => {
+                            ("string", format!("\"{}\"", escape(value)))
+                        }
+                    };
+                    let range = option.range.map_or(String::new(), |(low, high)| {
+                        format!("\trange {} {}\n", low, high)
+                    });
+                    format!(
+                        "config {}\n\t{} \"{}\"\n{}\tdefault {}\n{}{}",
+                        symbol(&option.name),
+                        kind,
+                        prompt,
+                        range,
+                        default,
+                        depends,
+                        help
+                    )
+                }
+            };
+        }
+
+        content + "\nendmenu\n"
+    }
+
+    pub fn from_content(pkg_name: &str, content: Option<&Value>) -> Result<Self, String> {
+        let Some(content) = content else {
+            return Ok(Self::default());
+        };
+        let table = content.as_table().ok_or("config must be a table")?;
+
+        let options = table
+            .iter()
+            .map(|(name, content)| ConfigOption::from_content(pkg_name, name, content))
+            .collect::<Result<Vec<ConfigOption>, String>>()?;
+
+        for option in &options {
+            for dep in &option.depends {
+                if !options
+                    .iter()
+                    .any(|o| o.name == dep.name && o.option_type == OptionType::Bool)
+                {
+                    return Err(format!(
+                        "config option `{}` depends on `{}`, which isn't a bool option",
+                        option.name, dep.name
+                    ));
+                }
+            }
+        }
+
+        Ok(Self { options })
+    }
+}
+
+/// The `<name>_config.h` header of `pkg`, with its file name, resolved with
+/// the `values` set by the package depending on it. Returns `None` when the
+/// package has no `[config]` table.
+pub fn config_header(pkg: &Package, values: &Table) -> Result<Option<(String, String)>, String> {
+    let schema = pkg.config();
+    if schema.is_empty() {
+        if let Some(name) = values.keys().next() {
+            return Err(format!("`{}` has no config option `{}`", pkg.name(), name));
+        }
+        return Ok(None);
+    }
+
+    let resolved = schema.resolve(pkg.name(), values)?;
+    Ok(Some((
+        format!("{}_config.h", pkg.name()),
+        schema.header(pkg.name(), &resolved),
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use tailor_rs::kconfig::{Kconfig, Values};
+
+    use super::*;
+
+    const SCHEMA: &str = r#"
+[log]
+type = "bool"
+default = true
+prompt = "Log messages"
+help = "Enables the log messages"
+
+[log_level]
+type = "int"
+default = 2
+range = [0, 4]
+depends = ["log"]
+
+[quiet]
+type = "bool"
+depends = ["!log"]
+
+[name]
+type = "string"
+default = "say \"hi\""
+
+[transport]
+type = "enum"
+values = ["tcp", "udp"]
+default = "udp"
+"#;
+
+    fn schema(content: &str) -> Result<ConfigSchema, String> {
+        ConfigSchema::from_content("my-lib", Some(&content.parse::<Value>().unwrap()))
+    }
+
+    fn resolve(values: &str) -> Result<BTreeMap<String, OptionValue>, String> {
+        schema(SCHEMA)
+            .unwrap()
+            .resolve("my-lib", &values.parse::<Table>().unwrap())
+    }
+
+    #[test]
+    fn invalid_schemas() {
+        for (content, error) in [
+            (
+                "[a]\ntype = \"float\"",
+                "type of config option `a` must be \"bool\", \"int\", \"string\" or \"enum\"",
+            ),
+            (
+                "[a]\ntype = \"enum\"\nvalues = []",
+                "values of enum config option `a` must be a list of strings",
+            ),
+            (
+                "[a]\ntype = \"string\"\nrange = [0, 1]",
+                "config option `a` has a range, but it's not an int",
+            ),
+            (
+                "[a]\ntype = \"int\"\nrange = [4, 1]",
+                "range of config option `a` must be [<min>, <max>]",
+            ),
+            (
+                "[a]\ntype = \"int\"\nrange = [0, 4]\ndefault = 5",
+                "config option `a` of `my-lib` must be between 0 and 4",
+            ),
+            (
+                "[a]\ntype = \"enum\"\nvalues = [\"x\"]\ndefault = \"y\"",
+                "config option `a` of `my-lib` must be one of: x",
+            ),
+            (
+                "[a]\ntype = \"bool\"\ndepends = [\"b\"]\n[b]\ntype = \"int\"",
+                "config option `a` depends on `b`, which isn't a bool option",
+            ),
+            (
+                "[a]\ntype = \"bool\"\ndepends = 1",
+                "depends of config option `a` must be a list of options",
+            ),
+        ] {
+            assert_eq!(schema(content).err().as_deref(), Some(error));
+        }
+    }
+
+    #[test]
+    fn values_are_validated_and_follow_the_dependencies() {
+        let defaults = resolve("").unwrap();
+        assert_eq!(
+            defaults,
+            BTreeMap::from([
+                ("log".to_string(), OptionValue::Bool(true)),
+                ("log_level".to_string(), OptionValue::Int(2)),
+                (
+                    "name".to_string(),
+                    OptionValue::String("say \"hi\"".to_string())
+                ),
+                (
+                    "transport".to_string(),
+                    OptionValue::String("udp".to_string())
+                ),
+            ])
+        );
+
+        let values = resolve("log = false\nquiet = true\ntransport = \"tcp\"").unwrap();
+        assert_eq!(values.get("log_level"), None);
+        assert_eq!(values.get("quiet"), Some(&OptionValue::Bool(true)));
+        assert_eq!(
+            values.get("transport"),
+            Some(&OptionValue::String("tcp".to_string()))
+        );
+
+        for (values, error) in [
+            (
+                "log_level = 9",
+                "config option `log_level` of `my-lib` must be between 0 and 4",
+            ),
+            (
+                "log = \"yes\"",
+                "config option `log` of `my-lib` must be a boolean",
+            ),
+            (
+                "name = 3",
+                "config option `name` of `my-lib` must be a string",
+            ),
+            (
+                "transport = \"usb\"",
+                "config option `transport` of `my-lib` must be one of: tcp, udp",
+            ),
+            ("color = true", "`my-lib` has no config option `color`"),
+            (
+                "log = false\nlog_level = 3",
+                "config option `log_level` of `my-lib` is set, but its dependencies aren't met",
+            ),
+        ] {
+            assert_eq!(resolve(values).err().as_deref(), Some(error));
+        }
+    }
+
+    #[test]
+    fn header_defines_the_enabled_options() {
+        let schema = schema(SCHEMA).unwrap();
+        let values = resolve("log_level = 4").unwrap();
+        assert_eq!(
+            schema.header("my-lib", &values),
+            "/* Automatically generated by Tailor from the [config] of `my-lib`, do not edit */\n\
+             #ifndef MY_LIB_CONFIG_H\n#define MY_LIB_CONFIG_H\n\n\
+             #define CONFIG_MY_LIB_LOG 1\n#define CONFIG_MY_LIB_LOG_LEVEL 4\n\
+             #define CONFIG_MY_LIB_NAME \"say \\\"hi\\\"\"\n\
+             #define CONFIG_MY_LIB_TRANSPORT_UDP 1\n\n#endif\n"
+        );
+    }
+
+    /// The macros defined by `header`.
+    fn macros(header: &str) -> Vec<&str> {
+        header
+            .lines()
+            .filter(|line| line.starts_with("#define CONFIG_"))
+            .collect()
+    }
+
+    #[test]
+    fn exported_kconfig_gives_the_same_macros() {
+        let schema = schema(SCHEMA).unwrap();
+        let exported = schema.kconfig("my-lib");
+        assert!(exported.contains(
+            "config MY_LIB_LOG\n\tbool \"Log messages\"\n\tdefault y\n\thelp\n\t  Enables the log messages\n"
+        ));
+        assert!(exported.contains(
+            "config MY_LIB_LOG_LEVEL\n\tint \"log_level\"\n\trange 0 4\n\tdefault 2\n\tdepends on MY_LIB_LOG\n"
+        ));
+
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("Kconfig"), &exported).unwrap();
+        let kconfig = Kconfig::from_file(&dir.path().join("Kconfig")).unwrap();
+        let kconfig_header = kconfig.header(&kconfig.resolve(&Values::new()));
+        let header = schema.header("my-lib", &resolve("").unwrap());
+        assert_eq!(macros(&kconfig_header), macros(&header));
+    }
+}
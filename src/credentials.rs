@@ -0,0 +1,163 @@
+use std::{
+    io::Write,
+    path::PathBuf,
+    process::{Command, Stdio},
+};
+
+use toml::{Table, Value};
+
+use crate::config::Config;
+
+/// The tokens of the registries and of the Git hosts, stored in
+/// `<config dir>/tailor/credentials.toml`, readable only by its owner:
+///
+/// ```toml
+/// [registries."https://registry.example.com"]
+/// token = "..."
+///
+/// [git."git.example.com"]
+/// token = "..."
+/// ```
+///
+/// Registries are keyed by their URL, so a token is never sent to another
+/// registry. There's no `Debug`, so the tokens can't end up in a log.
+#[derive(Default)]
+pub struct Credentials {
+    table: Table,
+}
+
+impl Credentials {
+    fn path() -> PathBuf {
+        Config::dir().join("credentials.toml")
+    }
+
+    /// Loads the credentials. A missing file has no credentials.
+    pub fn load() -> Result<Self, String> {
+        let path = Self::path();
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let content = std::fs::read_to_string(&path)
+            .map_err(|e| format!("fail to read file {}: {}", path.display(), e))?;
+        let table = content
+            .parse::<Table>()
+            .map_err(|_| format!("invalid credentials {}", path.display()))?;
+
+        Ok(Self { table })
+    }
+
+    fn token(&self, section: &str, key: &str) -> Option<&str> {
+        self.table.get(section)?.get(key)?.get("token")?.as_str()
+    }
+
+    fn set_token(&mut self, section: &str, key: &str, token: &str) {
+        let section = self
+            .table
+            .entry(section)
+            .or_insert_with(|| Value::Table(Table::new()));
+        if let Value::Table(section) = section {
+            let mut entry = Table::new();
+            entry.insert("token".to_string(), Value::String(token.to_string()));
+            section.insert(key.to_string(), Value::Table(entry));
+        }
+    }
+
+    pub fn registry_token(&self, url: &str) -> Option<&str> {
+        self.token("registries", url)
+    }
+
+    pub fn set_registry_token(&mut self, url: &str, token: &str) {
+        self.set_token("registries", url, token);
+    }
+
+    pub fn git_token(&self, host: &str) -> Option<&str> {
+        self.token("git", host)
+    }
+
+    pub fn set_git_token(&mut self, host: &str, token: &str) {
+        self.set_token("git", host, token);
+    }
+
+    /// Writes the credentials, with `0600` permissions on Unix.
+    pub fn save(&self) -> Result<(), String> {
+        let path = Self::path();
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)
+                .map_err(|e| format!("fail to create directory {}: {}", dir.display(), e))?;
+        }
+
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+
+            options.mode(0o600);
+            // `mode` only applies to new files.
+            if path.exists() {
+                std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600))
+                    .map_err(|e| format!("fail to protect {}: {}", path.display(), e))?;
+            }
+        }
+
+        options
+            .open(&path)
+            .and_then(|mut file| file.write_all(self.table.to_string().as_bytes()))
+            .map_err(|e| format!("fail to write {}: {}", path.display(), e))
+    }
+}
+
+/// Runs the credential helper `helper` as `<helper> <action> <url>`, giving
+/// it `input` through stdin, and returns its output, or `None` when it fails.
+/// Helpers implement `get`, printing the token of the URL, and `store`,
+/// reading it from stdin.
+fn run_helper(
+    helper: &str,
+    action: &str,
+    url: &str,
+    input: &str,
+) -> Result<Option<String>, String> {
+    let mut parts = helper.split_whitespace();
+    let program = parts.next().ok_or("the credential helper is empty")?;
+
+    let mut child = Command::new(program)
+        .args(parts)
+        .arg(action)
+        .arg(url)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::inherit())
+        .spawn()
+        .map_err(|e| format!("fail to run credential helper `{}`: {}", program, e))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin
+            .write_all(input.as_bytes())
+            .map_err(|e| format!("fail to run credential helper `{}`: {}", program, e))?;
+    }
+
+    let output = child
+        .wait_with_output()
+        .map_err(|e| format!("fail to run credential helper `{}`: {}", program, e))?;
+    Ok(output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).trim().to_string()))
+}
+
+/// The token of `url` given by the credential helper, if it has one.
+pub fn helper_token(helper: &str, url: &str) -> Result<Option<String>, String> {
+    Ok(run_helper(helper, "get", url, "")?.filter(|token| !token.is_empty()))
+}
+
+/// Gives the token of `url` to the credential helper to store it.
+pub fn helper_store(helper: &str, url: &str, token: &str) -> Result<(), String> {
+    run_helper(helper, "store", url, &format!("{}\n", token))?
+        .map(|_| ())
+        .ok_or_else(|| {
+            format!(
+                "credential helper `{}` failed to store the token of {}",
+                helper, url
+            )
+        })
+}
@@ -0,0 +1,101 @@
+use std::{path::Path, process};
+
+use crate::{
+    build_pkg::BuildPkg, command::Command, config::Config, dependency_manager::dependency_paths,
+    fmt::success,
+};
+
+#[derive(Default)]
+pub struct DebugPkg {
+    build: BuildPkg,
+    debugger: Option<String>,
+    args: Vec<String>,
+}
+
+impl DebugPkg {
+    /// Creates the debugger process for `binary`. gdb receives each source
+    /// directory through `--directory`. lldb reads the absolute source paths
+    /// recorded in the debug info, so it only needs the program arguments.
+    fn debugger_command(&self, debugger: &str, binary: &Path, dirs: &[&Path]) -> process::Command {
+        let mut command = process::Command::new(debugger);
+        let is_lldb = Path::new(debugger)
+            .file_name()
+            .is_some_and(|name| name.to_string_lossy().starts_with("lldb"));
+
+        if is_lldb {
+            command.arg("--").arg(binary).args(&self.args);
+        } else {
+            for dir in dirs {
+                command.arg(format!("--directory={}", dir.display()));
+            }
+            command.arg("--args").arg(binary).args(&self.args);
+        }
+
+        command
+    }
+}
+
+impl Command for DebugPkg {
+    fn parse_args(&mut self, args: &[String]) -> Option<()> {
+        if args.is_empty() || args[0] != "debug" {
+            return None;
+        }
+
+        let mut i = 1;
+        while i < args.len() {
+            match args[i].as_str() {
+                "--" => {
+                    self.args = args[i + 1..].to_vec();
+                    break;
+                }
+                "--release" => return None,
+                "--debugger" => {
+                    self.debugger = Some(args.get(i + 1)?.clone());
+                    i += 2;
+                }
+                arg => match arg.strip_prefix("--debugger=") {
+                    Some(debugger) => {
+                        self.debugger = Some(debugger.to_string());
+                        i += 1;
+                    }
+                    None => i += self.build.parse_arg(&args[i..])?,
+                },
+            }
+        }
+
+        self.build.finish_args()
+    }
+
+    fn execute(&self) -> Result<(), String> {
+        let (workspace, abs_path, pkg) = self.build.selected_binary("debug")?;
+
+        self.build.build_package(&abs_path, workspace.as_ref())?;
+
+        let config = Config::load()?;
+        let debugger = self
+            .debugger
+            .as_deref()
+            .or(config.debugger())
+            .unwrap_or("gdb");
+
+        println!(
+            "{} `{}` with `{}`",
+            success("Debugging"),
+            pkg.name(),
+            debugger
+        );
+
+        let dep_paths = dependency_paths(&pkg, &abs_path, workspace.as_ref());
+        let dirs = [abs_path.as_path()]
+            .into_iter()
+            .chain(dep_paths.iter().map(|p| p.as_path()))
+            .collect::<Vec<&Path>>();
+        let binary = self.build.binary_path(&pkg, &abs_path)?;
+
+        self.debugger_command(debugger, &binary, &dirs)
+            .status()
+            .map_err(|e| format!("fail to execute debugger `{}`: {}", debugger, e))?;
+
+        Ok(())
+    }
+}
@@ -1,117 +1,373 @@
 use sha2::{Digest, Sha256};
-use std::path::PathBuf;
-use toml::Value;
+use std::path::{Path, PathBuf};
+use toml::{Table, Value};
 
-#[derive(Debug)]
-pub enum Dependency {
+use crate::{cfg::Condition, version::VersionReq};
+
+/// Where a dependency is fetched from.
+#[derive(Debug, Clone)]
+pub enum Source {
     Registry {
-        name: String,
         version: String,
     },
     Git {
-        name: String,
         url: String,
         revision: String,
     },
     Local {
-        #[allow(unused)]
-        name: String,
         path: PathBuf,
     },
+    /// A library installed in the system, found with pkg-config.
+    System {
+        module: String,
+        version: Option<String>,
+    },
+}
+
+/// The table a dependency is declared in: `[dependencies]` are compiled into
+/// the package, `[dev-dependencies]` only into its tests, and
+/// `[build-dependencies]` are tools built for the host before the package.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DependencyKind {
+    #[default]
+    Normal,
+    Dev,
+    Build,
+}
+
+impl DependencyKind {
+    /// The manifest table holding dependencies of this kind.
+    pub fn table(&self) -> &'static str {
+        match self {
+            Self::Normal => "dependencies",
+            Self::Dev => "dev-dependencies",
+            Self::Build => "build-dependencies",
+        }
+    }
+}
+
+/// How a dependency is compiled into the package: as a Tailor package, with
+/// its sources added to the package, or as a plain CMake project, added with
+/// `add_subdirectory` and linked through its CMake targets.
+#[derive(Debug, Clone, Default)]
+pub enum BuildSystem {
+    #[default]
+    Tailor,
+    CMake {
+        targets: Vec<String>,
+    },
+}
+
+#[derive(Debug, Clone)]
+pub struct Dependency {
+    name: String,
+    source: Source,
+    features: Vec<String>,
+    default_features: bool,
+    optional: bool,
+    condition: Option<Condition>,
+    kind: DependencyKind,
+    build: BuildSystem,
+    manifest: Option<PathBuf>,
+    config: Table,
 }
 
 impl Dependency {
+    pub fn new(name: &str, source: Source) -> Self {
+        Self {
+            name: name.to_string(),
+            source,
+            features: vec![],
+            default_features: true,
+            optional: false,
+            condition: None,
+            kind: DependencyKind::Normal,
+            build: BuildSystem::Tailor,
+            manifest: None,
+            config: Table::new(),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn source(&self) -> &Source {
+        &self.source
+    }
+
+    /// Returns a copy of the dependency fetched from `source` instead.
+    pub fn with_source(&self, source: Source) -> Self {
+        Self {
+            source,
+            ..self.clone()
+        }
+    }
+
+    /// Features of the dependency enabled by the `features` key.
+    pub fn features(&self) -> &[String] {
+        &self.features
+    }
+
+    pub fn default_features(&self) -> bool {
+        self.default_features
+    }
+
+    /// Optional dependencies are only used when enabled by a feature.
+    pub fn optional(&self) -> bool {
+        self.optional
+    }
+
+    /// Dependencies declared in `[target.<condition>.dependencies]` are only
+    /// used when the condition matches the platform being built for.
+    pub fn condition(&self) -> Option<&Condition> {
+        self.condition.as_ref()
+    }
+
+    /// Returns a copy of the dependency only used under `condition`.
+    pub fn with_condition(&self, condition: Condition) -> Self {
+        Self {
+            condition: Some(condition),
+            ..self.clone()
+        }
+    }
+
+    pub fn kind(&self) -> DependencyKind {
+        self.kind
+    }
+
+    /// Returns a copy of the dependency declared in the table of `kind`.
+    pub fn with_kind(&self, kind: DependencyKind) -> Self {
+        Self {
+            kind,
+            ..self.clone()
+        }
+    }
+
+    pub fn build(&self) -> &BuildSystem {
+        &self.build
+    }
+
+    /// The overlay manifest given by the `manifest` key, used in place of the
+    /// `Tailor.toml` of projects that don't have one.
+    pub fn manifest(&self) -> Option<&Path> {
+        self.manifest.as_deref()
+    }
+
+    /// Returns a copy of the dependency using the overlay manifest at `path`.
+    pub fn with_manifest(&self, path: PathBuf) -> Self {
+        Self {
+            manifest: Some(path),
+            ..self.clone()
+        }
+    }
+
+    /// The values of the `[config]` options of the dependency set by the
+    /// package, with the `config` key.
+    pub fn config(&self) -> &Table {
+        &self.config
+    }
+
     pub fn hash(&self) -> Vec<u8> {
         let mut hasher = Sha256::new();
-        match self {
-            Self::Registry { name, version } => {
-                hasher.update(name.as_bytes());
+        hasher.update(self.name.as_bytes());
+        match &self.source {
+            Source::Registry { version } => {
                 hasher.update(version.as_bytes());
             }
-            Self::Git {
-                name,
-                url,
-                revision,
-            } => {
-                hasher.update(name.as_bytes());
+            Source::Git { url, revision } => {
                 hasher.update(url.as_bytes());
                 hasher.update(revision.as_bytes());
             }
-            Self::Local { name, path } => {
-                hasher.update(name.as_bytes());
+            Source::Local { path } => {
                 hasher.update(path.to_string_lossy().as_bytes());
             }
+            Source::System { module, version } => {
+                hasher.update(module.as_bytes());
+                if let Some(version) = version {
+                    hasher.update(version.as_bytes());
+                }
+            }
         }
+        for feature in &self.features {
+            hasher.update(feature.as_bytes());
+        }
+        hasher.update([
+            self.default_features as u8,
+            self.optional as u8,
+            self.kind as u8,
+        ]);
+        if let Some(condition) = &self.condition {
+            hasher.update(condition.to_string().as_bytes());
+        }
+        if let BuildSystem::CMake { targets } = &self.build {
+            for target in targets {
+                hasher.update(target.as_bytes());
+            }
+        }
+        if let Some(manifest) = &self.manifest {
+            hasher.update(manifest.to_string_lossy().as_bytes());
+        }
+        hasher.update(self.config.to_string().as_bytes());
         hasher.finalize().to_vec()
     }
 
     pub fn from_content(name: &str, content: &Value) -> Result<Self, String> {
-        [
-            Self::parse_registry_string_dependency,
-            Self::parse_registry_dependency,
-            Self::parse_git_dependency,
-            Self::parse_local_dependency,
-        ]
-        .iter()
-        .filter_map(|parser| parser(name, content).ok())
-        .next()
-        .ok_or(name.to_string())
-    }
-
-    fn parse_registry_string_dependency(name: &str, content: &Value) -> Result<Self, String> {
-        content
-            .as_str()
-            .map(|version| Self::Registry {
-                name: name.to_string(),
-                version: version.to_string(),
-            })
-            .ok_or_else(|| format!("invalid registry dependency format for '{}'", name))
-    }
-
-    fn parse_registry_dependency(name: &str, content: &Value) -> Result<Self, String> {
-        content
-            .as_table()
-            .and_then(|table| {
-                Some(Self::Registry {
-                    name: name.to_string(),
-                    version: table
-                        .get("version")
-                        .and_then(Value::as_str)
-                        .map(String::from)?,
-                })
-            })
-            .ok_or_else(|| format!("invalid registry dependency format for '{}'", name))
-    }
-
-    fn parse_git_dependency(name: &str, content: &Value) -> Result<Self, String> {
-        content
-            .as_table()
-            .and_then(|table| {
-                Some(Self::Git {
-                    name: name.to_string(),
-                    url: table.get("url").and_then(Value::as_str).map(String::from)?,
-                    revision: table
-                        .get("revision")
-                        .and_then(Value::as_str)
+        let source = match content {
+            Value::String(version) => Source::Registry {
+                version: version.clone(),
+            },
+            Value::Table(table) => Self::parse_source(name, table)?,
+            _ => {
+                return Err(format!(
+                    "dependency '{}' must be a version or a table",
+                    name
+                ));
+            }
+        };
+
+        if let Source::Registry { version } = &source {
+            VersionReq::parse(version)
+                .map_err(|e| format!("dependency '{}' has an {}", name, e))?;
+        }
+
+        let mut dependency = Self::new(name, source);
+        let Some(table) = content.as_table() else {
+            return Ok(dependency);
+        };
+
+        if let Some(features) = table.get("features") {
+            dependency.features = string_list(features)
+                .ok_or_else(|| format!("features of '{}' must be a list of strings", name))?;
+        }
+        if let Some(default_features) = table.get("default-features") {
+            dependency.default_features = default_features
+                .as_bool()
+                .ok_or_else(|| format!("default-features of '{}' must be a boolean", name))?;
+        }
+        if let Some(optional) = table.get("optional") {
+            dependency.optional = optional
+                .as_bool()
+                .ok_or_else(|| format!("optional of '{}' must be a boolean", name))?;
+        }
+        match table.get("build").map(Value::as_str) {
+            None | Some(Some("tailor")) => {}
+            Some(Some("cmake")) => {
+                dependency.build = BuildSystem::CMake {
+                    targets: match table.get("targets") {
+                        Some(targets) => string_list(targets).ok_or_else(|| {
+                            format!("targets of '{}' must be a list of strings", name)
+                        })?,
+                        None => vec![name.to_string()],
+                    },
+                };
+            }
+            Some(_) => {
+                return Err(format!(
+                    "build of '{}' must be \"tailor\" or \"cmake\"",
+                    name
+                ));
+            }
+        }
+        if let Some(manifest) = table.get("manifest") {
+            dependency.manifest = Some(
+                manifest
+                    .as_str()
+                    .map(PathBuf::from)
+                    .ok_or_else(|| format!("manifest of '{}' must be a path", name))?,
+            );
+        }
+
+        if let Some(config) = table.get("config") {
+            dependency.config = config
+                .as_table()
+                .cloned()
+                .ok_or_else(|| format!("config of '{}' must be a table", name))?;
+        }
+
+        Ok(dependency)
+    }
+
+    /// Where the dependency declared by `table` is fetched from, set by one of
+    /// the `pkg-config`, `git` (or `url`), `path` and `version` keys, checked
+    /// in this order. `version` is also the minimum version of a `pkg-config`
+    /// library, but it can't be mixed with the other sources.
+    fn parse_source(name: &str, table: &Table) -> Result<Source, String> {
+        let string = |key: &str| {
+            table
+                .get(key)
+                .map(|v| {
+                    v.as_str()
                         .map(String::from)
-                        .unwrap_or_else(|| "main".to_string()),
-                })
-            })
-            .ok_or_else(|| format!("invalid git dependency format for '{}'", name))
-    }
-
-    fn parse_local_dependency(name: &str, content: &Value) -> Result<Self, String> {
-        content
-            .as_table()
-            .and_then(|table| {
-                Some(Self::Local {
-                    name: name.to_string(),
-                    path: table
-                        .get("path")
-                        .and_then(Value::as_str)
-                        .map(PathBuf::from)?,
+                        .ok_or_else(|| format!("{} of '{}' must be a string", key, name))
                 })
-            })
-            .ok_or_else(|| format!("invalid local dependency format for '{}'", name))
+                .transpose()
+        };
+        let (module, git, url, path, version, revision) = (
+            string("pkg-config")?,
+            string("git")?,
+            string("url")?,
+            string("path")?,
+            string("version")?,
+            string("revision")?,
+        );
+
+        let mut sources = [
+            ("pkg-config", module.is_some()),
+            ("git", git.is_some()),
+            ("url", url.is_some()),
+            ("path", path.is_some()),
+            ("version", version.is_some() && module.is_none()),
+        ]
+        .iter()
+        .filter(|(_, set)| *set)
+        .map(|(key, _)| format!("`{}`", key))
+        .collect::<Vec<String>>();
+        if sources.len() > 1 {
+            let last = sources.pop().unwrap_or_default();
+            return Err(format!(
+                "dependency '{}' has more than one source: {} and {}",
+                name,
+                sources.join(", "),
+                last
+            ));
+        }
+        if revision.is_some() && git.is_none() && url.is_none() {
+            return Err(format!(
+                "revision of '{}' is only for `git` dependencies",
+                name
+            ));
+        }
+
+        if let Some(module) = module {
+            return Ok(Source::System { module, version });
+        }
+        if let Some(url) = git.or(url) {
+            return Ok(Source::Git {
+                url,
+                revision: revision.unwrap_or_else(|| "main".to_string()),
+            });
+        }
+        if let Some(path) = path {
+            return Ok(Source::Local {
+                path: PathBuf::from(path),
+            });
+        }
+        if let Some(version) = version {
+            return Ok(Source::Registry { version });
+        }
+
+        Err(format!(
+            "dependency '{}' must have a `version`, `git`, `path` or `pkg-config` key",
+            name
+        ))
     }
 }
+
+fn string_list(content: &Value) -> Option<Vec<String>> {
+    content.as_array().and_then(|arr| {
+        arr.iter()
+            .map(|v| v.as_str().map(String::from))
+            .collect::<Option<Vec<String>>>()
+    })
+}
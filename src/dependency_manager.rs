@@ -1,143 +1,568 @@
-use std::path::{Path, PathBuf};
-
-use dirs::config_dir;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::{Path, PathBuf},
+};
 
 use crate::{
-    dependency::Dependency,
-    fmt::Progress,
-    git::{git_checkout, git_clone},
+    cfg::Platform,
+    config::Config,
+    config_schema::config_header,
+    dependency::{BuildSystem, Dependency, DependencyKind, Source},
+    features::{ResolvedFeatures, resolve_features},
+    fmt::{Progress, warning},
+    git::{git_checkout, git_clone, git_fetch, git_head_commit},
+    lockfile::{LockedDependency, Lockfile, Pin},
+    mirror::git_mirror,
     package::Package,
+    package_pkg::extract_tarball,
+    pkg_config,
+    registry::{IndexEntry, Registry, newest_matching},
+    sources::expand_sources,
+    version::VersionReq,
+    workspace::Workspace,
 };
 
-fn dependency_storage_path(dependency: &Dependency) -> PathBuf {
-    let pkg_storage_fullpath = config_dir()
-        .expect("fail to get config directory")
-        .join("tailor")
-        .join("packages");
+/// A dependency ready to be compiled into a package.
+#[derive(Debug, Default)]
+pub struct ResolvedDependency {
+    pub sources: Vec<String>,
+    pub includes: Vec<String>,
+    pub definitions: Vec<String>,
+    pub compile_options: Vec<String>,
+    pub link_libraries: Vec<String>,
+    /// CMake projects added with `add_subdirectory`, with the name of their
+    /// build folder.
+    pub subdirectories: Vec<(String, String)>,
+    /// Headers generated for the dependency, with their file name.
+    pub headers: Vec<(String, String)>,
+    /// CMake variables given to the package, such as the executable of a
+    /// build-dependency.
+    pub variables: Vec<(String, String)>,
+}
+
+/// The folder holding the dependency, or `None` for system libraries.
+fn dependency_storage_path(dependency: &Dependency) -> Option<PathBuf> {
+    let pkg_storage_fullpath = Config::dir().join("packages");
+    let name = dependency.name();
 
-    match dependency {
-        Dependency::Local { path, .. } => path.clone(),
-        Dependency::Registry { name, version } => {
-            pkg_storage_fullpath.join(format!("{name}@{version}"))
+    match dependency.source() {
+        Source::Local { path } => Some(path.clone()),
+        Source::Registry { version } => {
+            Some(pkg_storage_fullpath.join(format!("{name}@{version}")))
         }
-        Dependency::Git { name, revision, .. } => {
-            pkg_storage_fullpath.join(format!("{name}@{revision}"))
+        Source::Git { revision, .. } => {
+            Some(pkg_storage_fullpath.join(format!("{name}@{revision}")))
         }
+        Source::System { .. } => None,
     }
 }
 
+/// The manifest of a Tailor dependency: the overlay manifest if given, or
+/// the `Tailor.toml` inside the dependency.
+fn dependency_manifest(dependency: &Dependency, dep_path: &Path) -> PathBuf {
+    dependency
+        .manifest()
+        .map_or_else(|| dep_path.join("Tailor.toml"), Path::to_path_buf)
+}
+
 fn dependency_is_valid(dependency: &Dependency) -> bool {
-    dependency_storage_path(dependency)
-        .join("Tailor.toml")
-        .exists()
+    dependency_storage_path(dependency).is_none_or(|path| {
+        let project_file = match dependency.build() {
+            BuildSystem::Tailor => dependency_manifest(dependency, &path),
+            BuildSystem::CMake { .. } => path.join("CMakeLists.txt"),
+        };
+        path.is_dir() && project_file.exists()
+    })
 }
 
-fn fetch_git_url(name: &str, version: &str) -> Result<String, String> {
-    let url = format!(
-        "http://127.0.0.1:5000/registry?name={}&version={}",
-        name, version
-    );
+fn download_git_dependency(
+    dependency: &Dependency,
+    url: &str,
+    revision: &str,
+    locked_commit: Option<&str>,
+) -> Result<(), String> {
+    let dep_path = dependency_storage_path(dependency).ok_or("not a git dependency")?;
+    let name = dependency.name();
 
-    let res = reqwest::blocking::get(url)
-        .map_err(|_| "fail to fetch URL".to_string())?
-        .text()
-        .map_err(|_| "fail to fetch URL".to_string())?;
+    let download = Progress::new("Downloading", format!("{} @ {}", name, revision));
+
+    git_clone(&git_mirror(&Config::load()?, url)?, &dep_path)?;
+
+    git_checkout(locked_commit.unwrap_or(revision), &dep_path)?;
 
-    if res.is_empty() {
-        return Err(format!("fail to get git URL for {name} @ {version}"));
+    download.finish("Downloaded", format!("{} @ {}", name, revision));
+
+    Ok(())
+}
+
+/// Checks out the commit locked for a Git dependency. The storage is shared by
+/// all the packages using the same revision, so another package may have left
+/// it at another commit, or cloned it before the commit existed.
+fn checkout_locked_commit(url: &str, commit: &str, path: &Path) -> Result<(), String> {
+    if git_head_commit(path)? == commit {
+        return Ok(());
     }
 
-    Ok(res)
+    if git_checkout(commit, path).is_err() {
+        git_fetch(&git_mirror(&Config::load()?, url)?, path)?;
+        git_checkout(commit, path)?;
+    }
+    Ok(())
 }
 
-fn download_git_dependency(name: &str, url: &str, revision: &str) -> Result<(), String> {
-    let dep_path = dependency_storage_path(&Dependency::Git {
-        name: name.to_string(),
-        url: url.to_string(),
-        revision: revision.to_string(),
-    });
+/// `dependency` from the registry at the exact `version`.
+fn pinned(dependency: &Dependency, version: &str) -> Dependency {
+    dependency.with_source(Source::Registry {
+        version: version.to_string(),
+    })
+}
 
-    let download = Progress::new("Downloading", format!("{} @ {}", name, revision));
+/// The registry dependencies among `dependencies` pinned to the version
+/// locked in `lockfile`, so they are stored per version and not per
+/// requirement.
+fn locked_dependencies(dependencies: &[Dependency], lockfile: &Lockfile) -> Vec<Dependency> {
+    dependencies
+        .iter()
+        .map(
+            |dep| match (dep.source(), lockfile.find(dep).and_then(|d| d.version())) {
+                (Source::Registry { .. }, Some(version)) => pinned(dep, version),
+                _ => dep.clone(),
+            },
+        )
+        .collect()
+}
 
-    git_clone(url, &dep_path)?;
+/// The index entry of a registry dependency: the version locked in
+/// `Tailor.lock`, which must still have the locked checksum, or else the
+/// newest version matching the requirement. Yanked versions are refused,
+/// unless they are locked.
+fn registry_entry(
+    dependency: &Dependency,
+    requirement: &str,
+    locked: Option<&LockedDependency>,
+) -> Result<IndexEntry, String> {
+    let name = dependency.name();
+    let registry = Registry::from_config()?;
 
-    git_checkout(revision, &dep_path)?;
+    if let Some((version, checksum)) = locked.and_then(|d| d.version().zip(d.checksum())) {
+        let entry = registry.entry(name, version)?.ok_or_else(|| {
+            format!(
+                "`{}` @ {} locked by Tailor.lock not found in registry {}",
+                name,
+                version,
+                registry.url()
+            )
+        })?;
+        if checksum != entry.checksum() {
+            return Err(format!(
+                "checksum of `{}` @ {} in registry {} doesn't match Tailor.lock, expected {} but got {}",
+                name,
+                version,
+                registry.url(),
+                checksum,
+                entry.checksum()
+            ));
+        }
+        if entry.yanked() {
+            println!(
+                "{}: `{}` @ {} was yanked, but it's still used as it's locked by Tailor.lock",
+                warning(),
+                name,
+                version
+            );
+        }
+        return Ok(entry);
+    }
 
-    download.finish("Downloaded", format!("{} @ {}", name, revision));
+    // A version not locked yet is looked up in a fresh index, so a version
+    // yanked since the index was cached isn't used.
+    let requirement = VersionReq::parse(requirement)?;
+    let entries = registry.entries(name)?;
+    if let Some(entry) = newest_matching(&entries, &requirement, false) {
+        return Ok(entry.clone());
+    }
+    match newest_matching(&entries, &requirement, true) {
+        Some(entry) => Err(format!(
+            "`{}` @ {} was yanked from registry {}",
+            name,
+            entry.version(),
+            registry.url()
+        )),
+        None => Err(format!(
+            "no version of `{}` matching `{}` found in registry {}",
+            name,
+            requirement,
+            registry.url()
+        )),
+    }
+}
+
+/// Downloads the tarball of a registry dependency and extracts it into the
+/// storage folder.
+fn download_registry_dependency(dependency: &Dependency, entry: &IndexEntry) -> Result<(), String> {
+    let dep_path = dependency_storage_path(&pinned(dependency, entry.version()))
+        .ok_or("not a registry dependency")?;
+    let (name, version) = (entry.name(), entry.version());
+
+    let download = Progress::new("Downloading", format!("{} @ {}", name, version));
+
+    let tarball = Registry::from_config()?.download(entry)?;
+    let parent = dep_path.parent().ok_or("invalid storage path")?;
+    std::fs::create_dir_all(parent)
+        .map_err(|e| format!("fail to create directory {}: {}", parent.display(), e))?;
+    let temp = tempfile::tempdir_in(parent)
+        .map_err(|e| format!("fail to create a temporary directory: {}", e))?;
+    extract_tarball(&tarball, temp.path())?;
+
+    let extracted = temp.path().join(format!("{}-{}", name, version));
+    if !extracted.is_dir() {
+        return Err(format!(
+            "the tarball of `{}` @ {} has no `{}-{}` folder",
+            name, version, name, version
+        ));
+    }
+    if dep_path.exists() {
+        std::fs::remove_dir_all(&dep_path)
+            .map_err(|e| format!("fail to remove {}: {}", dep_path.display(), e))?;
+    }
+    std::fs::rename(&extracted, &dep_path)
+        .map_err(|e| format!("fail to write {}: {}", dep_path.display(), e))?;
+
+    download.finish("Downloaded", format!("{} @ {}", name, version));
 
     Ok(())
 }
 
-fn dependency_download(parent_pkg_path: &Path, dependency: &Dependency) -> Result<(), String> {
-    match dependency {
-        Dependency::Local { path, name } => {
-            let path = parent_pkg_path.join(path);
-            let import = Progress::new("Importing", format!("{} from `{}`", name, path.display()));
+fn dependency_download(
+    dependency: &Dependency,
+    locked: Option<&LockedDependency>,
+) -> Result<(), String> {
+    match dependency.source() {
+        Source::Local { path } => {
+            let import = Progress::new(
+                "Importing",
+                format!("{} from `{}`", dependency.name(), path.display()),
+            );
 
-            let pkg = Package::from_file(&path.join("Tailor.toml"))?;
+            if let BuildSystem::CMake { .. } = dependency.build() {
+                if !path.join("CMakeLists.txt").exists() {
+                    return Err(format!("CMakeLists.txt not found in `{}`", path.display()));
+                }
+                import.finish("Imported", format!("{} (CMake)", dependency.name()));
+                return Ok(());
+            }
+
+            let pkg = Package::from_file(&dependency_manifest(dependency, path))?;
 
             import.finish("Imported", format!("{} @ {}", pkg.name(), pkg.version()));
 
             Ok(())
         }
-        Dependency::Registry { name, version } => {
-            let url = fetch_git_url(name, version)?;
-            download_git_dependency(name, &url, version)
+        Source::Registry { version } => {
+            let entry = registry_entry(dependency, version, locked)?;
+            download_registry_dependency(dependency, &entry)
         }
-        Dependency::Git {
-            name,
-            url,
-            revision,
-        } => download_git_dependency(name, url, revision),
+        Source::Git { url, revision } => {
+            download_git_dependency(dependency, url, revision, locked.and_then(|d| d.commit()))
+        }
+        Source::System { .. } => Ok(()),
     }
 }
 
-fn dependency_source_paths(dependency: &Dependency) -> Result<Vec<String>, String> {
-    let dep_path = dependency_storage_path(dependency);
-    let manifest_path = dep_path.join("Tailor.toml");
+/// The features requested from a dependency by all the packages using it.
+#[derive(Debug, Default)]
+struct FeatureRequest {
+    features: BTreeSet<String>,
+    default_features: bool,
+}
 
-    Ok(Package::from_file(&manifest_path)?
-        .sources()
-        .iter()
-        .map(|source| dep_path.join(source).to_string_lossy().to_string())
-        .collect())
+/// The features of each dependency, unified over the packages using it: `pkg`
+/// with its enabled `features`, and the other members of its workspace with
+/// their default features. So a dependency shared by the members is compiled
+/// with the same features, and the same API, in all of them.
+fn unified_features(
+    pkg: &Package,
+    pkg_path: &Path,
+    workspace: Option<&Workspace>,
+    features: &ResolvedFeatures,
+    platform: &Platform,
+    kinds: &[DependencyKind],
+) -> Result<BTreeMap<String, FeatureRequest>, String> {
+    let mut others = vec![];
+    for (path, member) in workspace.map_or(&[][..], Workspace::members) {
+        if path != pkg_path {
+            others.push((member, path, resolve_features(member, &[], false, true)?));
+        }
+    }
+
+    let mut requests = BTreeMap::<String, FeatureRequest>::new();
+    for (member, path, member_features) in [(pkg, pkg_path, features)]
+        .into_iter()
+        .chain(others.iter().map(|(m, p, f)| (*m, p.as_path(), f)))
+    {
+        for dependency in
+            selected_dependencies(member, path, workspace, member_features, platform, kinds)
+        {
+            let request = requests.entry(dependency.name().to_string()).or_default();
+            request
+                .features
+                .extend(dependency.features().iter().cloned());
+            request
+                .features
+                .extend(member_features.dependency_features(dependency.name()));
+            request.default_features |= dependency.default_features();
+        }
+    }
+
+    Ok(requests)
 }
 
-fn dependency_include_paths(dependency: &Dependency) -> Result<Vec<String>, String> {
-    let dep_path = dependency_storage_path(dependency);
-    let manifest_path = dep_path.join("Tailor.toml");
+/// Compiles the dependency with the features requested by the packages using
+/// it. System libraries are linked with the flags given by pkg-config
+/// instead, and CMake projects through their CMake targets.
+fn resolve_dependency(
+    dependency: &Dependency,
+    request: &FeatureRequest,
+) -> Result<ResolvedDependency, String> {
+    let Some(dep_path) = dependency_storage_path(dependency) else {
+        return resolve_system_dependency(dependency);
+    };
+
+    if let BuildSystem::CMake { targets } = dependency.build() {
+        return Ok(ResolvedDependency {
+            link_libraries: targets.clone(),
+            subdirectories: vec![(
+                dep_path.to_string_lossy().to_string(),
+                dependency.name().to_string(),
+            )],
+            ..Default::default()
+        });
+    }
+
+    let dep_pkg = Package::from_file(&dependency_manifest(dependency, &dep_path))?;
+
+    let requested = request.features.iter().cloned().collect::<Vec<String>>();
+    let features = resolve_features(&dep_pkg, &requested, false, request.default_features)?;
+    let headers = config_header(&dep_pkg, dependency.config())?
+        .into_iter()
+        .collect();
+
+    Ok(ResolvedDependency {
+        sources: expand_sources(&dep_path, dep_pkg.sources())?
+            .iter()
+            .map(|source| source.to_string_lossy().to_string())
+            .collect(),
+        includes: dep_pkg
+            .includes()
+            .iter()
+            .map(|include| dep_path.join(include).to_string_lossy().to_string())
+            .collect(),
+        definitions: features.definitions(),
+        headers,
+        ..Default::default()
+    })
+}
+
+fn resolve_system_dependency(dependency: &Dependency) -> Result<ResolvedDependency, String> {
+    let Source::System { module, version } = dependency.source() else {
+        return Err(format!("`{}` is not a system library", dependency.name()));
+    };
+
+    let library = pkg_config::probe(dependency.name(), module, version.as_deref())?;
+
+    Ok(ResolvedDependency {
+        includes: library.includes,
+        compile_options: library.cflags,
+        link_libraries: library.libs,
+        ..Default::default()
+    })
+}
 
-    Ok(Package::from_file(&manifest_path)?
-        .includes()
+/// The dependencies of `pkg` as they are resolved: local paths are joined to
+/// the package folder (as are overlay manifests), and a path dependency or a
+/// registry one with the version `*` named after a member of the workspace is
+/// the member folder itself. A dependency on a given version of a member still
+/// comes from the registry.
+fn effective_dependencies(
+    pkg: &Package,
+    pkg_path: &Path,
+    workspace: Option<&Workspace>,
+) -> Vec<Dependency> {
+    pkg.dependencies()
         .iter()
-        .map(|include| dep_path.join(include).to_string_lossy().to_string())
-        .collect())
+        .map(|dep| match dep.manifest() {
+            Some(manifest) => dep.with_manifest(pkg_path.join(manifest)),
+            None => dep.clone(),
+        })
+        .map(|dep| {
+            let versionless = match dep.source() {
+                Source::Local { .. } => true,
+                Source::Registry { version } => version.trim() == "*",
+                _ => false,
+            };
+            if versionless && let Some((path, _)) = workspace.and_then(|ws| ws.member(dep.name())) {
+                return dep.with_source(Source::Local { path: path.clone() });
+            }
+
+            match dep.source() {
+                Source::Local { path } => dep.with_source(Source::Local {
+                    path: pkg_path.join(path),
+                }),
+                _ => dep.clone(),
+            }
+        })
+        .collect()
 }
 
-pub fn resolve_dependencies(
+/// The dependencies of `pkg` declared in the tables of `kinds`, without the
+/// optional ones not enabled by a feature and the ones whose condition
+/// doesn't match `platform`.
+fn selected_dependencies(
+    pkg: &Package,
+    pkg_path: &Path,
+    workspace: Option<&Workspace>,
+    features: &ResolvedFeatures,
+    platform: &Platform,
+    kinds: &[DependencyKind],
+) -> Vec<Dependency> {
+    effective_dependencies(pkg, pkg_path, workspace)
+        .into_iter()
+        .filter(|dep| kinds.contains(&dep.kind()))
+        .filter(|dep| !dep.optional() || features.enables_dependency(dep.name()))
+        .filter(|dep| dep.condition().is_none_or(|c| c.matches(platform)))
+        .collect()
+}
+
+/// Downloads the dependencies not stored yet, and locks the commit or the
+/// version of the ones not in the lockfile. Returns the dependencies with
+/// the registry ones pinned to their locked version.
+fn fetch_dependencies(
+    dependencies: &[Dependency],
     pkg: &Package,
     pkg_path: &Path,
-) -> Result<(Vec<String>, Vec<String>), String> {
-    for dependency in pkg.dependencies() {
-        if dependency_is_valid(dependency) {
+    workspace: Option<&Workspace>,
+) -> Result<Vec<Dependency>, String> {
+    let lockfile_path = workspace.map_or(pkg_path.join("Tailor.lock"), Workspace::lockfile_path);
+    let old_lockfile = Lockfile::load(&lockfile_path)?;
+    let mut lockfile = old_lockfile.clone();
+
+    for dependency in dependencies {
+        // Registry dependencies not locked yet are looked up in the index even
+        // when a matching version is stored, so a yanked version isn't locked.
+        if let Source::Registry { version } = dependency.source() {
+            let locked = lockfile.find(dependency).cloned();
+            let stored = locked
+                .as_ref()
+                .and_then(LockedDependency::version)
+                .is_some_and(|version| dependency_is_valid(&pinned(dependency, version)));
+            if !stored {
+                let entry = registry_entry(dependency, version, locked.as_ref())?;
+                if !dependency_is_valid(&pinned(dependency, entry.version())) {
+                    download_registry_dependency(dependency, &entry)?;
+                }
+                lockfile.lock(
+                    dependency,
+                    Pin::Tarball {
+                        version: entry.version(),
+                        checksum: entry.checksum(),
+                    },
+                );
+            }
             continue;
         }
 
-        dependency_download(pkg_path, dependency)?;
+        if !dependency_is_valid(dependency) {
+            dependency_download(dependency, lockfile.find(dependency))?;
+        }
+
+        if let (Source::Git { url, .. }, Some(path)) =
+            (dependency.source(), dependency_storage_path(dependency))
+        {
+            match lockfile.find(dependency).and_then(|d| d.commit()) {
+                Some(commit) => checkout_locked_commit(url, commit, &path)?,
+                None => {
+                    let commit = git_head_commit(&path)?;
+                    lockfile.lock(dependency, Pin::Commit(&commit));
+                }
+            }
+        }
+    }
+
+    match workspace {
+        Some(ws) => lockfile.retain(
+            &ws.members()
+                .iter()
+                .flat_map(|(path, member)| effective_dependencies(member, path, workspace))
+                .collect::<Vec<Dependency>>(),
+        ),
+        None => lockfile.retain(&effective_dependencies(pkg, pkg_path, workspace)),
     }
+    if lockfile != old_lockfile {
+        lockfile.save(&lockfile_path)?;
+    }
+
+    Ok(locked_dependencies(dependencies, &lockfile))
+}
 
-    let sources = pkg
-        .dependencies()
+/// Fetches and resolves the dependencies of `pkg` compiled into it: the
+/// `[dependencies]`, plus the `[dev-dependencies]` when `kinds` has them.
+/// Dependencies are never transitive, so only the manifest of `pkg` matters,
+/// along with the other members of the workspace for the features.
+pub fn resolve_dependencies(
+    pkg: &Package,
+    pkg_path: &Path,
+    workspace: Option<&Workspace>,
+    features: &ResolvedFeatures,
+    platform: &Platform,
+    kinds: &[DependencyKind],
+) -> Result<Vec<ResolvedDependency>, String> {
+    let dependencies = selected_dependencies(pkg, pkg_path, workspace, features, platform, kinds);
+    let dependencies = fetch_dependencies(&dependencies, pkg, pkg_path, workspace)?;
+
+    let requests = unified_features(pkg, pkg_path, workspace, features, platform, kinds)?;
+    dependencies
         .iter()
-        .map(|dep| dependency_source_paths(dep).unwrap_or(vec![]))
-        .flatten()
-        .collect();
-    let includes = pkg
-        .dependencies()
+        .map(|dep| resolve_dependency(dep, &requests[dep.name()]))
+        .collect()
+}
+
+/// Fetches the `[build-dependencies]` of `pkg`, returning their folders.
+/// System libraries are skipped, as there's nothing to build.
+pub fn fetch_build_dependencies(
+    pkg: &Package,
+    pkg_path: &Path,
+    workspace: Option<&Workspace>,
+    features: &ResolvedFeatures,
+    platform: &Platform,
+) -> Result<Vec<PathBuf>, String> {
+    let dependencies = selected_dependencies(
+        pkg,
+        pkg_path,
+        workspace,
+        features,
+        platform,
+        &[DependencyKind::Build],
+    );
+    let dependencies = fetch_dependencies(&dependencies, pkg, pkg_path, workspace)?;
+
+    Ok(dependencies
         .iter()
-        .map(|dep| dependency_include_paths(dep).unwrap_or(vec![]))
-        .flatten()
-        .collect();
+        .filter_map(dependency_storage_path)
+        .collect())
+}
+
+/// Directories holding the source code of the dependencies of `pkg`.
+pub fn dependency_paths(
+    pkg: &Package,
+    pkg_path: &Path,
+    workspace: Option<&Workspace>,
+) -> Vec<PathBuf> {
+    let lockfile_path = workspace.map_or(pkg_path.join("Tailor.lock"), Workspace::lockfile_path);
+    let lockfile = Lockfile::load(&lockfile_path).unwrap_or_default();
 
-    Ok((sources, includes))
+    locked_dependencies(&effective_dependencies(pkg, pkg_path, workspace), &lockfile)
+        .iter()
+        .filter(|dep| dep.kind() == DependencyKind::Normal)
+        .filter_map(dependency_storage_path)
+        .collect()
 }
@@ -0,0 +1,131 @@
+use std::fmt::Display;
+
+/// A Tailor edition, `<year>.<number>`. Each edition keeps building the
+/// manifests of the previous ones as they were, and the manifest semantics
+/// that change between editions only apply to the packages that opt in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Edition {
+    year: u16,
+    number: u16,
+}
+
+impl Edition {
+    /// The first edition, used by manifests without `edition`.
+    pub const E2025_1: Edition = Edition {
+        year: 2025,
+        number: 1,
+    };
+    /// Sources found in the subfolders of `src/` by default, and C11.
+    pub const E2025_2: Edition = Edition {
+        year: 2025,
+        number: 2,
+    };
+    /// The newest edition this version of Tailor supports, set in the
+    /// packages created by `tailor new`.
+    pub const LATEST: Edition = Self::E2025_2;
+
+    /// The `src` of the packages that don't set it.
+    pub fn default_sources(&self) -> &'static str {
+        if *self >= Self::E2025_2 {
+            "src/**/*.c"
+        } else {
+            "src/*.c"
+        }
+    }
+
+    /// The C standard the packages are compiled with, as given to
+    /// `CMAKE_C_STANDARD`.
+    pub fn c_standard(&self) -> &'static str {
+        if *self >= Self::E2025_2 { "11" } else { "99" }
+    }
+}
+
+impl Default for Edition {
+    fn default() -> Self {
+        Self::E2025_1
+    }
+}
+
+impl TryFrom<&str> for Edition {
+    type Error = String;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        let invalid = || {
+            format!(
+                "invalid edition `{}`, expected `<year>.<number>`, such as `{}`",
+                value,
+                Self::LATEST
+            )
+        };
+
+        let (year, number) = value.split_once('.').ok_or_else(invalid)?;
+        let edition = Edition {
+            year: year.parse().map_err(|_| invalid())?,
+            number: number.parse().map_err(|_| invalid())?,
+        };
+        if year.len() != 4 || number.starts_with('0') || edition < Self::E2025_1 {
+            return Err(invalid());
+        }
+        if edition > Self::LATEST {
+            return Err(format!(
+                "edition `{}` requires a newer Tailor, this one supports editions up to `{}`",
+                edition,
+                Self::LATEST
+            ));
+        }
+
+        Ok(edition)
+    }
+}
+
+impl Display for Edition {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{}", self.year, self.number)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn editions_are_parsed_and_ordered() {
+        assert_eq!(Edition::try_from("2025.1"), Ok(Edition::E2025_1));
+        assert_eq!(Edition::try_from("2025.2"), Ok(Edition::E2025_2));
+        assert!(Edition::E2025_1 < Edition::E2025_2);
+        assert_eq!(Edition::default(), Edition::E2025_1);
+        assert_eq!(Edition::LATEST.to_string(), "2025.2");
+    }
+
+    #[test]
+    fn invalid_and_newer_editions_are_errors() {
+        for edition in [
+            "2025", "2025.", "25.1", "2025.01", "2025.x", "2024.9", "2025.1.0",
+        ] {
+            assert_eq!(
+                Edition::try_from(edition),
+                Err(format!(
+                    "invalid edition `{}`, expected `<year>.<number>`, such as `2025.2`",
+                    edition
+                ))
+            );
+        }
+        for edition in ["2025.3", "2026.1"] {
+            assert_eq!(
+                Edition::try_from(edition),
+                Err(format!(
+                    "edition `{}` requires a newer Tailor, this one supports editions up to `2025.2`",
+                    edition
+                ))
+            );
+        }
+    }
+
+    #[test]
+    fn defaults_change_with_the_edition() {
+        assert_eq!(Edition::E2025_1.default_sources(), "src/*.c");
+        assert_eq!(Edition::E2025_1.c_standard(), "99");
+        assert_eq!(Edition::E2025_2.default_sources(), "src/**/*.c");
+        assert_eq!(Edition::E2025_2.c_standard(), "11");
+    }
+}
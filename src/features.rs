@@ -0,0 +1,163 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use toml::Value;
+
+use crate::package::Package;
+
+/// The features selected in the command line for the packages being built.
+#[derive(Debug, Default, Clone)]
+pub struct FeatureSelection {
+    pub features: Vec<String>,
+    pub all_features: bool,
+    pub no_default_features: bool,
+}
+
+/// The features enabled in a package and what they require from its
+/// dependencies.
+#[derive(Debug, Default)]
+pub struct ResolvedFeatures {
+    package: String,
+    features: BTreeSet<String>,
+    dependencies: BTreeMap<String, BTreeSet<String>>,
+}
+
+impl ResolvedFeatures {
+    /// Whether the optional dependency `name` was enabled by a feature.
+    pub fn enables_dependency(&self, name: &str) -> bool {
+        self.dependencies.contains_key(name)
+    }
+
+    /// Features of the dependency `name` enabled by the package features.
+    pub fn dependency_features(&self, name: &str) -> Vec<String> {
+        self.dependencies
+            .get(name)
+            .map_or(vec![], |f| f.iter().cloned().collect())
+    }
+
+    /// The C preprocessor definitions of the enabled features.
+    pub fn definitions(&self) -> Vec<String> {
+        self.features
+            .iter()
+            .map(|f| definition(&self.package, f))
+            .collect()
+    }
+}
+
+/// `TAILOR_FEATURE_<PACKAGE>_<NAME>`, with the names in upper case and any
+/// character not valid in a C identifier replaced by `_`. The package name
+/// keeps apart the features of the same name of a package and of its
+/// dependencies, which see each other's definitions.
+pub fn definition(package: &str, feature: &str) -> String {
+    let identifier = |name: &str| {
+        name.chars()
+            .map(|c| match c {
+                'a'..='z' | 'A'..='Z' | '0'..='9' => c.to_ascii_uppercase(),
+                _ => '_',
+            })
+            .collect::<String>()
+    };
+    format!(
+        "TAILOR_FEATURE_{}_{}",
+        identifier(package),
+        identifier(feature)
+    )
+}
+
+/// Parses the `[features]` table: each feature is a list of the features it
+/// implies, where `dep:<name>` enables an optional dependency and
+/// `<dep>/<feature>` enables a feature of a dependency.
+pub fn parse_features(content: Option<&Value>) -> Result<BTreeMap<String, Vec<String>>, String> {
+    let Some(content) = content else {
+        return Ok(BTreeMap::new());
+    };
+
+    content
+        .as_table()
+        .ok_or("features must be a table")?
+        .iter()
+        .map(|(name, implied)| {
+            implied
+                .as_array()
+                .and_then(|arr| {
+                    arr.iter()
+                        .map(|v| v.as_str().map(String::from))
+                        .collect::<Option<Vec<String>>>()
+                })
+                .map(|implied| (name.clone(), implied))
+                .ok_or_else(|| format!("feature '{}' must be a list of strings", name))
+        })
+        .collect()
+}
+
+/// Enables `requested` (plus `default`, when `default` is true, or all the
+/// features, when `all` is true) in `pkg`, following the implications in its
+/// `[features]` table.
+pub fn resolve_features(
+    pkg: &Package,
+    requested: &[String],
+    all: bool,
+    default: bool,
+) -> Result<ResolvedFeatures, String> {
+    let mut resolved = ResolvedFeatures {
+        package: pkg.name().to_string(),
+        ..Default::default()
+    };
+
+    let mut pending = requested.to_vec();
+    if all {
+        pending.extend(pkg.features().keys().cloned());
+    }
+    if default && pkg.features().contains_key("default") {
+        pending.push("default".to_string());
+    }
+
+    while let Some(feature) = pending.pop() {
+        if let Some(dep) = feature.strip_prefix("dep:") {
+            enable_dependency(pkg, &mut resolved, dep, None)?;
+            continue;
+        }
+
+        if let Some((dep, dep_feature)) = feature.split_once('/') {
+            enable_dependency(pkg, &mut resolved, dep, Some(dep_feature))?;
+            continue;
+        }
+
+        if resolved.features.contains(&feature) {
+            continue;
+        }
+
+        let implied = pkg.features().get(&feature).ok_or_else(|| {
+            format!(
+                "package `{}` does not have the feature `{}`",
+                pkg.name(),
+                feature
+            )
+        })?;
+        pending.extend(implied.iter().cloned());
+        resolved.features.insert(feature);
+    }
+
+    Ok(resolved)
+}
+
+fn enable_dependency(
+    pkg: &Package,
+    resolved: &mut ResolvedFeatures,
+    name: &str,
+    feature: Option<&str>,
+) -> Result<(), String> {
+    if !pkg.dependencies().iter().any(|d| d.name() == name) {
+        return Err(format!(
+            "package `{}` does not have the dependency `{}`",
+            pkg.name(),
+            name
+        ));
+    }
+
+    let features = resolved.dependencies.entry(name.to_string()).or_default();
+    if let Some(feature) = feature {
+        features.insert(feature.to_string());
+    }
+
+    Ok(())
+}
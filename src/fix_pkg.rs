@@ -0,0 +1,223 @@
+use toml_edit::{Array, DocumentMut, Item, Key, Table, value};
+
+use crate::{
+    build_pkg::BuildPkg,
+    command::{Command, parse_flag_value},
+    edition::Edition,
+    fmt::{info, success, warning},
+    package::Package,
+};
+
+#[derive(Default)]
+pub struct FixPkg {
+    build: BuildPkg,
+    edition: Option<String>,
+    dry_run: bool,
+}
+
+/// Rewrites a manifest written for the previous edition so it keeps the same
+/// behavior under `edition`. Returns notes on the changes that can't be
+/// expressed in the manifest.
+struct Migration {
+    edition: Edition,
+    apply: fn(&mut Table) -> Vec<String>,
+}
+
+const MIGRATIONS: &[Migration] = &[Migration {
+    edition: Edition::E2025_2,
+    apply: to_2025_2,
+}];
+
+fn to_2025_2(manifest: &mut Table) -> Vec<String> {
+    if let Some((key, item)) = manifest.remove_entry("lib")
+        && !manifest.contains_key("type")
+    {
+        let mut lib = value("lib");
+        if let (Some(new), Some(old)) = (lib.as_value_mut(), item.as_value()) {
+            *new.decor_mut() = old.decor().clone();
+        }
+        insert_after(
+            manifest,
+            "version",
+            Key::new("type").with_leaf_decor(key.leaf_decor().clone()),
+            lib,
+        );
+    }
+
+    // The default sources now include the subfolders of `src/`.
+    if !manifest.contains_key("src") {
+        let mut sources = Array::new();
+        sources.push(Edition::E2025_1.default_sources());
+        insert_after(manifest, "edition", Key::new("src"), value(sources));
+    }
+
+    vec![format!(
+        "the package is now compiled as C{} instead of C{}",
+        Edition::E2025_2.c_standard(),
+        Edition::E2025_1.c_standard()
+    )]
+}
+
+/// Inserts `key` right after the key `after`, or at the end of the key values
+/// of `table` when there's no `after`, keeping the comments of the moved keys.
+fn insert_after(table: &mut Table, after: &str, key: Key, item: Item) {
+    let keys = table
+        .iter()
+        .filter(|(_, item)| item.is_value())
+        .map(|(k, _)| k.to_string())
+        .collect::<Vec<String>>();
+    let moved = match keys.iter().position(|k| k == after) {
+        Some(position) => keys[position + 1..]
+            .iter()
+            .filter_map(|k| table.remove_entry(k))
+            .collect(),
+        None => Vec::new(),
+    };
+
+    table.insert_formatted(&key, item);
+    for (key, item) in moved {
+        table.insert_formatted(&key, item);
+    }
+}
+
+/// The lines removed (`-`) and added (`+`) from `old` to `new`, from their
+/// longest common subsequence.
+fn diff(old: &str, new: &str) -> Vec<String> {
+    let old = old.lines().collect::<Vec<&str>>();
+    let new = new.lines().collect::<Vec<&str>>();
+
+    let mut lcs = vec![vec![0; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lcs[i][j] = if old[i] == new[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut lines = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            i += 1;
+            j += 1;
+        } else if i < old.len() && (j == new.len() || lcs[i + 1][j] >= lcs[i][j + 1]) {
+            lines.push(format!("\x1B[31m- {}\x1B[0m", old[i]));
+            i += 1;
+        } else {
+            lines.push(format!("\x1B[32m+ {}\x1B[0m", new[j]));
+            j += 1;
+        }
+    }
+
+    lines
+}
+
+impl Command for FixPkg {
+    fn parse_args(&mut self, args: &[String]) -> Option<()> {
+        if args.is_empty() || args[0] != "fix" {
+            return None;
+        }
+
+        let mut i = 1;
+        while i < args.len() {
+            if let Some((edition, consumed)) = parse_flag_value(&args[i..], &["--edition"]) {
+                self.edition = Some(edition.to_string());
+                i += consumed;
+                continue;
+            }
+            if args[i] == "--dry-run" {
+                self.dry_run = true;
+                i += 1;
+                continue;
+            }
+            i += self.build.parse_arg(&args[i..])?;
+        }
+        self.edition.as_ref()?;
+
+        self.build.finish_args()
+    }
+
+    fn execute(&self) -> Result<(), String> {
+        let target = Edition::try_from(self.edition.as_deref().ok_or("missing --edition")?)?;
+        let (_, paths) = self.build.selected_packages()?;
+
+        for path in paths {
+            let manifest = path.join("Tailor.toml");
+            let pkg = Package::from_file(&manifest)?;
+            if pkg.is_virtual() {
+                continue;
+            }
+
+            if target < pkg.edition() {
+                return Err(format!(
+                    "`{}` is already on edition `{}`, it can't be migrated back to `{}`",
+                    pkg.name(),
+                    pkg.edition(),
+                    target
+                ));
+            }
+            if target == pkg.edition() {
+                println!(
+                    "{} `{}` is already on edition `{}`",
+                    info("Fresh"),
+                    pkg.name(),
+                    target
+                );
+                continue;
+            }
+
+            let content = std::fs::read_to_string(&manifest)
+                .map_err(|e| format!("fail to read file {}: {}", manifest.display(), e))?;
+            let mut document = content
+                .parse::<DocumentMut>()
+                .map_err(|e| format!("fail to parse {}: {}", manifest.display(), e))?;
+
+            let table = document.as_table_mut();
+            if table.contains_key("edition") {
+                table["edition"] = value(target.to_string());
+            } else {
+                insert_after(
+                    table,
+                    "version",
+                    Key::new("edition"),
+                    value(target.to_string()),
+                );
+            }
+
+            let mut notes = Vec::new();
+            for migration in MIGRATIONS
+                .iter()
+                .filter(|m| pkg.edition() < m.edition && m.edition <= target)
+            {
+                notes.extend((migration.apply)(table));
+            }
+
+            let fixed = document.to_string();
+            println!(
+                "{} `{}` from edition `{}` to `{}`",
+                info("Migrating"),
+                pkg.name(),
+                pkg.edition(),
+                target
+            );
+            for line in diff(&content, &fixed) {
+                println!("{:>12} {}", "", line);
+            }
+            for note in notes {
+                println!("{}: {}", warning(), note);
+            }
+
+            if self.dry_run {
+                continue;
+            }
+            std::fs::write(&manifest, fixed)
+                .map_err(|e| format!("fail to write {}: {}", manifest.display(), e))?;
+            println!("{} {}", success("Fixed"), manifest.display());
+        }
+
+        Ok(())
+    }
+}
@@ -1,28 +1,168 @@
 use std::{
-    path::Path,
+    path::{Path, PathBuf},
     process::{Command, Stdio},
 };
 
+use crate::{config::Config, credentials::Credentials, network::git_env};
+
+/// The scheme and the host of an `https://` or `http://` Git URL.
+fn url_host(url: &str) -> Option<(&str, &str)> {
+    let (scheme, rest) = url.split_once("://")?;
+    let authority = rest.split('/').next()?;
+    Some((scheme, authority.rsplit('@').next().unwrap_or(authority)))
+}
+
+/// A `git` command reaching the repository at `url`, with the token of its
+/// host and the network settings of the config.
+fn remote_command(url: &str) -> Result<Command, String> {
+    let mut command = Command::new("git");
+    // Without a terminal for the prompt, a clone asking for a password would
+    // wait forever.
+    command
+        .env("GIT_TERMINAL_PROMPT", "0")
+        .envs(git_env(Config::load()?.http())?);
+
+    // The token stored by `tailor login --git <host>` is given through the
+    // environment, as the command line of a process may be seen by other
+    // users.
+    if let Some((scheme, host)) = url_host(url)
+        && let Some(token) = Credentials::load()?.git_token(host)
+    {
+        command
+            .env("GIT_CONFIG_COUNT", "1")
+            .env(
+                "GIT_CONFIG_KEY_0",
+                format!("http.{}://{}/.extraHeader", scheme, host),
+            )
+            .env(
+                "GIT_CONFIG_VALUE_0",
+                format!("Authorization: Bearer {}", token),
+            );
+    }
+
+    Ok(command)
+}
+
 pub fn git_clone(url: &str, path: &Path) -> Result<(), String> {
-    Command::new("git")
+    let status = remote_command(url)?
         .arg("clone")
         .arg(url)
         .arg(path)
         .stdout(Stdio::null())
         .stderr(Stdio::null())
         .status()
-        .map(|_| ())
-        .map_err(|e| format!("fail to clone repository: {}", e))
+        .map_err(|e| format!("fail to clone repository: {}", e))?;
+
+    if !status.success() {
+        return Err(format!("fail to clone repository `{}`", url));
+    }
+    Ok(())
+}
+
+/// Fetches the branches and the tags of the repository at `url` into the
+/// clone at `path`.
+pub fn git_fetch(url: &str, path: &Path) -> Result<(), String> {
+    let status = remote_command(url)?
+        .arg("fetch")
+        .arg("--tags")
+        .arg(url)
+        .current_dir(path)
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .map_err(|e| format!("fail to fetch repository: {}", e))?;
+
+    if !status.success() {
+        return Err(format!("fail to fetch repository `{}`", url));
+    }
+    Ok(())
 }
 
 pub fn git_checkout(revision: &str, path: &Path) -> Result<(), String> {
-    Command::new("git")
+    let status = Command::new("git")
         .arg("checkout")
         .arg(revision)
         .current_dir(path)
         .stdout(Stdio::null())
         .stderr(Stdio::null())
         .status()
-        .map(|_| ())
-        .map_err(|e| format!("fail to checkout revision: {}", e))
+        .map_err(|e| format!("fail to checkout revision: {}", e))?;
+
+    if !status.success() {
+        return Err(format!(
+            "fail to checkout revision `{}` in `{}`",
+            revision,
+            path.display()
+        ));
+    }
+    Ok(())
+}
+
+pub fn git_head_commit(path: &Path) -> Result<String, String> {
+    let output = Command::new("git")
+        .arg("rev-parse")
+        .arg("HEAD")
+        .current_dir(path)
+        .stderr(Stdio::null())
+        .output()
+        .map_err(|e| format!("fail to get current commit: {}", e))?;
+
+    if !output.status.success() {
+        return Err(format!(
+            "fail to get current commit of `{}`",
+            path.display()
+        ));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// The uncommitted files inside `path`, relative to the repository root.
+/// Returns no files when `path` isn't inside a Git repository.
+pub fn git_dirty_files(path: &Path) -> Result<Vec<String>, String> {
+    let output = Command::new("git")
+        .arg("status")
+        .arg("--porcelain")
+        .arg("--")
+        .arg(".")
+        .current_dir(path)
+        .stderr(Stdio::null())
+        .output()
+        .map_err(|e| format!("fail to get git status: {}", e))?;
+
+    if !output.status.success() {
+        return Ok(vec![]);
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(|line| line[3..].to_string())
+        .collect())
+}
+
+/// The files inside `path` that Git doesn't ignore, tracked or not, relative
+/// to it. Returns `None` when `path` isn't inside a Git repository, or Git
+/// isn't installed.
+pub fn git_package_files(path: &Path) -> Option<Vec<PathBuf>> {
+    let output = Command::new("git")
+        .arg("ls-files")
+        .arg("--cached")
+        .arg("--others")
+        .arg("--exclude-standard")
+        .arg("-z")
+        .current_dir(path)
+        .stderr(Stdio::null())
+        .output()
+        .ok()
+        .filter(|output| output.status.success())?;
+
+    let mut files = String::from_utf8_lossy(&output.stdout)
+        .split('\0')
+        .filter(|file| !file.is_empty())
+        .map(PathBuf::from)
+        .collect::<Vec<PathBuf>>();
+    // A file removed but not committed yet is still listed.
+    files.retain(|file| path.join(file).is_file());
+    files.dedup();
+    Some(files)
 }
@@ -0,0 +1,169 @@
+use std::path::Path;
+
+use toml::{Table, Value};
+
+use crate::{
+    command::{Command, parse_flag_value},
+    edition::Edition,
+    package_pkg::read_tarball_file,
+    registry::{Registry, latest},
+};
+
+#[derive(Default)]
+pub struct InfoPkg {
+    name: String,
+    version: Option<String>,
+    registry: Option<String>,
+}
+
+/// Prints `label` followed by `value`, aligned with the other labels, unless
+/// `value` is empty.
+fn field(label: &str, value: &str) {
+    if !value.is_empty() {
+        println!("{:<12} {}", format!("{}:", label), value);
+    }
+}
+
+/// The string `key` of `manifest`, or an empty string.
+fn string<'a>(manifest: &'a Table, key: &str) -> &'a str {
+    manifest
+        .get(key)
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+}
+
+/// The strings of the list at `value`, skipping the values of other types.
+fn strings(value: Option<&Value>) -> Vec<&str> {
+    value.and_then(Value::as_array).map_or(vec![], |list| {
+        list.iter().filter_map(Value::as_str).collect()
+    })
+}
+
+impl Command for InfoPkg {
+    fn parse_args(&mut self, args: &[String]) -> Option<()> {
+        if args.is_empty() || args[0] != "info" {
+            return None;
+        }
+
+        let mut i = 1;
+        while i < args.len() {
+            if let Some((registry, consumed)) = parse_flag_value(&args[i..], &["--registry"]) {
+                self.registry = Some(registry.to_string());
+                i += consumed;
+                continue;
+            }
+            if args[i].starts_with('-') || !self.name.is_empty() {
+                return None;
+            }
+            match args[i].split_once('@') {
+                Some((name, version)) => {
+                    self.name = name.to_string();
+                    self.version = Some(version.to_string());
+                }
+                None => self.name = args[i].clone(),
+            }
+            i += 1;
+        }
+
+        (!self.name.is_empty()).then_some(())
+    }
+
+    fn execute(&self) -> Result<(), String> {
+        let registry = Registry::named(self.registry.as_deref())?;
+        let entries = registry.entries(&self.name)?;
+        if entries.is_empty() {
+            return Err(format!(
+                "`{}` not found in registry {}",
+                self.name,
+                registry.url()
+            ));
+        }
+
+        let entry = match &self.version {
+            Some(version) => entries
+                .iter()
+                .find(|e| e.version() == version)
+                .ok_or_else(|| format!("`{}` has no version {}", self.name, version))?,
+            None => latest(&entries)
+                .or(entries.last())
+                .ok_or_else(|| format!("`{}` has no versions", self.name))?,
+        };
+
+        let tarball = registry.download(entry)?;
+        let manifest = read_tarball_file(
+            &tarball,
+            &Path::new(&format!("{}-{}", entry.name(), entry.version())).join("Tailor.toml"),
+        )?;
+        // The manifest is only read for its metadata, and isn't validated, so
+        // versions published for a newer edition can still be shown.
+        let manifest = manifest.parse::<Table>().map_err(|e| {
+            format!(
+                "invalid Tailor.toml in `{}` @ {}: {}",
+                entry.name(),
+                entry.version(),
+                e.message()
+            )
+        })?;
+
+        println!("{} @ {}", entry.name(), entry.version());
+        if let Some(description) = manifest.get("description").and_then(Value::as_str) {
+            println!("{}", description);
+        }
+        println!();
+        field("license", string(&manifest, "license"));
+        field("authors", &strings(manifest.get("authors")).join(", "));
+        field("repository", string(&manifest, "repository"));
+        field("keywords", &strings(manifest.get("keywords")).join(", "));
+        field(
+            "edition",
+            &manifest
+                .get("edition")
+                .and_then(Value::as_str)
+                .map_or_else(|| Edition::default().to_string(), String::from),
+        );
+        field(
+            "versions",
+            &entries
+                .iter()
+                .map(|e| match e.yanked() {
+                    true => format!("{} (yanked)", e.version()),
+                    false => e.version().to_string(),
+                })
+                .collect::<Vec<String>>()
+                .join(", "),
+        );
+
+        for kind in ["normal", "build", "dev"] {
+            let dependencies = entry
+                .dependencies()
+                .iter()
+                .filter(|d| d.kind == kind)
+                .collect::<Vec<_>>();
+            if dependencies.is_empty() {
+                continue;
+            }
+
+            match kind {
+                "normal" => println!("\ndependencies:"),
+                _ => println!("\n{}-dependencies:", kind),
+            }
+            for dependency in dependencies {
+                match &dependency.version {
+                    Some(version) => println!("  {} = \"{}\"", dependency.name, version),
+                    None => println!("  {} (system)", dependency.name),
+                }
+            }
+        }
+
+        if let Some(features) = manifest.get("features").and_then(Value::as_table)
+            && !features.is_empty()
+        {
+            println!("\nfeatures:");
+            for (feature, enables) in features {
+                println!("  {} = [{}]", feature, strings(Some(enables)).join(", "));
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,1327 @@
+//! A standalone Kconfig parser and `autoconf.h` generator.
+//!
+//! It doesn't depend on the rest of Tailor: [`Kconfig::from_file`] parses a
+//! Kconfig tree, [`parse_config`] reads `.config` and defconfig fragments, and
+//! [`Kconfig::resolve`] applies them, following `depends on`, `select`,
+//! `imply`, `default` and `choice` rules, to produce the values written by
+//! [`Kconfig::config_file`] and [`Kconfig::header`].
+
+use std::{
+    cmp::{max, min},
+    collections::BTreeMap,
+    fmt::{self, Display},
+    path::{Path, PathBuf},
+};
+
+/// The prefix of the symbols in `.config` and `autoconf.h`.
+pub const PREFIX: &str = "CONFIG_";
+
+/// The resolved value of each active symbol: `y`, `m` or `n` for `bool` and
+/// `tristate` symbols, and the value itself for the others. Symbols whose
+/// dependencies aren't met are absent.
+pub type Values = BTreeMap<String, String>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SymbolType {
+    Bool,
+    Tristate,
+    Int,
+    Hex,
+    String,
+}
+
+impl SymbolType {
+    fn is_tristate(&self) -> bool {
+        matches!(self, Self::Bool | Self::Tristate)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CmpOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+/// A Kconfig expression. Words that aren't defined symbols, such as `y` or
+/// `10`, are evaluated as constants.
+#[derive(Debug, Clone)]
+pub enum Expr {
+    Symbol(String),
+    Const(String),
+    Not(Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Cmp(CmpOp, Box<Expr>, Box<Expr>),
+}
+
+impl Expr {
+    /// Writes the expression, with parentheses around the operands of lower
+    /// precedence than `precedence`.
+    fn write(&self, f: &mut fmt::Formatter, precedence: u8) -> fmt::Result {
+        let own = match self {
+            Self::Or(..) => 1,
+            Self::And(..) => 2,
+            _ => 3,
+        };
+        if own < precedence {
+            write!(f, "(")?;
+        }
+        match self {
+            Self::Symbol(name) => write!(f, "{}", name)?,
+            Self::Const(value) => write!(f, "\"{}\"", escape(value))?,
+            Self::Not(expr) => {
+                write!(f, "!")?;
+                expr.write(f, 3)?;
+            }
+            Self::And(a, b) => {
+                a.write(f, 2)?;
+                write!(f, " && ")?;
+                b.write(f, 2)?;
+            }
+            Self::Or(a, b) => {
+                a.write(f, 1)?;
+                write!(f, " || ")?;
+                b.write(f, 1)?;
+            }
+            Self::Cmp(op, a, b) => {
+                let op = match op {
+                    CmpOp::Eq => "=",
+                    CmpOp::Ne => "!=",
+                    CmpOp::Lt => "<",
+                    CmpOp::Le => "<=",
+                    CmpOp::Gt => ">",
+                    CmpOp::Ge => ">=",
+                };
+                a.write(f, 3)?;
+                write!(f, " {} ", op)?;
+                b.write(f, 3)?;
+            }
+        }
+        if own < precedence {
+            write!(f, ")")?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.write(f, 0)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Symbol {
+    name: String,
+    symbol_type: SymbolType,
+    prompt: Option<(String, Option<Expr>)>,
+    defaults: Vec<(Expr, Option<Expr>)>,
+    depends: Vec<Expr>,
+    selects: Vec<(String, Option<Expr>)>,
+    implies: Vec<(String, Option<Expr>)>,
+    ranges: Vec<(Expr, Expr, Option<Expr>)>,
+    help: String,
+    choice: Option<usize>,
+}
+
+impl Symbol {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn symbol_type(&self) -> SymbolType {
+        self.symbol_type
+    }
+
+    /// The text shown in menus. Symbols without a prompt can't be set by the
+    /// user, only by defaults and selects.
+    pub fn prompt(&self) -> Option<&str> {
+        self.prompt.as_ref().map(|(prompt, _)| prompt.as_str())
+    }
+
+    pub fn help(&self) -> &str {
+        &self.help
+    }
+
+    /// The `depends on` expressions, including the ones inherited from the
+    /// enclosing `if`, `menu` and `choice` blocks.
+    pub fn depends(&self) -> &[Expr] {
+        &self.depends
+    }
+
+    /// Makes the attributes of one definition also require `deps`: the ones
+    /// declared before `counts`, the lengths of the defaults, selects, implies
+    /// and ranges when the definition started, when `earlier` is set, or else
+    /// the ones after. The prompt is only restricted when `prompt` is set.
+    fn restrict(
+        &mut self,
+        counts: (usize, usize, usize, usize),
+        earlier: bool,
+        prompt: bool,
+        deps: &Expr,
+    ) {
+        let part = |len: usize, count: usize| if earlier { 0..count } else { count..len };
+        for i in part(self.defaults.len(), counts.0) {
+            restricted(&mut self.defaults[i].1, deps);
+        }
+        for i in part(self.selects.len(), counts.1) {
+            restricted(&mut self.selects[i].1, deps);
+        }
+        for i in part(self.implies.len(), counts.2) {
+            restricted(&mut self.implies[i].1, deps);
+        }
+        for i in part(self.ranges.len(), counts.3) {
+            restricted(&mut self.ranges[i].2, deps);
+        }
+        if prompt && let Some((_, cond)) = &mut self.prompt {
+            restricted(cond, deps);
+        }
+    }
+
+    fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            symbol_type: SymbolType::Bool,
+            prompt: None,
+            defaults: vec![],
+            depends: vec![],
+            selects: vec![],
+            implies: vec![],
+            ranges: vec![],
+            help: String::new(),
+            choice: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Choice {
+    prompt: Option<String>,
+    defaults: Vec<(String, Option<Expr>)>,
+    depends: Vec<Expr>,
+    members: Vec<String>,
+}
+
+impl Choice {
+    pub fn prompt(&self) -> Option<&str> {
+        self.prompt.as_deref()
+    }
+
+    pub fn members(&self) -> &[String] {
+        &self.members
+    }
+
+    pub fn depends(&self) -> &[Expr] {
+        &self.depends
+    }
+}
+
+/// The menu tree, in the order it's declared.
+#[derive(Debug, Clone)]
+pub enum Node {
+    Symbol(String),
+    Menu {
+        prompt: String,
+        depends: Vec<Expr>,
+        children: Vec<Node>,
+    },
+    Choice {
+        index: usize,
+        children: Vec<Node>,
+    },
+    Comment {
+        text: String,
+        depends: Vec<Expr>,
+    },
+}
+
+#[derive(Debug, Default)]
+pub struct Kconfig {
+    title: Option<String>,
+    symbols: Vec<Symbol>,
+    index: BTreeMap<String, usize>,
+    choices: Vec<Choice>,
+    nodes: Vec<Node>,
+}
+
+impl Kconfig {
+    /// Parses the Kconfig file at `path`. `source` paths are relative to the
+    /// folder of this file, and `rsource` paths to the file that has them.
+    pub fn from_file(path: &Path) -> Result<Self, String> {
+        let root = path.parent().unwrap_or(Path::new(".")).to_path_buf();
+
+        let mut kconfig = Kconfig::default();
+        let nodes = kconfig.parse_file(path, &root, &[], &[])?;
+        kconfig.nodes = nodes;
+
+        Ok(kconfig)
+    }
+
+    /// The `mainmenu` title.
+    pub fn title(&self) -> Option<&str> {
+        self.title.as_deref()
+    }
+
+    pub fn nodes(&self) -> &[Node] {
+        &self.nodes
+    }
+
+    /// The symbols, in the order they are declared.
+    pub fn symbols(&self) -> &[Symbol] {
+        &self.symbols
+    }
+
+    pub fn choice(&self, index: usize) -> Option<&Choice> {
+        self.choices.get(index)
+    }
+
+    pub fn symbol(&self, name: &str) -> Option<&Symbol> {
+        self.index.get(name).map(|i| &self.symbols[*i])
+    }
+
+    /// Resolves the value of every symbol, with `user` holding the values
+    /// read from `.config` and defconfig fragments.
+    pub fn resolve(&self, user: &Values) -> Values {
+        let mut values = Values::new();
+
+        // Each pass recomputes every symbol from the previous values, until
+        // nothing changes. Dependencies and selects only move values
+        // forward, so this converges.
+        for _ in 0..=self.symbols.len() + 1 {
+            let next = self.resolve_pass(user, &values);
+            if next == values {
+                break;
+            }
+            values = next;
+        }
+
+        values
+    }
+
+    /// The smallest set of values resolving to `values`: the ones that differ
+    /// from what the defaults and the other values give, as saved in a
+    /// defconfig.
+    pub fn minimal_config(&self, values: &Values) -> Values {
+        let mut minimal = Values::new();
+        let mut resolved = self.resolve(&minimal);
+
+        for symbol in &self.symbols {
+            let value = values.get(&symbol.name);
+            if symbol.prompt.is_none() || value.is_none() || resolved.get(&symbol.name) == value {
+                continue;
+            }
+            minimal.insert(symbol.name.clone(), value.cloned().unwrap_or_default());
+            resolved = self.resolve(&minimal);
+        }
+
+        minimal
+    }
+
+    /// The `.config` file holding `values`.
+    pub fn config_file(&self, values: &Values) -> String {
+        let mut content = "# Automatically generated by Tailor\n".to_string();
+        if let Some(title) = &self.title {
+            content += &format!("# {}\n", title);
+        }
+
+        for symbol in &self.symbols {
+            let Some(value) = values.get(&symbol.name) else {
+                continue;
+            };
+
+            content += &match symbol.symbol_type {
+                SymbolType::Bool | SymbolType::Tristate if value == "n" => {
+                    format!("# {}{} is not set\n", PREFIX, symbol.name)
+                }
+                SymbolType::String => format!("{}{}=\"{}\"\n", PREFIX, symbol.name, escape(value)),
+                _ => format!("{}{}={}\n", PREFIX, symbol.name, value),
+            };
+        }
+
+        content
+    }
+
+    /// The `autoconf.h` header defining `values` for the C sources.
+    pub fn header(&self, values: &Values) -> String {
+        let mut content = "/* Automatically generated by Tailor, do not edit */\n\
+                           #ifndef AUTOCONF_H\n\
+                           #define AUTOCONF_H\n\n"
+            .to_string();
+
+        for symbol in &self.symbols {
+            let Some(value) = values.get(&symbol.name) else {
+                continue;
+            };
+
+            let name = format!("{}{}", PREFIX, symbol.name);
+            content += &match (symbol.symbol_type, value.as_str()) {
+                (SymbolType::Bool | SymbolType::Tristate, "y") => format!("#define {} 1\n", name),
+                (SymbolType::Tristate, "m") => format!("#define {}_MODULE 1\n", name),
+                (SymbolType::Bool | SymbolType::Tristate, _) => continue,
+                (SymbolType::String, value) => format!("#define {} \"{}\"\n", name, escape(value)),
+                (SymbolType::Hex, value) if !value.starts_with("0x") => {
+                    format!("#define {} 0x{}\n", name, value)
+                }
+                (SymbolType::Int | SymbolType::Hex, value) => {
+                    format!("#define {} {}\n", name, value)
+                }
+            };
+        }
+
+        content + "\n#endif\n"
+    }
+
+    fn resolve_pass(&self, user: &Values, values: &Values) -> Values {
+        let mut next = Values::new();
+
+        for symbol in self.symbols.iter().filter(|s| s.choice.is_none()) {
+            let deps = self.eval_all(&symbol.depends, values);
+            let visible = self.visibility(symbol, values);
+            let user_value = user.get(&symbol.name).filter(|_| visible > 0);
+            let default = symbol
+                .defaults
+                .iter()
+                .find(|(_, cond)| cond.as_ref().is_none_or(|c| self.eval(c, values) > 0))
+                .map(|(value, _)| value);
+
+            if !symbol.symbol_type.is_tristate() {
+                if deps == 0 {
+                    continue;
+                }
+
+                let value = match (user_value, default) {
+                    (Some(value), _) => value.clone(),
+                    (None, Some(value)) => self.value_of(value, values),
+                    (None, None) => String::new(),
+                };
+                let value = self.clamp(symbol, value, values);
+                if !value.is_empty() || symbol.symbol_type == SymbolType::String {
+                    next.insert(symbol.name.clone(), value);
+                }
+                continue;
+            }
+
+            let mut value = match (user_value, default) {
+                (Some(value), _) => min(tristate(value), visible),
+                (None, Some(value)) => min(self.eval(value, values), deps),
+                (None, None) => 0,
+            };
+            if user_value.is_none() {
+                value = max(value, min(self.reverse(&symbol.name, true, values), deps));
+            }
+            let selected = self.reverse(&symbol.name, false, values);
+            value = max(value, selected);
+            if symbol.symbol_type == SymbolType::Bool && value == 1 {
+                value = 2;
+            }
+
+            if deps > 0 || selected > 0 {
+                next.insert(symbol.name.clone(), tristate_name(value).to_string());
+            }
+        }
+
+        for choice in &self.choices {
+            if self.eval_all(&choice.depends, values) == 0 {
+                continue;
+            }
+
+            let visible = choice
+                .members
+                .iter()
+                .filter(|m| {
+                    self.symbol(m)
+                        .is_some_and(|s| self.eval_all(&s.depends, values) > 0)
+                })
+                .collect::<Vec<&String>>();
+            let selected = visible
+                .iter()
+                .find(|m| user.get(m.as_str()).is_some_and(|v| v == "y"))
+                .or_else(|| {
+                    choice
+                        .defaults
+                        .iter()
+                        .filter(|(_, cond)| cond.as_ref().is_none_or(|c| self.eval(c, values) > 0))
+                        .find_map(|(name, _)| visible.iter().find(|m| **m == name))
+                })
+                .or(visible.first());
+
+            for member in &visible {
+                let value = if Some(member) == selected { "y" } else { "n" };
+                next.insert(member.to_string(), value.to_string());
+            }
+        }
+
+        next
+    }
+
+    /// The symbols that `select` `name`, with the condition of each select.
+    pub fn selected_by(&self, name: &str) -> Vec<(&str, Option<&Expr>)> {
+        self.symbols
+            .iter()
+            .flat_map(|s| {
+                s.selects
+                    .iter()
+                    .filter(|(target, _)| target == name)
+                    .map(|(_, cond)| (s.name.as_str(), cond.as_ref()))
+            })
+            .collect()
+    }
+
+    /// Whether the user can set `symbol`: its dependencies and the condition
+    /// of its prompt, as a tristate.
+    pub fn visibility(&self, symbol: &Symbol, values: &Values) -> u8 {
+        let deps = self.eval_all(&symbol.depends, values);
+        match &symbol.prompt {
+            Some((_, Some(cond))) => min(deps, self.eval(cond, values)),
+            Some((_, None)) => deps,
+            None => 0,
+        }
+    }
+
+    /// The strongest value given to `name` by the symbols that `select` it,
+    /// or `imply` it when `implied` is true.
+    fn reverse(&self, name: &str, implied: bool, values: &Values) -> u8 {
+        self.symbols
+            .iter()
+            .flat_map(|s| {
+                let targets = if implied { &s.implies } else { &s.selects };
+                targets
+                    .iter()
+                    .filter(|(target, _)| target == name)
+                    .map(move |(_, cond)| {
+                        let by = tristate(values.get(&s.name).map_or("n", String::as_str));
+                        cond.as_ref().map_or(by, |c| min(by, self.eval(c, values)))
+                    })
+            })
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Limits an `int` or `hex` value to the first active `range`.
+    fn clamp(&self, symbol: &Symbol, value: String, values: &Values) -> String {
+        let Some(number) = parse_number(&value) else {
+            return value;
+        };
+        let Some((low, high, _)) = symbol
+            .ranges
+            .iter()
+            .find(|(_, _, cond)| cond.as_ref().is_none_or(|c| self.eval(c, values) > 0))
+        else {
+            return value;
+        };
+
+        let low = parse_number(&self.value_of(low, values));
+        let high = parse_number(&self.value_of(high, values));
+        let clamped = match (low, high) {
+            (Some(low), _) if number < low => low,
+            (_, Some(high)) if number > high => high,
+            _ => return value,
+        };
+
+        match symbol.symbol_type {
+            SymbolType::Hex => format!("0x{:x}", clamped),
+            _ => clamped.to_string(),
+        }
+    }
+
+    /// Evaluates all of `exprs`, as in a list of `depends on`.
+    pub fn eval_all(&self, exprs: &[Expr], values: &Values) -> u8 {
+        exprs
+            .iter()
+            .map(|e| self.eval(e, values))
+            .min()
+            .unwrap_or(2)
+    }
+
+    /// Evaluates `expr` as a tristate: 0 for `n`, 1 for `m` and 2 for `y`.
+    pub fn eval(&self, expr: &Expr, values: &Values) -> u8 {
+        match expr {
+            Expr::Symbol(_) | Expr::Const(_) => tristate(&self.value_of(expr, values)),
+            Expr::Not(expr) => 2 - self.eval(expr, values),
+            Expr::And(a, b) => min(self.eval(a, values), self.eval(b, values)),
+            Expr::Or(a, b) => max(self.eval(a, values), self.eval(b, values)),
+            Expr::Cmp(op, a, b) => {
+                let a = self.value_of(a, values);
+                let b = self.value_of(b, values);
+                let ordering = match (parse_number(&a), parse_number(&b)) {
+                    (Some(a), Some(b)) => a.cmp(&b),
+                    _ => a.cmp(&b),
+                };
+                let result = match op {
+                    CmpOp::Eq => ordering.is_eq(),
+                    CmpOp::Ne => ordering.is_ne(),
+                    CmpOp::Lt => ordering.is_lt(),
+                    CmpOp::Le => ordering.is_le(),
+                    CmpOp::Gt => ordering.is_gt(),
+                    CmpOp::Ge => ordering.is_ge(),
+                };
+                if result { 2 } else { 0 }
+            }
+        }
+    }
+
+    fn value_of(&self, expr: &Expr, values: &Values) -> String {
+        match expr {
+            Expr::Const(value) => value.clone(),
+            Expr::Symbol(name) => match self.symbol(name) {
+                Some(symbol) => values.get(name).cloned().unwrap_or_else(|| {
+                    if symbol.symbol_type.is_tristate() {
+                        "n".to_string()
+                    } else {
+                        String::new()
+                    }
+                }),
+                None => name.clone(),
+            },
+            expr => tristate_name(self.eval(expr, values)).to_string(),
+        }
+    }
+
+    fn symbol_mut(&mut self, name: &str) -> &mut Symbol {
+        let index = match self.index.get(name) {
+            Some(index) => *index,
+            None => {
+                self.symbols.push(Symbol::new(name));
+                self.index.insert(name.to_string(), self.symbols.len() - 1);
+                self.symbols.len() - 1
+            }
+        };
+        &mut self.symbols[index]
+    }
+
+    /// Parses the file at `path`, sourced by the files of `sources`.
+    fn parse_file(
+        &mut self,
+        path: &Path,
+        root: &Path,
+        deps: &[Expr],
+        sources: &[PathBuf],
+    ) -> Result<Vec<Node>, String> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| format!("fail to read file {}: {}", path.display(), e))?;
+
+        let mut parser = Parser::new(path, &content);
+        parser.sources = sources.to_vec();
+        parser
+            .sources
+            .push(path.canonicalize().unwrap_or(path.to_path_buf()));
+        let nodes = self.parse_block(&mut parser, root, deps, None)?;
+        if let Some(line) = parser.next_line() {
+            return Err(parser.error(&format!("unexpected `{}`", line.words.join(" "))));
+        }
+        Ok(nodes)
+    }
+
+    /// Parses entries until the `end` keyword (`endmenu`, `endif`, ...), or
+    /// the end of the file when there's none.
+    fn parse_block(
+        &mut self,
+        parser: &mut Parser,
+        root: &Path,
+        deps: &[Expr],
+        end: Option<&str>,
+    ) -> Result<Vec<Node>, String> {
+        let mut nodes = vec![];
+
+        loop {
+            let Some(mut line) = parser.next_line() else {
+                return match end {
+                    Some(end) => Err(parser.error(&format!("missing `{}`", end))),
+                    None => Ok(nodes),
+                };
+            };
+
+            let keyword = line.keyword();
+            if Some(keyword.as_str()) == end {
+                return Ok(nodes);
+            }
+
+            match keyword.as_str() {
+                "config" | "menuconfig" => {
+                    let name = line.word(parser)?;
+                    self.parse_symbol(parser, &name, deps, None)?;
+                    nodes.push(Node::Symbol(name));
+                }
+                "menu" => {
+                    let prompt = line.string(parser)?;
+                    let mut depends = deps.to_vec();
+                    while let Some(mut attr) = parser.next_attribute() {
+                        match attr.keyword().as_str() {
+                            "depends" => depends.push(attr.depends_on(parser)?),
+                            "visible" => {
+                                attr.expect("if", parser)?;
+                                depends.push(attr.expr(parser)?);
+                            }
+                            other => {
+                                return Err(
+                                    parser.error(&format!("unknown menu attribute `{}`", other))
+                                );
+                            }
+                        }
+                    }
+                    let children = self.parse_block(parser, root, &depends, Some("endmenu"))?;
+                    nodes.push(Node::Menu {
+                        prompt,
+                        depends,
+                        children,
+                    });
+                }
+                "choice" => nodes.push(self.parse_choice(parser, deps)?),
+                "if" => {
+                    let mut depends = deps.to_vec();
+                    depends.push(line.expr(parser)?);
+                    nodes.extend(self.parse_block(parser, root, &depends, Some("endif"))?);
+                }
+                "comment" => nodes.push(parse_comment(parser, &mut line, deps)?),
+                "source" | "rsource" | "osource" | "orsource" => {
+                    let file = expand_env(&line.string(parser)?);
+                    let path = if keyword.ends_with("rsource") {
+                        parser.dir().join(file)
+                    } else {
+                        root.join(file)
+                    };
+                    if keyword.starts_with('o') && !path.exists() {
+                        continue;
+                    }
+                    if parser
+                        .sources
+                        .contains(&path.canonicalize().unwrap_or(path.clone()))
+                    {
+                        return Err(
+                            parser.error(&format!("recursive source of `{}`", path.display()))
+                        );
+                    }
+                    let sources = parser.sources.clone();
+                    nodes.extend(self.parse_file(&path, root, deps, &sources)?);
+                }
+                "mainmenu" => self.title = Some(line.string(parser)?),
+                other => return Err(parser.error(&format!("unknown keyword `{}`", other))),
+            }
+        }
+    }
+
+    fn parse_choice(&mut self, parser: &mut Parser, deps: &[Expr]) -> Result<Node, String> {
+        let index = self.choices.len();
+        let mut choice = Choice {
+            prompt: None,
+            defaults: vec![],
+            depends: deps.to_vec(),
+            members: vec![],
+        };
+
+        while let Some(mut attr) = parser.next_attribute() {
+            match attr.keyword().as_str() {
+                "prompt" => choice.prompt = Some(attr.string(parser)?),
+                "bool" | "tristate" => {
+                    if attr.has_more() {
+                        choice.prompt = Some(attr.string(parser)?);
+                    }
+                }
+                "default" => {
+                    let name = attr.word(parser)?;
+                    choice.defaults.push((name, attr.condition(parser)?));
+                }
+                "depends" => choice.depends.push(attr.depends_on(parser)?),
+                "optional" => {}
+                "help" => {
+                    parser.help();
+                }
+                other => return Err(parser.error(&format!("unknown choice attribute `{}`", other))),
+            }
+        }
+        let depends = choice.depends.clone();
+        self.choices.push(choice);
+
+        let children = self.parse_choice_entries(parser, index, &depends, "endchoice")?;
+        Ok(Node::Choice { index, children })
+    }
+
+    /// Parses the entries of the choice `index` until `end`: its members, and
+    /// the comments and `if` blocks around them.
+    fn parse_choice_entries(
+        &mut self,
+        parser: &mut Parser,
+        index: usize,
+        deps: &[Expr],
+        end: &str,
+    ) -> Result<Vec<Node>, String> {
+        let mut children = vec![];
+        loop {
+            let Some(mut line) = parser.next_line() else {
+                return Err(parser.error(&format!("missing `{}`", end)));
+            };
+            let keyword = line.keyword();
+            if keyword == end {
+                return Ok(children);
+            }
+
+            match keyword.as_str() {
+                "config" => {
+                    let name = line.word(parser)?;
+                    self.parse_symbol(parser, &name, deps, Some(index))?;
+                    self.choices[index].members.push(name.clone());
+                    children.push(Node::Symbol(name));
+                }
+                "comment" => children.push(parse_comment(parser, &mut line, deps)?),
+                "if" => {
+                    let mut depends = deps.to_vec();
+                    depends.push(line.expr(parser)?);
+                    children.extend(self.parse_choice_entries(parser, index, &depends, "endif")?);
+                }
+                other => return Err(parser.error(&format!("unexpected `{}` in choice", other))),
+            }
+        }
+    }
+
+    fn parse_symbol(
+        &mut self,
+        parser: &mut Parser,
+        name: &str,
+        deps: &[Expr],
+        choice: Option<usize>,
+    ) -> Result<(), String> {
+        let is_new = !self.index.contains_key(name);
+        let symbol = self.symbol_mut(name);
+        if is_new {
+            symbol.choice = choice;
+        }
+        let before = (
+            symbol.defaults.len(),
+            symbol.selects.len(),
+            symbol.implies.len(),
+            symbol.ranges.len(),
+        );
+        let mut depends = deps.to_vec();
+        let mut has_prompt = false;
+
+        while let Some(mut attr) = parser.next_attribute() {
+            let keyword = attr.keyword();
+            let symbol_type = match keyword.as_str() {
+                "bool" | "def_bool" => Some(SymbolType::Bool),
+                "tristate" | "def_tristate" => Some(SymbolType::Tristate),
+                "int" => Some(SymbolType::Int),
+                "hex" => Some(SymbolType::Hex),
+                "string" => Some(SymbolType::String),
+                _ => None,
+            };
+
+            if let Some(symbol_type) = symbol_type {
+                symbol.symbol_type = symbol_type;
+                if keyword.starts_with("def_") {
+                    let value = attr.expr(parser)?;
+                    symbol.defaults.push((value, attr.condition(parser)?));
+                } else if attr.has_more() {
+                    let prompt = attr.string(parser)?;
+                    symbol.prompt = Some((prompt, attr.condition(parser)?));
+                    has_prompt = true;
+                }
+                continue;
+            }
+
+            match keyword.as_str() {
+                "prompt" => {
+                    let prompt = attr.string(parser)?;
+                    symbol.prompt = Some((prompt, attr.condition(parser)?));
+                    has_prompt = true;
+                }
+                "default" => {
+                    let value = attr.expr(parser)?;
+                    symbol.defaults.push((value, attr.condition(parser)?));
+                }
+                "depends" => depends.push(attr.depends_on(parser)?),
+                "select" => {
+                    let target = attr.word(parser)?;
+                    symbol.selects.push((target, attr.condition(parser)?));
+                }
+                "imply" => {
+                    let target = attr.word(parser)?;
+                    symbol.implies.push((target, attr.condition(parser)?));
+                }
+                "range" => {
+                    let low = attr.operand(parser)?;
+                    let high = attr.operand(parser)?;
+                    symbol.ranges.push((low, high, attr.condition(parser)?));
+                }
+                "help" | "---help---" => symbol.help = parser.help(),
+                "option" | "modules" | "transitional" => {}
+                other => return Err(parser.error(&format!("unknown config attribute `{}`", other))),
+            }
+        }
+
+        if is_new {
+            symbol.depends = depends;
+            return Ok(());
+        }
+
+        // Each definition of a symbol only gives its attributes under its own
+        // dependencies, and the symbol is available when any definition is.
+        let old = all(&symbol.depends);
+        let new = all(&depends);
+        if let Some(old) = &old {
+            symbol.restrict(before, true, !has_prompt, old);
+        }
+        if let Some(new) = &new {
+            symbol.restrict(before, false, has_prompt, new);
+        }
+        symbol.depends = match (old, new) {
+            (Some(old), Some(new)) => vec![Expr::Or(Box::new(old), Box::new(new))],
+            _ => vec![],
+        };
+
+        Ok(())
+    }
+}
+
+/// Parses a `comment` entry, whose text is on `line`.
+fn parse_comment(parser: &mut Parser, line: &mut Line, deps: &[Expr]) -> Result<Node, String> {
+    let text = line.string(parser)?;
+    let mut depends = deps.to_vec();
+    while let Some(mut attr) = parser.next_attribute() {
+        match attr.keyword().as_str() {
+            "depends" => depends.push(attr.depends_on(parser)?),
+            other => {
+                return Err(parser.error(&format!("unknown comment attribute `{}`", other)));
+            }
+        }
+    }
+    Ok(Node::Comment { text, depends })
+}
+
+/// All of `exprs`, or `None` when there are none.
+fn all(exprs: &[Expr]) -> Option<Expr> {
+    exprs
+        .iter()
+        .cloned()
+        .reduce(|a, b| Expr::And(Box::new(a), Box::new(b)))
+}
+
+/// `cond` with `deps` also required.
+fn restricted(cond: &mut Option<Expr>, deps: &Expr) {
+    *cond = Some(match cond.take() {
+        Some(cond) => Expr::And(Box::new(cond), Box::new(deps.clone())),
+        None => deps.clone(),
+    });
+}
+
+/// Reads the values of a `.config` file or a defconfig fragment:
+/// `CONFIG_<NAME>=<value>` and `# CONFIG_<NAME> is not set` lines.
+pub fn parse_config(content: &str) -> Result<Values, String> {
+    let mut values = Values::new();
+
+    for (number, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if let Some(name) = line
+            .strip_prefix("# ")
+            .and_then(|l| l.strip_suffix(" is not set"))
+            .and_then(|l| l.strip_prefix(PREFIX))
+        {
+            values.insert(name.to_string(), "n".to_string());
+            continue;
+        }
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let (name, value) = line
+            .strip_prefix(PREFIX)
+            .and_then(|l| l.split_once('='))
+            .ok_or_else(|| format!("line {}: invalid config `{}`", number + 1, line))?;
+        let value = match value.strip_prefix('"').and_then(|v| v.strip_suffix('"')) {
+            Some(value) => unescape(value),
+            None => value.to_string(),
+        };
+        values.insert(name.to_string(), value);
+    }
+
+    Ok(values)
+}
+
+fn tristate(value: &str) -> u8 {
+    match value {
+        "y" => 2,
+        "m" => 1,
+        _ => 0,
+    }
+}
+
+fn tristate_name(value: u8) -> &'static str {
+    match value {
+        2 => "y",
+        1 => "m",
+        _ => "n",
+    }
+}
+
+fn parse_number(value: &str) -> Option<i64> {
+    match value
+        .strip_prefix("0x")
+        .or_else(|| value.strip_prefix("0X"))
+    {
+        Some(hex) => i64::from_str_radix(hex, 16).ok(),
+        None => value.parse().ok(),
+    }
+}
+
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+fn unescape(value: &str) -> String {
+    let mut result = String::new();
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => result.extend(chars.next()),
+            c => result.push(c),
+        }
+    }
+    result
+}
+
+/// Replaces `$(VAR)` by the environment variable `VAR`, as used by `source`.
+fn expand_env(value: &str) -> String {
+    let mut result = String::new();
+    let mut rest = value;
+    while let Some(start) = rest.find("$(") {
+        let Some(end) = rest[start..].find(')') else {
+            break;
+        };
+        result += &rest[..start];
+        result += &std::env::var(&rest[start + 2..start + end]).unwrap_or_default();
+        rest = &rest[start + end + 1..];
+    }
+    result + rest
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Word(String),
+    Str(String),
+    Op(&'static str),
+}
+
+/// The tokens of a logical line, consumed from the front.
+struct Line {
+    words: Vec<String>,
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Line {
+    fn keyword(&mut self) -> String {
+        match self.tokens.get(self.pos) {
+            Some(Token::Word(word)) => {
+                self.pos += 1;
+                word.clone()
+            }
+            _ => String::new(),
+        }
+    }
+
+    fn has_more(&self) -> bool {
+        self.pos < self.tokens.len()
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn word(&mut self, parser: &Parser) -> Result<String, String> {
+        match self.next() {
+            Some(Token::Word(word)) => Ok(word),
+            _ => Err(parser.error("expected a name")),
+        }
+    }
+
+    fn string(&mut self, parser: &Parser) -> Result<String, String> {
+        match self.next() {
+            Some(Token::Str(value)) | Some(Token::Word(value)) => Ok(value),
+            _ => Err(parser.error("expected a string")),
+        }
+    }
+
+    fn expect(&mut self, word: &str, parser: &Parser) -> Result<(), String> {
+        match self.next() {
+            Some(Token::Word(w)) if w == word => Ok(()),
+            _ => Err(parser.error(&format!("expected `{}`", word))),
+        }
+    }
+
+    fn depends_on(&mut self, parser: &Parser) -> Result<Expr, String> {
+        self.expect("on", parser)?;
+        self.expr(parser)
+    }
+
+    /// The optional `if <expr>` ending an attribute.
+    fn condition(&mut self, parser: &Parser) -> Result<Option<Expr>, String> {
+        if !self.has_more() {
+            return Ok(None);
+        }
+        self.expect("if", parser)?;
+        let expr = self.expr(parser)?;
+        if self.has_more() {
+            return Err(parser.error("unexpected tokens after the condition"));
+        }
+        Ok(Some(expr))
+    }
+
+    /// An expression, stopping before a trailing `if`.
+    fn expr(&mut self, parser: &Parser) -> Result<Expr, String> {
+        let mut expr = self.and_expr(parser)?;
+        while self.peek() == Some(&Token::Op("||")) {
+            self.pos += 1;
+            expr = Expr::Or(Box::new(expr), Box::new(self.and_expr(parser)?));
+        }
+        Ok(expr)
+    }
+
+    fn and_expr(&mut self, parser: &Parser) -> Result<Expr, String> {
+        let mut expr = self.unary_expr(parser)?;
+        while self.peek() == Some(&Token::Op("&&")) {
+            self.pos += 1;
+            expr = Expr::And(Box::new(expr), Box::new(self.unary_expr(parser)?));
+        }
+        Ok(expr)
+    }
+
+    fn unary_expr(&mut self, parser: &Parser) -> Result<Expr, String> {
+        match self.peek() {
+            Some(Token::Op("!")) => {
+                self.pos += 1;
+                Ok(Expr::Not(Box::new(self.unary_expr(parser)?)))
+            }
+            Some(Token::Op("(")) => {
+                self.pos += 1;
+                let expr = self.expr(parser)?;
+                match self.next() {
+                    Some(Token::Op(")")) => Ok(expr),
+                    _ => Err(parser.error("expected `)`")),
+                }
+            }
+            _ => {
+                let operand = self.operand(parser)?;
+                let op = match self.peek() {
+                    Some(Token::Op("=")) => CmpOp::Eq,
+                    Some(Token::Op("!=")) => CmpOp::Ne,
+                    Some(Token::Op("<")) => CmpOp::Lt,
+                    Some(Token::Op("<=")) => CmpOp::Le,
+                    Some(Token::Op(">")) => CmpOp::Gt,
+                    Some(Token::Op(">=")) => CmpOp::Ge,
+                    _ => return Ok(operand),
+                };
+                self.pos += 1;
+                Ok(Expr::Cmp(
+                    op,
+                    Box::new(operand),
+                    Box::new(self.operand(parser)?),
+                ))
+            }
+        }
+    }
+
+    fn operand(&mut self, parser: &Parser) -> Result<Expr, String> {
+        match self.next() {
+            Some(Token::Word(word)) if word != "if" => Ok(Expr::Symbol(word)),
+            Some(Token::Str(value)) => Ok(Expr::Const(value)),
+            _ => Err(parser.error("expected a symbol or a value")),
+        }
+    }
+}
+
+struct Parser {
+    path: PathBuf,
+    lines: Vec<(usize, String)>,
+    pos: usize,
+    /// The files being parsed, from the root one to this one, so a file
+    /// sourcing itself is an error instead of an endless recursion.
+    sources: Vec<PathBuf>,
+}
+
+/// Keywords starting a new entry, which end the attributes of the previous
+/// one.
+const ENTRY_KEYWORDS: &[&str] = &[
+    "config",
+    "menuconfig",
+    "choice",
+    "endchoice",
+    "menu",
+    "endmenu",
+    "if",
+    "endif",
+    "comment",
+    "source",
+    "rsource",
+    "osource",
+    "orsource",
+    "mainmenu",
+];
+
+impl Parser {
+    /// Splits `content` into logical lines, joining the ones ending with `\`.
+    fn new(path: &Path, content: &str) -> Self {
+        let mut lines: Vec<(usize, String)> = vec![];
+        let mut continued = false;
+        for (number, line) in content.lines().enumerate() {
+            if continued && let Some((_, last)) = lines.last_mut() {
+                last.push(' ');
+                last.push_str(line.trim_end_matches('\\'));
+            } else {
+                lines.push((number + 1, line.trim_end_matches('\\').to_string()));
+            }
+            continued = line.ends_with('\\');
+        }
+
+        Self {
+            path: path.to_path_buf(),
+            lines,
+            pos: 0,
+            sources: vec![],
+        }
+    }
+
+    fn dir(&self) -> PathBuf {
+        self.path.parent().unwrap_or(Path::new(".")).to_path_buf()
+    }
+
+    fn error(&self, message: &str) -> String {
+        let number = self
+            .lines
+            .get(self.pos.saturating_sub(1))
+            .map_or(0, |(n, _)| *n);
+        format!("{}:{}: {}", self.path.display(), number, message)
+    }
+
+    fn peek_line(&self) -> Option<Line> {
+        self.lines[self.pos..]
+            .iter()
+            .map(|(_, line)| tokenize(line))
+            .find(|line| line.has_more())
+    }
+
+    /// The next non-empty line.
+    fn next_line(&mut self) -> Option<Line> {
+        while let Some((_, line)) = self.lines.get(self.pos) {
+            self.pos += 1;
+            let line = tokenize(line);
+            if line.has_more() {
+                return Some(line);
+            }
+        }
+        None
+    }
+
+    /// The next line if it's an attribute of the current entry.
+    fn next_attribute(&mut self) -> Option<Line> {
+        let mut line = self.peek_line()?;
+        if ENTRY_KEYWORDS.contains(&line.keyword().as_str()) {
+            return None;
+        }
+        self.next_line()
+    }
+
+    /// Consumes the text of a `help` attribute: the lines indented more than
+    /// the attribute, or empty.
+    fn help(&mut self) -> String {
+        let indent = |line: &str| line.len() - line.trim_start().len();
+
+        let mut text = vec![];
+        let mut help_indent = None;
+        while let Some((_, line)) = self.lines.get(self.pos) {
+            if line.trim().is_empty() {
+                text.push(String::new());
+                self.pos += 1;
+                continue;
+            }
+            let line_indent = indent(line);
+            if help_indent.is_some_and(|i| line_indent < i) {
+                break;
+            }
+            // The first line of the help text defines its indentation, and
+            // it must be indented more than the entry keywords.
+            if help_indent.is_none() {
+                if line_indent == 0 {
+                    break;
+                }
+                help_indent = Some(line_indent);
+            }
+            text.push(line.trim().to_string());
+            self.pos += 1;
+        }
+
+        text.join("\n").trim().to_string()
+    }
+}
+
+fn tokenize(line: &str) -> Line {
+    let mut tokens = vec![];
+    let mut chars = line.char_indices().peekable();
+
+    while let Some((start, c)) = chars.next() {
+        match c {
+            '#' => break,
+            c if c.is_whitespace() => {}
+            '"' | '\'' => {
+                let mut value = String::new();
+                while let Some((_, next)) = chars.next() {
+                    match next {
+                        '\\' => value.extend(chars.next().map(|(_, c)| c)),
+                        next if next == c => break,
+                        next => value.push(next),
+                    }
+                }
+                tokens.push(Token::Str(value));
+            }
+            '&' | '|' if chars.peek().is_some_and(|(_, next)| *next == c) => {
+                chars.next();
+                tokens.push(Token::Op(if c == '&' { "&&" } else { "||" }));
+            }
+            '!' | '<' | '>' if chars.peek().is_some_and(|(_, next)| *next == '=') => {
+                chars.next();
+                tokens.push(Token::Op(match c {
+                    '!' => "!=",
+                    '<' => "<=",
+                    _ => ">=",
+                }));
+            }
+            '!' => tokens.push(Token::Op("!")),
+            '<' => tokens.push(Token::Op("<")),
+            '>' => tokens.push(Token::Op(">")),
+            '=' => tokens.push(Token::Op("=")),
+            '(' => tokens.push(Token::Op("(")),
+            ')' => tokens.push(Token::Op(")")),
+            _ => {
+                let mut end = start + c.len_utf8();
+                while let Some((i, next)) = chars.peek() {
+                    if next.is_whitespace() || "\"'#&|!<>=()".contains(*next) {
+                        break;
+                    }
+                    end = i + next.len_utf8();
+                    chars.next();
+                }
+                tokens.push(Token::Word(line[start..end].to_string()));
+            }
+        }
+    }
+
+    Line {
+        words: tokens
+            .iter()
+            .map(|t| match t {
+                Token::Word(w) | Token::Str(w) => w.clone(),
+                Token::Op(op) => op.to_string(),
+            })
+            .collect(),
+        tokens,
+        pos: 0,
+    }
+}
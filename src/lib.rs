@@ -0,0 +1,5 @@
+//! The parts of Tailor usable without it, such as the Kconfig parser.
+
+#![deny(warnings)]
+
+pub mod kconfig;
@@ -0,0 +1,202 @@
+use std::path::Path;
+
+use toml::{Table, Value};
+
+use crate::dependency::{Dependency, Source};
+
+const HEADER: &str = "# This file is automatically generated by Tailor.
+# It is not intended for manual editing.
+
+";
+
+/// A downloaded dependency pinned to the commit it was checked out at, or to
+/// the version resolved from its requirement and the checksum of its tarball
+/// for registry dependencies.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LockedDependency {
+    name: String,
+    source: String,
+    reference: String,
+    version: Option<String>,
+    commit: Option<String>,
+    checksum: Option<String>,
+}
+
+impl LockedDependency {
+    pub fn commit(&self) -> Option<&str> {
+        self.commit.as_deref()
+    }
+
+    /// The exact version of a registry dependency.
+    pub fn version(&self) -> Option<&str> {
+        self.version.as_deref()
+    }
+
+    pub fn checksum(&self) -> Option<&str> {
+        self.checksum.as_deref()
+    }
+
+    /// The identity of a dependency in the lockfile: its source and the
+    /// version or revision it asks for. Local and system dependencies aren't
+    /// locked.
+    fn source_of(dependency: &Dependency) -> Option<(String, String)> {
+        match dependency.source() {
+            Source::Registry { version } => Some(("registry".to_string(), version.clone())),
+            Source::Git { url, revision } => Some((format!("git+{}", url), revision.clone())),
+            Source::Local { .. } | Source::System { .. } => None,
+        }
+    }
+
+    fn matches(&self, dependency: &Dependency) -> bool {
+        Self::source_of(dependency).is_some_and(|(source, reference)| {
+            self.name == dependency.name() && self.source == source && self.reference == reference
+        })
+    }
+
+    fn from_content(content: &Value) -> Option<Self> {
+        let table = content.as_table()?;
+        let field = |key: &str| table.get(key).and_then(Value::as_str).map(String::from);
+
+        let (commit, checksum) = (field("commit"), field("checksum"));
+        if commit.is_none() && checksum.is_none() {
+            return None;
+        }
+
+        let (source, reference) = (field("source")?, field("reference")?);
+        // Registry dependencies used to require an exact version, which is
+        // the version locked by the lockfiles written before requirements.
+        let version = match &checksum {
+            Some(_) => field("version").or_else(|| Some(reference.clone())),
+            None => None,
+        };
+
+        Some(Self {
+            name: field("name")?,
+            source,
+            reference,
+            version,
+            commit,
+            checksum,
+        })
+    }
+
+    fn to_content(&self) -> Value {
+        let mut table = Table::new();
+        table.insert("name".to_string(), Value::String(self.name.clone()));
+        table.insert("source".to_string(), Value::String(self.source.clone()));
+        table.insert(
+            "reference".to_string(),
+            Value::String(self.reference.clone()),
+        );
+        if let Some(version) = &self.version {
+            table.insert("version".to_string(), Value::String(version.clone()));
+        }
+        if let Some(commit) = &self.commit {
+            table.insert("commit".to_string(), Value::String(commit.clone()));
+        }
+        if let Some(checksum) = &self.checksum {
+            table.insert("checksum".to_string(), Value::String(checksum.clone()));
+        }
+        Value::Table(table)
+    }
+}
+
+/// What a dependency is locked to.
+pub enum Pin<'a> {
+    Commit(&'a str),
+    Tarball { version: &'a str, checksum: &'a str },
+}
+
+/// The `Tailor.lock` file, shared by all the packages of a workspace.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Lockfile {
+    dependencies: Vec<LockedDependency>,
+}
+
+impl Lockfile {
+    pub fn find(&self, dependency: &Dependency) -> Option<&LockedDependency> {
+        self.dependencies.iter().find(|d| d.matches(dependency))
+    }
+
+    /// Pins `dependency` to `pin`: the commit for Git dependencies, or the
+    /// version resolved from the requirement and the checksum of its tarball
+    /// for registry ones.
+    pub fn lock(&mut self, dependency: &Dependency, pin: Pin) {
+        let Some((source, reference)) = LockedDependency::source_of(dependency) else {
+            return;
+        };
+        let (version, commit, checksum) = match pin {
+            Pin::Commit(commit) => (None, Some(commit.to_string()), None),
+            Pin::Tarball { version, checksum } => {
+                (Some(version.to_string()), None, Some(checksum.to_string()))
+            }
+        };
+
+        self.dependencies.retain(|d| !d.matches(dependency));
+        self.dependencies.push(LockedDependency {
+            name: dependency.name().to_string(),
+            source,
+            reference,
+            version,
+            commit,
+            checksum,
+        });
+        self.dependencies
+            .sort_by(|a, b| (&a.name, &a.source).cmp(&(&b.name, &b.source)));
+    }
+
+    /// Removes the entries no longer required by any of `dependencies`.
+    pub fn retain(&mut self, dependencies: &[Dependency]) {
+        self.dependencies
+            .retain(|d| dependencies.iter().any(|dep| d.matches(dep)));
+    }
+
+    /// Loads the lockfile at `filepath`. A missing file is an empty lockfile.
+    pub fn load(filepath: &Path) -> Result<Self, String> {
+        if !filepath.exists() {
+            return Ok(Self::default());
+        }
+
+        let content = std::fs::read_to_string(filepath)
+            .map_err(|e| format!("fail to read file {}: {}", filepath.display(), e))?;
+
+        Self::from_content(&content)
+            .map_err(|e| format!("invalid lockfile {}: {}", filepath.display(), e))
+    }
+
+    pub fn save(&self, filepath: &Path) -> Result<(), String> {
+        std::fs::write(filepath, self.to_content())
+            .map_err(|e| format!("fail to write {}: {}", filepath.display(), e))
+    }
+
+    pub fn from_content(content: &str) -> Result<Self, String> {
+        let parsed = content
+            .parse::<Table>()
+            .map_err(|_| "Invalid Toml format".to_string())?;
+
+        let dependencies = parsed
+            .get("dependency")
+            .and_then(Value::as_array)
+            .map_or(Some(vec![]), |deps| {
+                deps.iter().map(LockedDependency::from_content).collect()
+            })
+            .ok_or("invalid dependency entry")?;
+
+        Ok(Self { dependencies })
+    }
+
+    pub fn to_content(&self) -> String {
+        let mut table = Table::new();
+        table.insert(
+            "dependency".to_string(),
+            Value::Array(
+                self.dependencies
+                    .iter()
+                    .map(LockedDependency::to_content)
+                    .collect(),
+            ),
+        );
+
+        format!("{}{}", HEADER, table)
+    }
+}
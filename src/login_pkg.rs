@@ -0,0 +1,88 @@
+use std::io::BufRead;
+
+use crate::{
+    command::{Command, parse_flag_value},
+    credentials::Credentials,
+    fmt::success,
+    registry::Registry,
+};
+
+#[derive(Default)]
+pub struct LoginPkg {
+    registry: Option<String>,
+    git_host: Option<String>,
+    token: Option<String>,
+}
+
+impl LoginPkg {
+    /// The token given in the command line, or else read from stdin, which
+    /// keeps it out of the shell history.
+    fn read_token(&self, target: &str) -> Result<String, String> {
+        let token = match &self.token {
+            Some(token) => token.clone(),
+            None => {
+                println!("please paste the token for {} below", target);
+                let mut line = String::new();
+                std::io::stdin()
+                    .lock()
+                    .read_line(&mut line)
+                    .map_err(|e| format!("fail to read the token: {}", e))?;
+                line.trim().to_string()
+            }
+        };
+
+        if token.is_empty() {
+            return Err("the token is empty".to_string());
+        }
+        Ok(token)
+    }
+}
+
+impl Command for LoginPkg {
+    fn parse_args(&mut self, args: &[String]) -> Option<()> {
+        if args.is_empty() || args[0] != "login" {
+            return None;
+        }
+
+        let mut i = 1;
+        while i < args.len() {
+            if let Some((registry, consumed)) = parse_flag_value(&args[i..], &["--registry"]) {
+                self.registry = Some(registry.to_string());
+                i += consumed;
+                continue;
+            }
+            if let Some((host, consumed)) = parse_flag_value(&args[i..], &["--git"]) {
+                self.git_host = Some(host.to_string());
+                i += consumed;
+                continue;
+            }
+            if args[i].starts_with('-') || self.token.is_some() {
+                return None;
+            }
+            self.token = Some(args[i].clone());
+            i += 1;
+        }
+
+        (self.registry.is_none() || self.git_host.is_none()).then_some(())
+    }
+
+    fn execute(&self) -> Result<(), String> {
+        match &self.git_host {
+            Some(host) => {
+                let token = self.read_token(host)?;
+                let mut credentials = Credentials::load()?;
+                credentials.set_git_token(host, &token);
+                credentials.save()?;
+                println!("{} token for {}", success("Saved"), host);
+            }
+            None => {
+                let registry = Registry::named(self.registry.as_deref())?;
+                let token = self.read_token(registry.url())?;
+                registry.login(&token)?;
+                println!("{} token for {}", success("Saved"), registry.url());
+            }
+        }
+
+        Ok(())
+    }
+}
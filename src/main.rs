@@ -1,26 +1,75 @@
 #![deny(warnings)]
 
 mod build_pkg;
+mod cfg;
+mod clean_pkg;
 mod cmake;
 mod command;
+mod config;
+mod config_pkg;
+mod config_schema;
+mod credentials;
+mod debug_pkg;
 mod dependency;
 mod dependency_manager;
+mod edition;
+mod features;
+mod fix_pkg;
 mod fmt;
 mod git;
+mod info_pkg;
+mod lockfile;
+mod login_pkg;
+mod menuconfig_pkg;
+mod mirror;
 mod mode;
+mod network;
 mod new_pkg;
+mod owner_pkg;
 mod package;
+mod package_pkg;
+mod pkg_config;
+mod profile;
+mod publish_pkg;
+mod registry;
 mod run_pkg;
+mod sdk;
+mod search_pkg;
+mod sources;
+mod target;
+mod test_pkg;
+mod version;
+mod workspace;
+mod yank_pkg;
 
 use std::env::args;
 
-use crate::{build_pkg::BuildPkg, command::Command, fmt::error, new_pkg::NewPkg, run_pkg::RunPkg};
+use crate::{
+    build_pkg::BuildPkg, clean_pkg::CleanPkg, command::Command, config_pkg::ConfigPkg,
+    debug_pkg::DebugPkg, fix_pkg::FixPkg, fmt::error, info_pkg::InfoPkg, login_pkg::LoginPkg,
+    menuconfig_pkg::MenuconfigPkg, new_pkg::NewPkg, owner_pkg::OwnerPkg, package_pkg::PackagePkg,
+    publish_pkg::PublishPkg, run_pkg::RunPkg, search_pkg::SearchPkg, test_pkg::TestPkg,
+    yank_pkg::YankPkg,
+};
 
 fn main() {
     let commands: &mut [&mut dyn Command] = &mut [
         &mut NewPkg::default(),
         &mut BuildPkg::default(),
         &mut RunPkg::default(),
+        &mut TestPkg::default(),
+        &mut DebugPkg::default(),
+        &mut ConfigPkg::default(),
+        &mut MenuconfigPkg::default(),
+        &mut FixPkg::default(),
+        &mut PackagePkg::default(),
+        &mut PublishPkg::default(),
+        &mut SearchPkg::default(),
+        &mut InfoPkg::default(),
+        &mut LoginPkg::default(),
+        &mut YankPkg::default(),
+        &mut OwnerPkg::default(),
+        &mut CleanPkg::default(),
     ];
     let args = args().collect::<Vec<String>>();
 
@@ -29,6 +78,7 @@ fn main() {
             let res = cmd.execute();
             if let Err(e) = res {
                 eprintln!("\n{}: {}", error(), e);
+                std::process::exit(1);
             }
             return;
         }
@@ -37,12 +87,62 @@ fn main() {
     println!("C language package manager\n");
     println!("Usage: tailor [COMMAND] [OPTIONS] <path>\n");
     println!("Options:");
-    println!("  --bin       Create a binary package (only for `new` command) (default)");
-    println!("  --lib       Create a library package (only for `new` command)");
-    println!("  --debug     Build (or run) in debug mode (default)");
-    println!("  --release   Build (or run) in release mode\n");
+    println!(
+        "  --bin                   Create a binary package (only for `new` command) (default)"
+    );
+    println!("  --lib                   Create a library package (only for `new` command)");
+    println!("  --sdk                   Create a package built by an SDK (only for `new` command)");
+    println!("  --debug                 Build (or run) in debug mode (default)");
+    println!("  --release               Build (or run) in release mode");
+    println!("  --target <name>         Build (or run) for a target from Tailor.toml or config");
+    println!(
+        "  --target-dir <dir>      Create the build files in <dir>/<package> instead of build/"
+    );
+    println!("  -p, --package <name>    Select a package of the workspace by name");
+    println!("  --workspace             Select all the packages of the workspace");
+    println!("  --exclude <name>        Exclude a package selected by `--workspace`");
+    println!("  -F, --features <list>   Enable the comma separated features");
+    println!("  --all-features          Enable all the features");
+    println!("  --no-default-features   Don't enable the `default` feature");
+    println!("  --runner <cmd>          Run the binary through <cmd> (only for `run` and `test`)");
+    println!(
+        "  --debugger <cmd>        Debugger to launch, gdb or lldb (only for `debug` command)"
+    );
+    println!(
+        "  --defconfig <file>      Configure from a defconfig instead of .config (only for `config`)"
+    );
+    println!("  --export-kconfig <file> Write the [config] options as Kconfig (only for `config`)");
+    println!("  --edition <edition>     Edition to migrate the manifest to (only for `fix`)");
+    println!("  --dry-run               Show the changes without writing them (only for `fix`)");
+    println!(
+        "  --no-verify             Don't build the tarball (only for `package` and `publish`)"
+    );
+    println!("  --allow-dirty           Publish with uncommitted changes (only for `publish`)");
+    println!("  --token <token>         Registry token (only for `publish`, `yank` and `owner`)");
+    println!("  --registry <name>       Registry of the [registries] table of the config");
+    println!("  --git <host>            Save the token of a Git host (only for `login`)");
+    println!("  --undo                  Undo a yank (only for `yank`)");
+    println!("  -a, --add <user>        Add an owner (only for `owner`)");
+    println!("  -r, --remove <user>     Remove an owner (only for `owner`)");
+    println!("  -l, --list              List the owners (only for `owner`)");
+    println!(
+        "  --limit <n>             Number of packages to list, 10 by default (only for `search`)\n"
+    );
     println!("Commands:");
-    println!("  new         Create a new package");
-    println!("  build       Build the package");
-    println!("  run         Run the package");
+    println!("  new                     Create a new package");
+    println!("  build                   Build the package");
+    println!("  run                     Run the package");
+    println!("  test                    Build and run the tests of the package");
+    println!("  debug                   Build in debug mode and launch the debugger");
+    println!("  config                  Resolve the Kconfig and [config] options into headers");
+    println!("  menuconfig              Edit the Kconfig options in the terminal");
+    println!("  fix                     Migrate the manifest to a newer edition");
+    println!("  package                 Create a .tar.gz of the package to publish");
+    println!("  publish                 Upload the package to the registry");
+    println!("  search <query>          Search packages in the registry");
+    println!("  info <name>[@version]   Show a package of the registry");
+    println!("  login [token]           Save the token of a registry");
+    println!("  yank <name>@<version>   Retract a published version from the registry");
+    println!("  owner [name]            Manage who may publish a package in the registry");
+    println!("  clean                   Remove the build files");
 }
@@ -0,0 +1,1058 @@
+use std::{
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+use crossterm::{
+    cursor::{Hide, MoveTo, Show},
+    event::{self, Event, KeyCode, KeyEventKind, KeyModifiers},
+    execute, queue,
+    style::{Attribute, Print, SetAttribute},
+    terminal::{self, Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen},
+};
+use tailor_rs::kconfig::{Kconfig, Node, SymbolType, Values};
+
+use crate::{
+    build_pkg::BuildPkg,
+    command::Command,
+    config_pkg::{resolve_config, write_header},
+    fmt::success,
+    package::Package,
+};
+
+#[derive(Default)]
+pub struct MenuconfigPkg {
+    build: BuildPkg,
+}
+
+/// A menu on the screen, with the selected entry and the first one shown.
+struct Frame {
+    title: String,
+    nodes: Vec<Node>,
+    /// The choice whose members are the entries, if it's a choice.
+    choice: Option<usize>,
+    /// Search results list hidden symbols too.
+    search: bool,
+    cursor: usize,
+    offset: usize,
+}
+
+impl Frame {
+    fn new(title: &str, nodes: Vec<Node>) -> Self {
+        Self {
+            title: title.to_string(),
+            nodes,
+            choice: None,
+            search: false,
+            cursor: 0,
+            offset: 0,
+        }
+    }
+}
+
+/// Restores the terminal when the editor exits, even on errors.
+struct Screen;
+
+impl Screen {
+    fn enter() -> io::Result<Self> {
+        terminal::enable_raw_mode()?;
+        execute!(io::stdout(), EnterAlternateScreen, Hide)?;
+        Ok(Self)
+    }
+}
+
+impl Drop for Screen {
+    fn drop(&mut self) {
+        let _ = execute!(io::stdout(), Show, LeaveAlternateScreen);
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+/// The interactive editor of the Kconfig options of a package.
+struct Menuconfig<'a> {
+    kconfig: &'a Kconfig,
+    user: Values,
+    values: Values,
+    frames: Vec<Frame>,
+    show_all: bool,
+    modified: bool,
+    saved: bool,
+    status: String,
+    dot_config: PathBuf,
+    defconfig: PathBuf,
+    build_dir: PathBuf,
+}
+
+const HINTS: &str = "Enter: open/edit  Space: toggle  ?: help  /: search  z: show all  \
+                     s: save  D: save defconfig  Esc: back  q: quit";
+
+fn tristate_name(value: u8) -> &'static str {
+    ["n", "m", "y"][value.min(2) as usize]
+}
+
+/// The value typed for an `int`, `hex` or `string` symbol, with the `0x` of
+/// an hexadecimal number.
+fn parse_value(symbol_type: SymbolType, value: &str) -> Result<String, String> {
+    let value = value.trim();
+    match symbol_type {
+        SymbolType::Int if value.parse::<i64>().is_err() => {
+            Err(format!("`{}` is not an integer", value))
+        }
+        SymbolType::Hex => {
+            let digits = value.trim_start_matches("0x").trim_start_matches("0X");
+            if i64::from_str_radix(digits, 16).is_err() {
+                return Err(format!("`{}` is not an hexadecimal number", value));
+            }
+            Ok(format!("0x{}", digits))
+        }
+        _ => Ok(value.to_string()),
+    }
+}
+
+impl<'a> Menuconfig<'a> {
+    /// The editor of `values` in the menu `title`, saving `.config` and
+    /// `defconfig` in `dir`, and `autoconf.h` in `build_dir`.
+    fn new(
+        kconfig: &'a Kconfig,
+        values: Values,
+        title: &str,
+        dir: &Path,
+        build_dir: PathBuf,
+    ) -> Self {
+        Self {
+            kconfig,
+            user: values.clone(),
+            values,
+            frames: vec![Frame::new(title, kconfig.nodes().to_vec())],
+            show_all: false,
+            modified: false,
+            saved: false,
+            status: String::new(),
+            dot_config: dir.join(".config"),
+            defconfig: dir.join("defconfig"),
+            build_dir,
+        }
+    }
+
+    /// Runs the editor until the user quits. Returns whether the
+    /// configuration was saved.
+    fn run(&mut self) -> io::Result<bool> {
+        let _screen = Screen::enter()?;
+        let mut out = io::stdout();
+
+        loop {
+            self.draw(&mut out)?;
+            let Event::Key(key) = event::read()? else {
+                continue;
+            };
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+            self.status.clear();
+
+            let len = self.entries().len();
+            let rows = self.rows()?;
+            let frame = self
+                .frames
+                .last_mut()
+                .expect("the main menu is never closed");
+            match key.code {
+                KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    return Ok(self.saved);
+                }
+                KeyCode::Up | KeyCode::Char('k') => frame.cursor = frame.cursor.saturating_sub(1),
+                KeyCode::Down | KeyCode::Char('j') => {
+                    frame.cursor = (frame.cursor + 1).min(len.saturating_sub(1))
+                }
+                KeyCode::PageUp => frame.cursor = frame.cursor.saturating_sub(rows),
+                KeyCode::PageDown => {
+                    frame.cursor = (frame.cursor + rows).min(len.saturating_sub(1))
+                }
+                KeyCode::Home => frame.cursor = 0,
+                KeyCode::End => frame.cursor = len.saturating_sub(1),
+                KeyCode::Enter | KeyCode::Right => self.open(&mut out)?,
+                KeyCode::Char(' ') => {
+                    if let Some(name) = self.selected_symbol() {
+                        self.toggle(&name)
+                    }
+                }
+                KeyCode::Char(c @ ('y' | 'm' | 'n')) => {
+                    if let Some(name) = self.selected_symbol() {
+                        self.set_tristate(&name, c)
+                    }
+                }
+                KeyCode::Char('?') | KeyCode::Char('h') => self.help(&mut out)?,
+                KeyCode::Char('/') => self.search(&mut out)?,
+                KeyCode::Char('z') => self.show_all = !self.show_all,
+                KeyCode::Char('s') => self.save(),
+                KeyCode::Char('D') => self.save_defconfig(),
+                KeyCode::Esc | KeyCode::Left | KeyCode::Backspace if self.frames.len() > 1 => {
+                    self.frames.pop();
+                }
+                KeyCode::Esc | KeyCode::Char('q') => {
+                    if !self.modified {
+                        return Ok(self.saved);
+                    }
+                    match self.confirm(&mut out, "Save the configuration? [y/n] ")? {
+                        Some(true) => {
+                            self.save();
+                            if !self.modified {
+                                return Ok(true);
+                            }
+                        }
+                        Some(false) => return Ok(self.saved),
+                        None => {}
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn frame(&self) -> &Frame {
+        self.frames.last().expect("the main menu is never closed")
+    }
+
+    /// The number of entries that fit in the screen.
+    fn rows(&self) -> io::Result<usize> {
+        Ok(terminal::size()?.1.saturating_sub(3).max(1) as usize)
+    }
+
+    /// How visible `node` is: whether its dependencies are met, as a
+    /// tristate.
+    fn visibility(&self, node: &Node) -> u8 {
+        match node {
+            Node::Symbol(name) => self
+                .kconfig
+                .symbol(name)
+                .map_or(0, |s| self.kconfig.visibility(s, &self.values)),
+            Node::Menu { depends, .. } | Node::Comment { depends, .. } => {
+                self.kconfig.eval_all(depends, &self.values)
+            }
+            Node::Choice { index, .. } => self
+                .kconfig
+                .choice(*index)
+                .map_or(0, |c| self.kconfig.eval_all(c.depends(), &self.values)),
+        }
+    }
+
+    /// The entries of the current menu, with whether each one is visible.
+    /// Hidden entries are only listed by `z` and by searches.
+    fn entries(&self) -> Vec<(Node, bool)> {
+        let frame = self.frame();
+        frame
+            .nodes
+            .iter()
+            .map(|node| (node.clone(), self.visibility(node) > 0))
+            .filter(|(_, visible)| *visible || self.show_all || frame.search)
+            .collect()
+    }
+
+    /// The symbols currently selecting `name`, forcing its value.
+    fn forced_by(&self, name: &str) -> Vec<String> {
+        self.kconfig
+            .selected_by(name)
+            .into_iter()
+            .filter(|(by, cond)| {
+                self.values.get(*by).is_some_and(|v| v != "n")
+                    && cond.is_none_or(|c| self.kconfig.eval(c, &self.values) > 0)
+            })
+            .map(|(by, _)| by.to_string())
+            .collect()
+    }
+
+    fn line(&self, node: &Node) -> String {
+        let frame = self.frame();
+        match node {
+            Node::Symbol(name) => {
+                let Some(symbol) = self.kconfig.symbol(name) else {
+                    return name.clone();
+                };
+                let value = self.values.get(name).map_or("n", String::as_str);
+                let forced = !self.forced_by(name).is_empty();
+                let mark = match (frame.choice, symbol.symbol_type()) {
+                    (Some(_), _) if value == "y" => "(X)".to_string(),
+                    (Some(_), _) => "( )".to_string(),
+                    (None, SymbolType::Bool | SymbolType::Tristate) => {
+                        let (open, close) = match (forced, symbol.symbol_type()) {
+                            (true, _) => ('-', '-'),
+                            (false, SymbolType::Bool) => ('[', ']'),
+                            (false, _) => ('<', '>'),
+                        };
+                        let inner = match value {
+                            "y" => '*',
+                            "m" => 'M',
+                            _ => ' ',
+                        };
+                        format!("{}{}{}", open, inner, close)
+                    }
+                    (None, _) => format!("({})", self.values.get(name).map_or("", String::as_str)),
+                };
+
+                let mut line = format!("{} {}", mark, symbol.prompt().unwrap_or(name));
+                if self.show_all || frame.search {
+                    line += &format!(" ({})", name);
+                }
+                line
+            }
+            Node::Menu { prompt, .. } => format!("    {}  --->", prompt),
+            Node::Choice { index, .. } => {
+                let choice = self.kconfig.choice(*index);
+                let selected = choice
+                    .into_iter()
+                    .flat_map(|c| c.members())
+                    .find(|m| self.values.get(*m).is_some_and(|v| v == "y"))
+                    .and_then(|m| self.kconfig.symbol(m))
+                    .map_or("", |s| s.prompt().unwrap_or(s.name()));
+                format!(
+                    "    {} ({})  --->",
+                    choice.and_then(|c| c.prompt()).unwrap_or("Choice"),
+                    selected
+                )
+            }
+            Node::Comment { text, .. } => format!("    *** {} ***", text),
+        }
+    }
+
+    fn draw(&mut self, out: &mut impl Write) -> io::Result<()> {
+        let (width, _) = terminal::size()?;
+        let rows = self.rows()?;
+        let entries = self.entries();
+        let lines = entries
+            .iter()
+            .map(|(node, visible)| (self.line(node), *visible))
+            .collect::<Vec<(String, bool)>>();
+        let title = self
+            .frames
+            .iter()
+            .map(|f| f.title.as_str())
+            .collect::<Vec<&str>>()
+            .join(" > ");
+
+        let frame = self
+            .frames
+            .last_mut()
+            .expect("the main menu is never closed");
+        frame.cursor = frame.cursor.min(lines.len().saturating_sub(1));
+        if frame.cursor < frame.offset {
+            frame.offset = frame.cursor;
+        } else if frame.cursor >= frame.offset + rows {
+            frame.offset = frame.cursor + 1 - rows;
+        }
+
+        let fit = |text: &str| text.chars().take(width as usize).collect::<String>();
+        queue!(
+            out,
+            Clear(ClearType::All),
+            MoveTo(0, 0),
+            SetAttribute(Attribute::Bold),
+            Print(fit(&title)),
+            SetAttribute(Attribute::Reset),
+            MoveTo(0, 1),
+            SetAttribute(Attribute::Dim),
+            Print(fit(HINTS)),
+            SetAttribute(Attribute::Reset),
+        )?;
+        for (row, (line, visible)) in lines.iter().enumerate().skip(frame.offset).take(rows) {
+            queue!(out, MoveTo(0, (row - frame.offset + 2) as u16))?;
+            if row == frame.cursor {
+                queue!(out, SetAttribute(Attribute::Reverse))?;
+            }
+            if !visible {
+                queue!(out, SetAttribute(Attribute::Dim))?;
+            }
+            queue!(out, Print(fit(line)), SetAttribute(Attribute::Reset))?;
+        }
+        queue!(out, MoveTo(0, rows as u16 + 2), Print(fit(&self.status)))?;
+
+        out.flush()
+    }
+
+    fn selected(&self) -> Option<Node> {
+        self.entries()
+            .get(self.frame().cursor)
+            .map(|(node, _)| node.clone())
+    }
+
+    fn selected_symbol(&self) -> Option<String> {
+        match self.selected() {
+            Some(Node::Symbol(name)) => Some(name),
+            _ => None,
+        }
+    }
+
+    /// Enters the selected menu or choice, or edits the selected symbol.
+    fn open(&mut self, out: &mut impl Write) -> io::Result<()> {
+        match self.selected() {
+            Some(Node::Symbol(name)) => match self.kconfig.symbol(&name).map(|s| s.symbol_type()) {
+                Some(SymbolType::Int | SymbolType::Hex | SymbolType::String)
+                    if self.frame().choice.is_none() =>
+                {
+                    self.edit(out, &name)?
+                }
+                _ => self.toggle(&name),
+            },
+            Some(node) => self.enter(node),
+            None => {}
+        }
+        Ok(())
+    }
+
+    /// Shows the entries of the menu or the choice `node`.
+    fn enter(&mut self, node: Node) {
+        match node {
+            Node::Menu {
+                prompt, children, ..
+            } => self.frames.push(Frame::new(&prompt, children)),
+            Node::Choice { index, children } => {
+                let title = self
+                    .kconfig
+                    .choice(index)
+                    .and_then(|c| c.prompt())
+                    .unwrap_or("Choice");
+                let mut frame = Frame::new(title, children);
+                frame.choice = Some(index);
+                self.frames.push(frame);
+            }
+            _ => {}
+        }
+    }
+
+    /// Cycles a `bool` (`n`, `y`) or `tristate` (`n`, `m`, `y`) symbol, or
+    /// selects a member of the current choice.
+    fn toggle(&mut self, name: &str) {
+        let Some(symbol) = self.kconfig.symbol(name) else {
+            return;
+        };
+
+        let value = self.values.get(name).map_or("n", String::as_str);
+        let next = match (self.frame().choice, symbol.symbol_type(), value) {
+            (Some(_), _, _) => 'y',
+            (None, SymbolType::Bool, "y") => 'n',
+            (None, SymbolType::Bool, _) => 'y',
+            (None, SymbolType::Tristate, "n") => 'm',
+            (None, SymbolType::Tristate, "m") => 'y',
+            (None, SymbolType::Tristate, _) => 'n',
+            _ => return,
+        };
+        self.set_tristate(name, next);
+    }
+
+    fn set_tristate(&mut self, name: &str, value: char) {
+        let Some(symbol) = self.kconfig.symbol(name) else {
+            return;
+        };
+        if !matches!(
+            symbol.symbol_type(),
+            SymbolType::Bool | SymbolType::Tristate
+        ) || (value == 'm' && symbol.symbol_type() == SymbolType::Bool)
+        {
+            return;
+        }
+
+        // Selecting a member of a choice deselects the others.
+        if let Some(choice) = self.frame().choice.and_then(|i| self.kconfig.choice(i)) {
+            if value != 'y' {
+                return;
+            }
+            for member in choice.members() {
+                self.user.insert(member.clone(), "n".to_string());
+            }
+        }
+        self.set(name, value.to_string());
+    }
+
+    /// Edits the value of an `int`, `hex` or `string` symbol.
+    fn edit(&mut self, out: &mut impl Write, name: &str) -> io::Result<()> {
+        let Some(symbol) = self.kconfig.symbol(name) else {
+            return Ok(());
+        };
+        let current = self.values.get(name).cloned().unwrap_or_default();
+        let prompt = format!("{}: ", symbol.prompt().unwrap_or(name));
+        let Some(value) = self.read_line(out, &prompt, &current)? else {
+            return Ok(());
+        };
+
+        match parse_value(symbol.symbol_type(), &value) {
+            Ok(value) => self.set(name, value),
+            Err(e) => self.status = e,
+        }
+        Ok(())
+    }
+
+    /// Sets a user value and resolves the configuration again, telling why
+    /// the symbol didn't take the value, if it didn't.
+    fn set(&mut self, name: &str, value: String) {
+        self.user.insert(name.to_string(), value.clone());
+        self.values = self.kconfig.resolve(&self.user);
+        self.modified = true;
+
+        if self.values.get(name) != Some(&value) {
+            self.status = self.reason(name);
+        }
+    }
+
+    /// Why the value of `name` can't be changed.
+    fn reason(&self, name: &str) -> String {
+        let Some(symbol) = self.kconfig.symbol(name) else {
+            return String::new();
+        };
+
+        if symbol.prompt().is_none() {
+            return format!(
+                "`{}` has no prompt, it's only set by defaults and selects",
+                name
+            );
+        }
+        if self.kconfig.visibility(symbol, &self.values) == 0 {
+            return format!("`{}` is hidden: {}", name, self.depends(symbol.depends()));
+        }
+        let forced = self.forced_by(name);
+        if !forced.is_empty() {
+            return format!("`{}` is selected by {}", name, forced.join(", "));
+        }
+        format!("`{}` is limited by its dependencies or its range", name)
+    }
+
+    /// The `depends on` expressions with their current values.
+    fn depends(&self, depends: &[tailor_rs::kconfig::Expr]) -> String {
+        if depends.is_empty() {
+            return "no dependencies".to_string();
+        }
+        let list = depends
+            .iter()
+            .map(|e| {
+                format!(
+                    "{} [={}]",
+                    e,
+                    tristate_name(self.kconfig.eval(e, &self.values))
+                )
+            })
+            .collect::<Vec<String>>()
+            .join(" && ");
+        format!("depends on {}", list)
+    }
+
+    /// Shows the help text and the dependencies of the selected entry until
+    /// a key is pressed.
+    fn help(&mut self, out: &mut impl Write) -> io::Result<()> {
+        let mut text = vec![];
+        match self.selected() {
+            Some(Node::Symbol(name)) => {
+                let Some(symbol) = self.kconfig.symbol(&name) else {
+                    return Ok(());
+                };
+                let symbol_type = match symbol.symbol_type() {
+                    SymbolType::Bool => "bool",
+                    SymbolType::Tristate => "tristate",
+                    SymbolType::Int => "int",
+                    SymbolType::Hex => "hex",
+                    SymbolType::String => "string",
+                };
+                text.push(format!("CONFIG_{} ({})", name, symbol_type));
+                if let Some(prompt) = symbol.prompt() {
+                    text.push(format!("Prompt: {}", prompt));
+                }
+                text.push(match self.values.get(&name) {
+                    Some(value) => format!("Value: {}", value),
+                    None => "Value: not set, the dependencies aren't met".to_string(),
+                });
+                text.push(format!("Dependencies: {}", self.depends(symbol.depends())));
+                let selected_by = self
+                    .kconfig
+                    .selected_by(&name)
+                    .iter()
+                    .map(|(by, cond)| {
+                        let value = self.values.get(*by).map_or("n", String::as_str);
+                        match cond {
+                            Some(cond) => format!("{} [={}] if {}", by, value, cond),
+                            None => format!("{} [={}]", by, value),
+                        }
+                    })
+                    .collect::<Vec<String>>();
+                if !selected_by.is_empty() {
+                    text.push(format!("Selected by: {}", selected_by.join(", ")));
+                }
+                text.push(String::new());
+                if symbol.help().is_empty() {
+                    text.push("There is no help available for this option.".to_string());
+                } else {
+                    text.extend(symbol.help().lines().map(String::from));
+                }
+            }
+            Some(Node::Menu {
+                prompt, depends, ..
+            }) => {
+                text.push(format!("Menu: {}", prompt));
+                text.push(format!("Dependencies: {}", self.depends(&depends)));
+            }
+            Some(Node::Choice { index, .. }) => {
+                let Some(choice) = self.kconfig.choice(index) else {
+                    return Ok(());
+                };
+                text.push(format!("Choice: {}", choice.prompt().unwrap_or("")));
+                text.push(format!("Dependencies: {}", self.depends(choice.depends())));
+                text.push(format!("Members: {}", choice.members().join(", ")));
+            }
+            Some(Node::Comment {
+                text: comment,
+                depends,
+            }) => {
+                text.push(format!("Comment: {}", comment));
+                text.push(format!("Dependencies: {}", self.depends(&depends)));
+            }
+            None => return Ok(()),
+        }
+        text.push(String::new());
+        text.push("Press any key to go back".to_string());
+
+        let (width, height) = terminal::size()?;
+        queue!(out, Clear(ClearType::All))?;
+        for (row, line) in text.iter().enumerate().take(height as usize) {
+            let line = line.chars().take(width as usize).collect::<String>();
+            queue!(out, MoveTo(0, row as u16), Print(line))?;
+        }
+        out.flush()?;
+
+        loop {
+            if let Event::Key(key) = event::read()?
+                && key.kind == KeyEventKind::Press
+            {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Lists the symbols whose name or prompt contains the text typed.
+    fn search(&mut self, out: &mut impl Write) -> io::Result<()> {
+        let Some(query) = self.read_line(out, "Search: ", "")? else {
+            return Ok(());
+        };
+        let query = query.trim().to_lowercase();
+        if query.is_empty() {
+            return Ok(());
+        }
+
+        let results = self
+            .kconfig
+            .symbols()
+            .iter()
+            .filter(|s| {
+                s.name().to_lowercase().contains(&query)
+                    || s.prompt()
+                        .is_some_and(|p| p.to_lowercase().contains(&query))
+            })
+            .map(|s| Node::Symbol(s.name().to_string()))
+            .collect::<Vec<Node>>();
+        if results.is_empty() {
+            self.status = format!("no option matches `{}`", query);
+            return Ok(());
+        }
+
+        let mut frame = Frame::new(&format!("Search `{}`", query), results);
+        frame.search = true;
+        self.frames.push(frame);
+        Ok(())
+    }
+
+    /// Asks a yes or no question in the status line. Returns `None` when
+    /// it's cancelled with any other key.
+    fn confirm(&mut self, out: &mut impl Write, question: &str) -> io::Result<Option<bool>> {
+        let row = self.rows()? as u16 + 2;
+        queue!(
+            out,
+            MoveTo(0, row),
+            Clear(ClearType::CurrentLine),
+            Print(question)
+        )?;
+        out.flush()?;
+
+        loop {
+            if let Event::Key(key) = event::read()?
+                && key.kind == KeyEventKind::Press
+            {
+                return Ok(match key.code {
+                    KeyCode::Char('y') => Some(true),
+                    KeyCode::Char('n') => Some(false),
+                    _ => None,
+                });
+            }
+        }
+    }
+
+    /// Reads a line of text in the status line. Returns `None` on `Esc`.
+    fn read_line(
+        &mut self,
+        out: &mut impl Write,
+        prompt: &str,
+        initial: &str,
+    ) -> io::Result<Option<String>> {
+        let mut input = initial.to_string();
+        let row = self.rows()? as u16 + 2;
+
+        loop {
+            queue!(
+                out,
+                MoveTo(0, row),
+                Clear(ClearType::CurrentLine),
+                Print(prompt),
+                Print(&input),
+                Show
+            )?;
+            out.flush()?;
+
+            let Event::Key(key) = event::read()? else {
+                continue;
+            };
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+            match key.code {
+                KeyCode::Enter => break,
+                KeyCode::Esc => {
+                    execute!(out, Hide)?;
+                    return Ok(None);
+                }
+                KeyCode::Backspace => {
+                    input.pop();
+                }
+                KeyCode::Char(c) => input.push(c),
+                _ => {}
+            }
+        }
+
+        execute!(out, Hide)?;
+        Ok(Some(input))
+    }
+
+    /// Writes `.config` and the `autoconf.h` used by the build.
+    fn save(&mut self) {
+        let result = std::fs::write(&self.dot_config, self.kconfig.config_file(&self.values))
+            .map_err(|e| format!("fail to write {}: {}", self.dot_config.display(), e))
+            .and_then(|_| {
+                write_header(
+                    &self.build_dir,
+                    "autoconf.h",
+                    &self.kconfig.header(&self.values),
+                )
+            });
+
+        self.status = match result {
+            Ok(_) => {
+                self.modified = false;
+                self.saved = true;
+                format!("Saved {}", self.dot_config.display())
+            }
+            Err(e) => e,
+        };
+    }
+
+    /// Writes the minimal defconfig: only the values that differ from the
+    /// defaults.
+    fn save_defconfig(&mut self) {
+        let minimal = self.kconfig.minimal_config(&self.values);
+        self.status = match std::fs::write(&self.defconfig, self.kconfig.config_file(&minimal)) {
+            Ok(_) => format!(
+                "Saved {} ({} options)",
+                self.defconfig.display(),
+                minimal.len()
+            ),
+            Err(e) => format!("fail to write {}: {}", self.defconfig.display(), e),
+        };
+    }
+}
+
+impl Command for MenuconfigPkg {
+    fn parse_args(&mut self, args: &[String]) -> Option<()> {
+        if args.is_empty() || args[0] != "menuconfig" {
+            return None;
+        }
+
+        let mut i = 1;
+        while i < args.len() {
+            i += self.build.parse_arg(&args[i..])?;
+        }
+
+        self.build.finish_args()
+    }
+
+    fn execute(&self) -> Result<(), String> {
+        let (_, paths) = self.build.selected_packages()?;
+
+        let mut packages = vec![];
+        for path in paths {
+            let pkg = Package::from_file(&path.join("Tailor.toml"))?;
+            if pkg.is_virtual() {
+                continue;
+            }
+            if let Some(config) = resolve_config(&pkg, &path, &[])? {
+                packages.push((path, pkg, config));
+            }
+        }
+
+        let (path, pkg, (kconfig, values)) = match packages.len() {
+            0 => return Err("no Kconfig file found in the selected packages".to_string()),
+            1 => packages.remove(0),
+            _ => {
+                return Err(format!(
+                    "there are many packages with a Kconfig file, select one with `-p <name>`: {}",
+                    packages
+                        .iter()
+                        .map(|(_, pkg, _)| pkg.name())
+                        .collect::<Vec<&str>>()
+                        .join(", ")
+                ));
+            }
+        };
+
+        let title = kconfig
+            .title()
+            .map_or_else(|| format!("{} configuration", pkg.name()), String::from);
+        let mut menuconfig = Menuconfig::new(
+            &kconfig,
+            values,
+            &title,
+            &path,
+            self.build.build_dir(&pkg, &path)?,
+        );
+
+        let saved = menuconfig
+            .run()
+            .map_err(|e| format!("fail to use the terminal: {}", e))?;
+        if saved {
+            println!(
+                "{} configuration of `{}` to {}",
+                success("Saved"),
+                pkg.name(),
+                menuconfig.dot_config.display()
+            );
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Parses `content` as a Kconfig file.
+    fn kconfig(content: &str) -> Kconfig {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("Kconfig"), content).unwrap();
+        Kconfig::from_file(&dir.path().join("Kconfig")).unwrap()
+    }
+
+    /// The editor of `kconfig` with its default values, saving in `dir`.
+    fn menuconfig<'a>(kconfig: &'a Kconfig, dir: &Path) -> Menuconfig<'a> {
+        Menuconfig::new(
+            kconfig,
+            kconfig.resolve(&Values::new()),
+            "Demo",
+            dir,
+            dir.join("build"),
+        )
+    }
+
+    fn value<'a>(menuconfig: &'a Menuconfig, name: &str) -> Option<&'a str> {
+        menuconfig.values.get(name).map(String::as_str)
+    }
+
+    /// The lines of the entries of the current menu.
+    fn lines(menuconfig: &Menuconfig) -> Vec<String> {
+        menuconfig
+            .entries()
+            .iter()
+            .map(|(node, _)| menuconfig.line(node))
+            .collect()
+    }
+
+    #[test]
+    fn toggle_cycles_bool_and_tristate_symbols() {
+        let kconfig = kconfig(
+            "config DEBUG\n\tbool \"Debug\"\n\
+             config DRIVER\n\ttristate \"Driver\"\n\
+             config COUNT\n\tint \"Count\"\n\tdefault 3\n",
+        );
+        let dir = tempfile::tempdir().unwrap();
+        let mut menuconfig = menuconfig(&kconfig, dir.path());
+
+        menuconfig.toggle("DEBUG");
+        assert_eq!(value(&menuconfig, "DEBUG"), Some("y"));
+        menuconfig.toggle("DEBUG");
+        assert_eq!(value(&menuconfig, "DEBUG"), Some("n"));
+        assert!(menuconfig.modified);
+
+        for expected in ["m", "y", "n"] {
+            menuconfig.toggle("DRIVER");
+            assert_eq!(value(&menuconfig, "DRIVER"), Some(expected));
+        }
+
+        // `m` is only a value of tristates, and `int` symbols are edited.
+        menuconfig.set_tristate("DEBUG", 'm');
+        assert_eq!(value(&menuconfig, "DEBUG"), Some("n"));
+        menuconfig.toggle("COUNT");
+        menuconfig.set_tristate("COUNT", 'y');
+        assert_eq!(value(&menuconfig, "COUNT"), Some("3"));
+
+        assert_eq!(lines(&menuconfig), ["[ ] Debug", "< > Driver", "(3) Count"]);
+    }
+
+    #[test]
+    fn selecting_a_member_of_a_choice_deselects_the_others() {
+        let kconfig = kconfig(
+            "choice\n\tprompt \"Optimization\"\n\tdefault OPT_SIZE\n\
+             config OPT_NONE\n\tbool \"None\"\n\
+             config OPT_SIZE\n\tbool \"Size\"\n\
+             endchoice\n",
+        );
+        let dir = tempfile::tempdir().unwrap();
+        let mut menuconfig = menuconfig(&kconfig, dir.path());
+        assert_eq!(lines(&menuconfig), ["    Optimization (Size)  --->"]);
+
+        let choice = menuconfig.selected().unwrap();
+        menuconfig.enter(choice);
+        assert_eq!(lines(&menuconfig), ["( ) None", "(X) Size"]);
+
+        menuconfig.toggle("OPT_NONE");
+        assert_eq!(value(&menuconfig, "OPT_NONE"), Some("y"));
+        assert_eq!(value(&menuconfig, "OPT_SIZE"), Some("n"));
+
+        // A choice always has a member selected.
+        menuconfig.toggle("OPT_NONE");
+        menuconfig.set_tristate("OPT_NONE", 'n');
+        assert_eq!(value(&menuconfig, "OPT_NONE"), Some("y"));
+    }
+
+    #[test]
+    fn refused_values_are_explained() {
+        let kconfig = kconfig(
+            "config NET\n\tbool \"Network\"\n\
+             config TCP\n\tbool \"TCP\"\n\tdepends on NET\n\
+             config DRIVER\n\tbool \"Driver\"\n\tselect BUS\n\
+             config BUS\n\tbool \"Bus\"\n\
+             config LEVEL\n\tint \"Level\"\n\trange 1 5\n\
+             config INTERNAL\n\tbool\n",
+        );
+        let dir = tempfile::tempdir().unwrap();
+        let mut menuconfig = menuconfig(&kconfig, dir.path());
+
+        menuconfig.set("TCP", "y".to_string());
+        assert_eq!(value(&menuconfig, "TCP"), None);
+        assert_eq!(menuconfig.status, "`TCP` is hidden: depends on NET [=n]");
+
+        menuconfig.toggle("DRIVER");
+        menuconfig.status.clear();
+        menuconfig.set_tristate("BUS", 'n');
+        assert_eq!(value(&menuconfig, "BUS"), Some("y"));
+        assert_eq!(menuconfig.status, "`BUS` is selected by DRIVER");
+
+        menuconfig.set("LEVEL", "9".to_string());
+        assert_eq!(value(&menuconfig, "LEVEL"), Some("5"));
+        assert_eq!(
+            menuconfig.status,
+            "`LEVEL` is limited by its dependencies or its range"
+        );
+
+        menuconfig.set_tristate("INTERNAL", 'y');
+        assert_eq!(
+            menuconfig.status,
+            "`INTERNAL` has no prompt, it's only set by defaults and selects"
+        );
+    }
+
+    #[test]
+    fn hidden_entries_are_only_listed_when_showing_all() {
+        let kconfig = kconfig(
+            "config NET\n\tbool \"Network\"\n\
+             config TCP\n\tbool \"TCP\"\n\tdepends on NET\n\
+             menu \"Sockets\"\n\tdepends on NET\n\
+             config SOCKETS\n\tint \"Sockets\"\n\tdefault 4\n\
+             endmenu\n\
+             comment \"No network\"\n\tdepends on !NET\n",
+        );
+        let dir = tempfile::tempdir().unwrap();
+        let mut menuconfig = menuconfig(&kconfig, dir.path());
+        let visibility = |menuconfig: &Menuconfig| {
+            kconfig
+                .nodes()
+                .iter()
+                .map(|node| menuconfig.visibility(node))
+                .collect::<Vec<u8>>()
+        };
+
+        assert_eq!(visibility(&menuconfig), [2, 0, 0, 2]);
+        assert_eq!(
+            lines(&menuconfig),
+            ["[ ] Network", "    *** No network ***"]
+        );
+        menuconfig.show_all = true;
+        assert_eq!(lines(&menuconfig).len(), 4);
+        menuconfig.show_all = false;
+
+        menuconfig.toggle("NET");
+        assert_eq!(visibility(&menuconfig), [2, 2, 2, 0]);
+        assert_eq!(
+            lines(&menuconfig),
+            ["[*] Network", "[ ] TCP", "    Sockets  --->"]
+        );
+    }
+
+    #[test]
+    fn typed_values_are_checked() {
+        assert_eq!(parse_value(SymbolType::Int, " 42 "), Ok("42".to_string()));
+        assert_eq!(
+            parse_value(SymbolType::Int, "4x"),
+            Err("`4x` is not an integer".to_string())
+        );
+        assert_eq!(parse_value(SymbolType::Hex, "1F"), Ok("0x1F".to_string()));
+        assert_eq!(parse_value(SymbolType::Hex, "0x20"), Ok("0x20".to_string()));
+        assert_eq!(
+            parse_value(SymbolType::Hex, "0xzz"),
+            Err("`0xzz` is not an hexadecimal number".to_string())
+        );
+        assert_eq!(
+            parse_value(SymbolType::String, "a b"),
+            Ok("a b".to_string())
+        );
+    }
+
+    #[test]
+    fn save_writes_the_config_the_header_and_the_defconfig() {
+        let kconfig = kconfig(
+            "config DEBUG\n\tbool \"Debug\"\n\
+             config COUNT\n\tint \"Count\"\n\tdefault 3\n",
+        );
+        let dir = tempfile::tempdir().unwrap();
+        let mut menuconfig = menuconfig(&kconfig, dir.path());
+        menuconfig.toggle("DEBUG");
+
+        menuconfig.save();
+        assert!(!menuconfig.modified);
+        assert!(menuconfig.saved);
+        assert_eq!(
+            menuconfig.status,
+            format!("Saved {}", dir.path().join(".config").display())
+        );
+        assert_eq!(
+            std::fs::read_to_string(dir.path().join(".config")).unwrap(),
+            "# Automatically generated by Tailor\nCONFIG_DEBUG=y\nCONFIG_COUNT=3\n"
+        );
+        let header =
+            std::fs::read_to_string(dir.path().join("build/generated/autoconf.h")).unwrap();
+        assert!(header.contains("#define CONFIG_DEBUG 1\n#define CONFIG_COUNT 3\n"));
+
+        menuconfig.save_defconfig();
+        assert_eq!(
+            menuconfig.status,
+            format!(
+                "Saved {} (1 options)",
+                dir.path().join("defconfig").display()
+            )
+        );
+        assert_eq!(
+            std::fs::read_to_string(dir.path().join("defconfig")).unwrap(),
+            "# Automatically generated by Tailor\nCONFIG_DEBUG=y\n"
+        );
+    }
+}
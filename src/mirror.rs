@@ -0,0 +1,138 @@
+use std::path::PathBuf;
+
+use crate::config::{Config, SourceConfig};
+
+/// The name of the default registry in the `[source]` table.
+const DEFAULT_SOURCE: &str = "default";
+
+/// Where the index files and the tarballs of a replaced registry are
+/// fetched from.
+#[derive(Debug, Clone)]
+pub enum RegistryMirror {
+    Url(String),
+    /// A folder with the layout of the registry cache: `index/<name>` and
+    /// `cache/<name>-<version>.tar.gz`.
+    Directory(PathBuf),
+}
+
+/// The source fetched instead of `name`, following the `replace-with` keys
+/// up to the last source, or `None` when `name` isn't replaced.
+fn replacement<'a>(
+    config: &'a Config,
+    name: &'a str,
+) -> Result<Option<(&'a str, &'a SourceConfig)>, String> {
+    let sources = config.sources();
+    let mut chain = vec![name];
+    let mut last = None;
+
+    let mut next = sources.get(name).and_then(SourceConfig::replace_with);
+    while let Some(replacement) = next {
+        if chain.contains(&replacement) {
+            return Err(format!(
+                "source `{}` is replaced by itself: {} -> {}",
+                name,
+                chain.join(" -> "),
+                replacement
+            ));
+        }
+        let source = sources.get(replacement).ok_or_else(|| {
+            format!(
+                "source `{}` is replaced with `{}`, which is not in the [source] table of the config",
+                chain[chain.len() - 1],
+                replacement
+            )
+        })?;
+
+        chain.push(replacement);
+        last = Some((replacement, source));
+        next = source.replace_with();
+    }
+
+    Ok(last)
+}
+
+/// The mirror of the registry at `url`, set by the replaced source whose
+/// `registry` is `url` or a prefix of it, the longest one winning. The source
+/// `default` without a `registry` is `configured`, the `registry` of the
+/// config, so it doesn't replace a registry set by `TAILOR_REGISTRY`.
+pub fn registry_mirror(
+    config: &Config,
+    url: &str,
+    configured: &str,
+) -> Result<Option<RegistryMirror>, String> {
+    let Some(name) = config
+        .sources()
+        .iter()
+        .filter(|(_, source)| source.replace_with().is_some())
+        .filter_map(|(name, source)| {
+            let registry = match source.registry() {
+                Some(registry) => registry,
+                None if name == DEFAULT_SOURCE => configured,
+                None => return None,
+            };
+            let registry = registry.trim_end_matches('/');
+            // On a tie, a source naming the registry wins over `default`.
+            strip_path_prefix(url, registry)
+                .map(|_| (name, (registry.len(), source.registry().is_some())))
+        })
+        .max_by_key(|(_, key)| *key)
+        .map(|(name, _)| name.as_str())
+    else {
+        return Ok(None);
+    };
+
+    let Some((replacement, source)) = replacement(config, name)? else {
+        return Ok(None);
+    };
+    match (source.registry(), source.directory()) {
+        (Some(url), _) => Ok(Some(RegistryMirror::Url(
+            url.trim_end_matches('/').to_string(),
+        ))),
+        (_, Some(dir)) => Ok(Some(RegistryMirror::Directory(dir.to_path_buf()))),
+        _ => Err(format!(
+            "source `{}` can't replace registry {}, it must have a `registry` or a `directory`",
+            replacement, url
+        )),
+    }
+}
+
+/// The path of `url` under `prefix`, without its leading `/`, when `prefix`
+/// ends on a path segment of `url`: `https://github.com/org` is a prefix of
+/// `https://github.com/org/repo.git`, but not of `https://github.com/organization`.
+fn strip_path_prefix<'a>(url: &'a str, prefix: &str) -> Option<&'a str> {
+    let rest = url.strip_prefix(prefix)?;
+    (rest.is_empty() || prefix.ends_with('/') || rest.starts_with('/'))
+        .then(|| rest.trim_start_matches('/'))
+}
+
+/// The URL `url` of a Git repository is cloned from: the same URL under the
+/// prefix of the mirror, or the same path under the directory of the mirror,
+/// when its prefix is the `git` of a replaced source. The longest prefix wins.
+pub fn git_mirror(config: &Config, url: &str) -> Result<String, String> {
+    let Some((name, rest)) = config
+        .sources()
+        .iter()
+        .filter(|(_, source)| source.replace_with().is_some())
+        .filter_map(|(name, source)| {
+            let prefix = source.git()?;
+            strip_path_prefix(url, prefix).map(|rest| (name, prefix, rest))
+        })
+        .max_by_key(|(_, prefix, _)| prefix.len())
+        .map(|(name, _, rest)| (name.as_str(), rest))
+    else {
+        return Ok(url.to_string());
+    };
+
+    let Some((replacement, source)) = replacement(config, name)? else {
+        return Ok(url.to_string());
+    };
+    match (source.git(), source.directory()) {
+        (Some(prefix), _) if rest.is_empty() => Ok(prefix.to_string()),
+        (Some(prefix), _) => Ok(format!("{}/{}", prefix.trim_end_matches('/'), rest)),
+        (_, Some(dir)) => Ok(dir.join(rest).display().to_string()),
+        _ => Err(format!(
+            "source `{}` can't replace the Git repositories of `{}`, it must have a `git` or a `directory`",
+            replacement, name
+        )),
+    }
+}
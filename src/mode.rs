@@ -1,4 +1,6 @@
-#[derive(Default)]
+use std::fmt::Display;
+
+#[derive(Default, Clone, Copy)]
 pub enum Mode {
     #[default]
     Debug,
@@ -17,11 +19,11 @@ impl TryFrom<&str> for Mode {
     }
 }
 
-impl ToString for Mode {
-    fn to_string(&self) -> String {
+impl Display for Mode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Mode::Debug => "debug".to_string(),
-            Mode::Release => "release".to_string(),
+            Mode::Debug => write!(f, "debug"),
+            Mode::Release => write!(f, "release"),
         }
     }
 }
@@ -0,0 +1,178 @@
+use std::{error::Error, path::PathBuf, thread::sleep, time::Duration};
+
+use reqwest::{
+    Certificate, NoProxy, Proxy, StatusCode,
+    blocking::{Client, RequestBuilder, Response},
+};
+
+use crate::{
+    config::{Config, HttpConfig},
+    fmt::warning,
+};
+
+/// The wait before the first retry, doubled at each of the next ones.
+const RETRY_DELAY: Duration = Duration::from_secs(1);
+
+/// The HTTP client of the network operations, configured by the `[http]`
+/// table of the config.
+pub struct Network {
+    client: Client,
+    retries: u32,
+}
+
+impl Network {
+    pub fn new(http: &HttpConfig) -> Result<Self, String> {
+        let mut builder = Client::builder()
+            .connect_timeout(http.connect_timeout())
+            .timeout(http.timeout());
+
+        if let Some(proxy) = http.proxy() {
+            let no_proxy = NoProxy::from_string(&http.no_proxy().join(","));
+            builder = builder.proxy(
+                Proxy::all(proxy)
+                    .map_err(|e| format!("invalid proxy `{}`: {}", proxy, describe(&e)))?
+                    .no_proxy(no_proxy),
+            );
+        }
+
+        for path in http.cainfo() {
+            let pem = std::fs::read(path)
+                .map_err(|e| format!("fail to read file {}: {}", path.display(), e))?;
+            for certificate in Certificate::from_pem_bundle(&pem).map_err(|e| {
+                format!(
+                    "invalid certificates in {}: {}",
+                    path.display(),
+                    describe(&e)
+                )
+            })? {
+                builder = builder.add_root_certificate(certificate);
+            }
+        }
+
+        Ok(Self {
+            client: builder
+                .build()
+                .map_err(|e| format!("fail to create the HTTP client: {}", describe(&e)))?,
+            retries: http.retries(),
+        })
+    }
+
+    pub fn client(&self) -> &Client {
+        &self.client
+    }
+
+    /// Sends `request`, and sends it again after a growing delay when it fails
+    /// with a network error or a server error, up to `http.retries` times.
+    pub fn send(&self, request: RequestBuilder) -> Result<Response, String> {
+        let mut delay = RETRY_DELAY;
+        for retries_left in (0..=self.retries).rev() {
+            // Requests with a streamed body can't be sent twice.
+            let Some(attempt) = request.try_clone().filter(|_| retries_left > 0) else {
+                break;
+            };
+
+            let failure = match attempt.send() {
+                Ok(res) if !is_spurious(res.status()) => return Ok(res),
+                Ok(res) => format!("{} answered {}", res.url(), res.status()),
+                Err(e) if e.is_connect() || e.is_timeout() || e.is_request() => describe(&e),
+                Err(e) => return Err(describe(&e)),
+            };
+
+            eprintln!(
+                "{}: {}, retrying in {}s ({} {} left)",
+                warning(),
+                failure,
+                delay.as_secs(),
+                retries_left,
+                if retries_left == 1 { "try" } else { "tries" }
+            );
+            sleep(delay);
+            delay *= 2;
+        }
+
+        request.send().map_err(|e| describe(&e))
+    }
+}
+
+/// Whether the server failed to answer, so sending the request again may work.
+fn is_spurious(status: StatusCode) -> bool {
+    status.is_server_error() || status == StatusCode::TOO_MANY_REQUESTS
+}
+
+/// The message of `error` with its causes, as reqwest hides them, such as
+/// `error sending request for url (...): client error (Connect): Connection
+/// refused`.
+pub fn describe(error: &reqwest::Error) -> String {
+    let mut message = error.to_string();
+    let mut source = error.source();
+    while let Some(cause) = source {
+        message = format!("{}: {}", message, cause);
+        source = cause.source();
+    }
+    message
+}
+
+/// The bundles of certificate authorities of the usual systems, the first one
+/// found being the one of the system.
+const SYSTEM_CAINFO: &[&str] = &[
+    "/etc/ssl/certs/ca-certificates.crt",
+    "/etc/pki/tls/certs/ca-bundle.crt",
+    "/etc/ssl/ca-bundle.pem",
+    "/etc/ssl/cert.pem",
+];
+
+/// The environment variables giving the proxy, the timeout and the certificate
+/// authorities of `http` to Git, so clones go through the same network as the
+/// registry.
+pub fn git_env(http: &HttpConfig) -> Result<Vec<(&'static str, String)>, String> {
+    let mut env = vec![];
+    if let Some(proxy) = http.proxy() {
+        env.push(("http_proxy", proxy.to_string()));
+        env.push(("https_proxy", proxy.to_string()));
+        if !http.no_proxy().is_empty() {
+            env.push(("no_proxy", http.no_proxy().join(",")));
+        }
+    }
+
+    // Git aborts a transfer slower than 1 byte per second for this long.
+    env.push(("GIT_HTTP_LOW_SPEED_LIMIT", "1".to_string()));
+    env.push((
+        "GIT_HTTP_LOW_SPEED_TIME",
+        http.timeout().as_secs().max(1).to_string(),
+    ));
+
+    if !http.cainfo().is_empty() {
+        env.push((
+            "GIT_SSL_CAINFO",
+            git_cainfo(http)?.to_string_lossy().into_owned(),
+        ));
+    }
+    Ok(env)
+}
+
+/// Writes the bundle of the certificate authorities trusted by Git: the ones
+/// of the system and the `cainfo` of `http`, as Git only takes a single
+/// bundle, which replaces the one of the system.
+fn git_cainfo(http: &HttpConfig) -> Result<PathBuf, String> {
+    let system = std::env::var_os("SSL_CERT_FILE")
+        .map(PathBuf::from)
+        .into_iter()
+        .chain(SYSTEM_CAINFO.iter().map(PathBuf::from))
+        .find(|path| path.is_file());
+
+    let mut bundle = vec![];
+    for path in system.iter().chain(http.cainfo()) {
+        let mut pem = std::fs::read(path)
+            .map_err(|e| format!("fail to read file {}: {}", path.display(), e))?;
+        if !pem.ends_with(b"\n") {
+            pem.push(b'\n');
+        }
+        bundle.extend(pem);
+    }
+
+    let path = Config::dir().join("git-cainfo.pem");
+    std::fs::create_dir_all(Config::dir())
+        .and_then(|_| std::fs::write(&path, bundle))
+        .map_err(|e| format!("fail to write file {}: {}", path.display(), e))?;
+    Ok(path)
+}
@@ -39,6 +39,7 @@ impl Command for NewPkg {
                 match args[1].as_str() {
                     "--bin" => self.pkg_type = PackageType::Binary,
                     "--lib" => self.pkg_type = PackageType::Library,
+                    "--sdk" => self.pkg_type = PackageType::Sdk,
                     _ => return None,
                 }
 
@@ -60,6 +61,7 @@ impl Command for NewPkg {
         match self.pkg_type {
             PackageType::Binary => bin::new_pkg(&self.path, &self.name),
             PackageType::Library => lib::new_pkg(&self.path, &self.name),
+            PackageType::Sdk => sdk::new_pkg(&self.path, &self.name),
         }
     }
 }
@@ -67,7 +69,7 @@ impl Command for NewPkg {
 mod bin {
     use std::path::Path;
 
-    use crate::fmt::success;
+    use crate::{edition::Edition, fmt::success};
 
     const MAIN_C: &str = include_str!("../template/main.c");
     const TAILOR_MANIFEST: &str = include_str!("../template/bin/Tailor.toml");
@@ -94,7 +96,9 @@ mod bin {
 
         std::fs::write(
             path.join("Tailor.toml"),
-            TAILOR_MANIFEST.replace("$pkg_name", name),
+            TAILOR_MANIFEST
+                .replace("$pkg_name", name)
+                .replace("$edition", &Edition::LATEST.to_string()),
         )
         .map_err(|_| "fail to write Tailor.toml".to_string())?;
 
@@ -110,7 +114,7 @@ mod bin {
 mod lib {
     use std::path::Path;
 
-    use crate::fmt::success;
+    use crate::{edition::Edition, fmt::success};
 
     const LIB_C: &str = include_str!("../template/lib.c");
     const LIB_H: &str = include_str!("../template/lib.h");
@@ -149,7 +153,9 @@ mod lib {
 
         std::fs::write(
             path.join("Tailor.toml"),
-            TAILOR_MANIFEST.replace("$pkg_name", name),
+            TAILOR_MANIFEST
+                .replace("$pkg_name", name)
+                .replace("$edition", &Edition::LATEST.to_string()),
         )
         .map_err(|_| "fail to write Tailor.toml".to_string())?;
 
@@ -158,3 +164,47 @@ mod lib {
         Ok(())
     }
 }
+
+mod sdk {
+    use std::path::Path;
+
+    use crate::{edition::Edition, fmt::success};
+
+    const MAIN_C: &str = include_str!("../template/main.c");
+    const TAILOR_MANIFEST: &str = include_str!("../template/sdk/Tailor.toml");
+
+    pub fn new_pkg(path: &Path, name: &str) -> Result<(), String> {
+        let abs_path = Path::new(".")
+            .canonicalize()
+            .map_err(|_| "fail to get absolute path")?
+            .join(path);
+        if abs_path.exists() {
+            return Err(format!(
+                "destination `{}` already exists.",
+                abs_path.display()
+            ));
+        }
+
+        std::fs::create_dir_all(path.join("src")).map_err(|_| "fail to create src".to_string())?;
+
+        std::fs::create_dir_all(path.join("include"))
+            .map_err(|_| "fail to create include".to_string())?;
+
+        std::fs::write(path.join("src/main.c"), MAIN_C)
+            .map_err(|_| "fail to write src/main.c".to_string())?;
+
+        // `$pkg_name` is kept in the SDK commands, where it's replaced by the
+        // package name when the CMakeLists is generated.
+        std::fs::write(
+            path.join("Tailor.toml"),
+            TAILOR_MANIFEST
+                .replacen("$pkg_name", name, 1)
+                .replace("$edition", &Edition::LATEST.to_string()),
+        )
+        .map_err(|_| "fail to write Tailor.toml".to_string())?;
+
+        println!("{} sdk package `{name}`", success("Creating"));
+
+        Ok(())
+    }
+}
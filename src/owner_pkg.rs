@@ -0,0 +1,96 @@
+use crate::{
+    command::{Command, parse_flag_value},
+    fmt::success,
+    package::Package,
+    registry::Registry,
+};
+
+#[derive(Default)]
+pub struct OwnerPkg {
+    name: Option<String>,
+    add: Vec<String>,
+    remove: Vec<String>,
+    list: bool,
+    token: Option<String>,
+    registry: Option<String>,
+}
+
+impl Command for OwnerPkg {
+    fn parse_args(&mut self, args: &[String]) -> Option<()> {
+        if args.is_empty() || args[0] != "owner" {
+            return None;
+        }
+
+        let mut i = 1;
+        while i < args.len() {
+            if let Some((user, consumed)) = parse_flag_value(&args[i..], &["-a", "--add"]) {
+                self.add.push(user.to_string());
+                i += consumed;
+                continue;
+            }
+            if let Some((user, consumed)) = parse_flag_value(&args[i..], &["-r", "--remove"]) {
+                self.remove.push(user.to_string());
+                i += consumed;
+                continue;
+            }
+            if args[i] == "-l" || args[i] == "--list" {
+                self.list = true;
+                i += 1;
+                continue;
+            }
+            if let Some((registry, consumed)) = parse_flag_value(&args[i..], &["--registry"]) {
+                self.registry = Some(registry.to_string());
+                i += consumed;
+                continue;
+            }
+            if let Some((token, consumed)) = parse_flag_value(&args[i..], &["--token"]) {
+                self.token = Some(token.to_string());
+                i += consumed;
+                continue;
+            }
+            if args[i].starts_with('-') || self.name.is_some() {
+                return None;
+            }
+            self.name = Some(args[i].clone());
+            i += 1;
+        }
+
+        (self.list || !self.add.is_empty() || !self.remove.is_empty()).then_some(())
+    }
+
+    fn execute(&self) -> Result<(), String> {
+        // Without a name, it's the package of the current folder.
+        let name = match &self.name {
+            Some(name) => name.clone(),
+            None => {
+                let manifest = std::env::current_dir()
+                    .map_err(|e| format!("fail to get current directory: {}", e))?
+                    .join("Tailor.toml");
+                Package::from_file(&manifest)?.name().to_string()
+            }
+        };
+
+        let registry = Registry::named(self.registry.as_deref())?;
+        let token = registry.token(self.token.as_deref())?;
+
+        if !self.add.is_empty() {
+            registry.change_owners(&name, &self.add, true, &token)?;
+            for user in &self.add {
+                println!("{} `{}` as owner of `{}`", success("Added"), user, name);
+            }
+        }
+        if !self.remove.is_empty() {
+            registry.change_owners(&name, &self.remove, false, &token)?;
+            for user in &self.remove {
+                println!("{} `{}` as owner of `{}`", success("Removed"), user, name);
+            }
+        }
+        if self.list {
+            for user in registry.owners(&name, &token)? {
+                println!("{}", user);
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -1,31 +1,66 @@
 use toml::Table;
 
-use crate::dependency::Dependency;
+use crate::{
+    cfg::Condition,
+    config_schema::ConfigSchema,
+    dependency::{Dependency, DependencyKind},
+    edition::Edition,
+    features::parse_features,
+    mode::Mode,
+    profile::{Profile, parse_profiles},
+    sdk::Sdk,
+    target::Target,
+};
 use sha2::{Digest, Sha256};
-use std::path::Path;
+use std::{collections::BTreeMap, path::Path};
 
 #[derive(Default, Clone, Copy, Debug)]
 pub enum PackageType {
     #[default]
     Binary,
     Library,
+    /// Built with the CMake structure of an SDK, described by `[sdk]`.
+    Sdk,
 }
 
+/// The `name` and the `version` of a package. Virtual manifests, which only
+/// group the members of a workspace, don't have them.
 #[derive(Debug)]
-pub struct Package {
+struct Identity {
     name: String,
     version: String,
+}
+
+#[derive(Debug)]
+pub struct Package {
+    identity: Option<Identity>,
+    edition: Edition,
+    description: Option<String>,
+    license: Option<String>,
+    authors: Vec<String>,
+    repository: Option<String>,
+    keywords: Vec<String>,
     dependencies: Vec<Dependency>,
     pkg_type: PackageType,
+    main: Option<String>,
     sources: Vec<String>,
     includes: Vec<String>,
+    targets: Vec<Target>,
+    profiles: Vec<Profile>,
+    workspace_members: Option<Vec<String>>,
+    features: BTreeMap<String, Vec<String>>,
+    sdk: Option<Sdk>,
+    kconfig: String,
+    kconfig_fragments: Vec<String>,
+    config: ConfigSchema,
 }
 
 impl Package {
     pub fn hash(&self) -> Vec<u8> {
         let mut hasher = Sha256::new();
-        hasher.update(self.name.as_bytes());
-        hasher.update(self.version.as_bytes());
+        hasher.update(self.name().as_bytes());
+        hasher.update(self.version().as_bytes());
+        hasher.update(self.edition.to_string().as_bytes());
         for dep in &self.dependencies {
             hasher.update(dep.hash());
         }
@@ -42,6 +77,12 @@ impl Package {
         &self.dependencies
     }
 
+    /// The source file holding `main`, relative to the package folder: the
+    /// `main` key, or `src/main.c` for binaries. Libraries have none.
+    pub fn main(&self) -> Option<&str> {
+        self.main.as_deref()
+    }
+
     pub fn sources(&self) -> &[String] {
         &self.sources
     }
@@ -50,16 +91,89 @@ impl Package {
         &self.includes
     }
 
+    pub fn targets(&self) -> &[Target] {
+        &self.targets
+    }
+
+    pub fn profile(&self, mode: &Mode) -> Option<&Profile> {
+        self.profiles.iter().find(|p| p.name() == mode.to_string())
+    }
+
+    /// The member patterns of the `[workspace]` table, if it's a workspace root.
+    pub fn workspace_members(&self) -> Option<&[String]> {
+        self.workspace_members.as_deref()
+    }
+
+    /// The `[features]` table: each feature and the features it implies.
+    pub fn features(&self) -> &BTreeMap<String, Vec<String>> {
+        &self.features
+    }
+
+    pub fn is_virtual(&self) -> bool {
+        self.identity.is_none()
+    }
+
+    /// The name of the package, empty for a virtual manifest.
     pub fn name(&self) -> &str {
-        &self.name
+        self.identity.as_ref().map_or("", |id| id.name.as_str())
     }
 
     pub fn pkg_type(&self) -> PackageType {
         self.pkg_type
     }
 
+    /// The `[sdk]` table, required by `sdk` packages.
+    pub fn sdk(&self) -> Option<&Sdk> {
+        self.sdk.as_ref()
+    }
+
+    /// The Kconfig file of the package, relative to its folder: `Kconfig`
+    /// unless `kconfig.file` says otherwise.
+    pub fn kconfig(&self) -> &str {
+        &self.kconfig
+    }
+
+    /// The defconfig fragments of `kconfig.fragments`, applied in order
+    /// before `.config`.
+    pub fn kconfig_fragments(&self) -> &[String] {
+        &self.kconfig_fragments
+    }
+
+    /// The options of the `[config]` table.
+    pub fn config(&self) -> &ConfigSchema {
+        &self.config
+    }
+
+    /// The `edition` of the manifest, or the first edition when it's not set.
+    pub fn edition(&self) -> Edition {
+        self.edition
+    }
+
+    pub fn description(&self) -> Option<&str> {
+        self.description.as_deref()
+    }
+
+    /// The SPDX expression of the license, such as `MIT OR Apache-2.0`.
+    pub fn license(&self) -> Option<&str> {
+        self.license.as_deref()
+    }
+
+    pub fn authors(&self) -> &[String] {
+        &self.authors
+    }
+
+    pub fn repository(&self) -> Option<&str> {
+        self.repository.as_deref()
+    }
+
+    /// The words `tailor search` also matches the package with.
+    pub fn keywords(&self) -> &[String] {
+        &self.keywords
+    }
+
+    /// The version of the package, empty for a virtual manifest.
     pub fn version(&self) -> &str {
-        &self.version
+        self.identity.as_ref().map_or("", |id| id.version.as_str())
     }
 
     pub fn from_file(filepath: &Path) -> Result<Self, String> {
@@ -72,22 +186,103 @@ impl Package {
     pub fn from_content(content: &str) -> Result<Self, String> {
         let parsed = content
             .parse::<Table>()
-            .map_err(|_| format!("Invalid Toml format"))?;
+            .map_err(|_| "Invalid Toml format".to_string())?;
 
-        let name = parsed
-            .get("name")
-            .and_then(|v| v.as_str())
-            .ok_or("Missing 'name' field")?
-            .to_string();
+        let workspace_members = parsed
+            .get("workspace")
+            .map(|v| {
+                v.get("members")
+                    .and_then(|v| v.as_array())
+                    .and_then(|arr| {
+                        arr.iter()
+                            .map(|v| v.as_str().map(String::from))
+                            .collect::<Option<Vec<String>>>()
+                    })
+                    .ok_or("workspace members must be a list of strings")
+            })
+            .transpose()?;
+
+        // A workspace root without `name` is a virtual manifest: it only
+        // groups the members and isn't a package itself.
+        let is_virtual = workspace_members.is_some() && parsed.get("name").is_none();
 
-        let dependencies = parsed
-            .get("dependencies")
+        let identity = if is_virtual {
+            None
+        } else {
+            Some(Identity {
+                name: parsed
+                    .get("name")
+                    .and_then(|v| v.as_str())
+                    .ok_or("Missing 'name' field")?
+                    .to_string(),
+                version: parsed
+                    .get("version")
+                    .and_then(|v| v.as_str())
+                    .ok_or("Missing 'version' field")?
+                    .to_string(),
+            })
+        };
+        let name = identity.as_ref().map_or("", |id| id.name.as_str());
+
+        let edition = match parsed.get("edition") {
+            Some(edition) => edition
+                .as_str()
+                .ok_or("edition must be a string")?
+                .try_into()?,
+            None => Edition::default(),
+        };
+
+        let kinds = [
+            DependencyKind::Normal,
+            DependencyKind::Dev,
+            DependencyKind::Build,
+        ];
+        let parse_dependencies = |table: &Table, kind: DependencyKind| {
+            table
+                .get(kind.table())
+                .and_then(|v| v.as_table())
+                .into_iter()
+                .flatten()
+                .map(move |(k, v)| Dependency::from_content(k, v).map(|dep| dep.with_kind(kind)))
+                .collect::<Vec<Result<Dependency, String>>>()
+        };
+
+        let mut dependencies = kinds
+            .iter()
+            .flat_map(|kind| parse_dependencies(&parsed, *kind))
+            .collect::<Vec<Result<Dependency, String>>>();
+
+        // `[target.<name>]` declares a target, while `[target.<name>.dependencies]`
+        // and `[target.'cfg(...)'.dependencies]` (or `dev-dependencies` and
+        // `build-dependencies`) declare conditional dependencies.
+        let mut targets = vec![];
+        for (key, content) in parsed
+            .get("target")
             .and_then(|v| v.as_table())
-            .map_or(vec![], |deps| {
-                deps.iter()
-                    .map(|(k, v)| Dependency::from_content(k, v))
-                    .collect()
-            });
+            .into_iter()
+            .flatten()
+        {
+            let table = content
+                .as_table()
+                .ok_or_else(|| format!("target '{}' must be a table", key))?;
+
+            let condition = Condition::parse(key)?;
+            for kind in kinds {
+                dependencies.extend(
+                    parse_dependencies(table, kind)
+                        .into_iter()
+                        .map(|dep| dep.map(|dep| dep.with_condition(condition.clone()))),
+                );
+            }
+
+            if !key.starts_with("cfg(")
+                && table
+                    .keys()
+                    .any(|k| kinds.iter().all(|kind| kind.table() != k))
+            {
+                targets.push(Target::from_content(key, content)?);
+            }
+        }
 
         if dependencies.iter().any(|dep| dep.is_err()) {
             let invalid_dependencies = dependencies
@@ -101,21 +296,94 @@ impl Package {
             ));
         }
 
+        // `type` is `bin`, `lib` or `sdk`. Manifests before the 2025.2 edition
+        // may mark libraries with a `lib` key instead.
+        let pkg_type = match parsed.get("type").map(|v| v.as_str()) {
+            None if parsed.get("lib").is_some() => {
+                if edition >= Edition::E2025_2 {
+                    return Err(format!(
+                        "the `lib` key was replaced by `type = \"lib\"` in edition `{}`",
+                        Edition::E2025_2
+                    ));
+                }
+                PackageType::Library
+            }
+            None | Some(Some("bin")) => PackageType::Binary,
+            Some(Some("lib")) => PackageType::Library,
+            Some(Some("sdk")) => PackageType::Sdk,
+            Some(_) => return Err("type must be \"bin\", \"lib\" or \"sdk\"".to_string()),
+        };
+        let sdk = parsed.get("sdk").map(Sdk::from_content).transpose()?;
+        if matches!(pkg_type, PackageType::Sdk) && sdk.is_none() {
+            return Err(format!("sdk package '{}' must have a [sdk] table", name));
+        }
+
+        let string_field = |key: &str| {
+            parsed
+                .get(key)
+                .map(|v| {
+                    v.as_str()
+                        .map(String::from)
+                        .ok_or(format!("{} must be a string", key))
+                })
+                .transpose()
+        };
+        let list_field = |key: &str| {
+            parsed
+                .get(key)
+                .map(|v| {
+                    v.as_array()
+                        .and_then(|arr| {
+                            arr.iter()
+                                .map(|v| v.as_str().map(String::from))
+                                .collect::<Option<Vec<String>>>()
+                        })
+                        .ok_or(format!("{} must be a list of strings", key))
+                })
+                .transpose()
+                .map(Option::unwrap_or_default)
+        };
+
+        let kconfig = parsed
+            .get("kconfig")
+            .map(|v| v.as_table().ok_or("kconfig must be a table"))
+            .transpose()?;
+        let kconfig_file = kconfig
+            .and_then(|t| t.get("file"))
+            .map(|v| v.as_str().ok_or("kconfig.file must be a path"))
+            .transpose()?
+            .unwrap_or("Kconfig")
+            .to_string();
+        let kconfig_fragments = kconfig
+            .and_then(|t| t.get("fragments"))
+            .map(|v| {
+                v.as_array()
+                    .and_then(|arr| {
+                        arr.iter()
+                            .map(|v| v.as_str().map(String::from))
+                            .collect::<Option<Vec<String>>>()
+                    })
+                    .ok_or("kconfig.fragments must be a list of paths")
+            })
+            .transpose()?
+            .unwrap_or_default();
+
         Ok(Package {
-            name,
-            version: parsed
-                .get("version")
-                .and_then(|v| v.as_str())
-                .ok_or("Missing 'version' field")?
-                .to_string(),
+            edition,
+            description: string_field("description")?,
+            license: string_field("license")?,
+            authors: list_field("authors")?,
+            repository: string_field("repository")?,
+            keywords: list_field("keywords")?,
             dependencies: dependencies.into_iter().filter_map(Result::ok).collect(),
-            pkg_type: if parsed.get("lib").is_some() {
-                PackageType::Library
-            } else {
-                PackageType::Binary
+            main: match string_field("main")? {
+                Some(main) => Some(main),
+                None if matches!(pkg_type, PackageType::Library) => None,
+                None => Some("src/main.c".to_string()),
             },
+            pkg_type,
             sources: parsed.get("src").and_then(|v| v.as_array()).map_or(
-                vec!["src/*.c".to_string()],
+                vec![edition.default_sources().to_string()],
                 |arr| {
                     arr.iter()
                         .map(|v| {
@@ -138,6 +406,15 @@ impl Package {
                         .collect()
                 },
             ),
+            targets,
+            profiles: parse_profiles(parsed.get("profile"))?,
+            workspace_members,
+            features: parse_features(parsed.get("features"))?,
+            sdk,
+            kconfig: kconfig_file,
+            kconfig_fragments,
+            config: ConfigSchema::from_content(name, parsed.get("config"))?,
+            identity,
         })
     }
 }
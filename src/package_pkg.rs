@@ -0,0 +1,320 @@
+use std::{
+    fs::File,
+    io::Read,
+    path::{Path, PathBuf},
+};
+
+use flate2::{Compression, read::GzDecoder, write::GzEncoder};
+
+use crate::{
+    build_pkg::BuildPkg,
+    command::Command,
+    dependency::{DependencyKind, Source},
+    fmt::{info, success, warning},
+    git::git_package_files,
+    package::{Package, PackageType},
+    registry::sha256,
+};
+
+#[derive(Default)]
+pub struct PackagePkg {
+    build: BuildPkg,
+    no_verify: bool,
+}
+
+/// The `.tar.gz` of a package, with its SHA-256 checksum.
+pub struct Tarball {
+    pub path: PathBuf,
+    pub checksum: String,
+}
+
+/// Checks that `pkg` can be used from a registry: it must be a library with a
+/// `<major>.<minor>.<patch>` version, and it can't need files outside of it,
+/// so its dependencies can't be local paths, Git repositories nor overlay
+/// manifests. Dev-dependencies are only used by its own tests, so they are
+/// not checked. Its keywords must be single words.
+pub fn verify_manifest(pkg: &Package) -> Result<(), String> {
+    if pkg.is_virtual() {
+        return Err("a virtual workspace can't be packaged".to_string());
+    }
+    if !matches!(pkg.pkg_type(), PackageType::Library) {
+        return Err(format!(
+            "`{}` is not a `lib` package, only libraries can be dependencies",
+            pkg.name()
+        ));
+    }
+
+    let release = pkg.version().split(['-', '+']).next().unwrap_or_default();
+    let parts = release.split('.').collect::<Vec<&str>>();
+    if parts.len() != 3 || parts.iter().any(|p| p.parse::<u64>().is_err()) {
+        return Err(format!(
+            "version `{}` of `{}` must be `<major>.<minor>.<patch>`",
+            pkg.version(),
+            pkg.name()
+        ));
+    }
+
+    if let Some(keyword) = pkg
+        .keywords()
+        .iter()
+        .find(|k| k.is_empty() || k.contains(char::is_whitespace))
+    {
+        return Err(format!(
+            "keyword `{}` of `{}` must be a single word",
+            keyword,
+            pkg.name()
+        ));
+    }
+
+    for dependency in pkg.dependencies() {
+        if dependency.kind() == DependencyKind::Dev {
+            continue;
+        }
+        let source = match dependency.source() {
+            Source::Local { .. } => "a local path",
+            Source::Git { .. } => "a Git repository",
+            Source::Registry { .. } | Source::System { .. } => {
+                if dependency.manifest().is_none() {
+                    continue;
+                }
+                "an overlay manifest"
+            }
+        };
+        return Err(format!(
+            "dependency `{}` of `{}` uses {}, which isn't available from the registry",
+            dependency.name(),
+            pkg.name(),
+            source
+        ));
+    }
+
+    Ok(())
+}
+
+/// Whether `file`, relative to the package at `abs_path`, is left out of the
+/// tarball: the hidden files, such as `.git` and `.config`, and the build
+/// files.
+fn is_excluded(file: &Path, abs_path: &Path, output_dir: &Path) -> bool {
+    let hidden = file
+        .components()
+        .any(|c| c.as_os_str().to_string_lossy().starts_with('.'));
+    let full_path = abs_path.join(file);
+    hidden || full_path.starts_with(output_dir) || full_path.starts_with(abs_path.join("build"))
+}
+
+/// The files of the package at `abs_path`, relative to it. Inside a Git
+/// repository, these are the files Git doesn't ignore, so `.gitignore` also
+/// applies to the tarball. Otherwise, all the files are packaged. The hidden
+/// files and the build files are always left out.
+fn package_files(abs_path: &Path, output_dir: &Path) -> Result<Vec<PathBuf>, String> {
+    fn collect(
+        dir: &Path,
+        abs_path: &Path,
+        output_dir: &Path,
+        files: &mut Vec<PathBuf>,
+    ) -> Result<(), String> {
+        let entries = std::fs::read_dir(dir)
+            .map_err(|e| format!("fail to read directory {}: {}", dir.display(), e))?;
+        for entry in entries {
+            let path = entry
+                .map_err(|e| format!("fail to read directory {}: {}", dir.display(), e))?
+                .path();
+            let Ok(relative) = path.strip_prefix(abs_path) else {
+                continue;
+            };
+            if is_excluded(relative, abs_path, output_dir) {
+                continue;
+            }
+
+            if path.is_dir() {
+                collect(&path, abs_path, output_dir, files)?;
+            } else {
+                files.push(relative.to_path_buf());
+            }
+        }
+        Ok(())
+    }
+
+    let mut files = match git_package_files(abs_path) {
+        Some(files) => files,
+        None => {
+            let mut files = vec![];
+            collect(abs_path, abs_path, output_dir, &mut files)?;
+            files
+        }
+    };
+    files.retain(|file| !is_excluded(file, abs_path, output_dir));
+    files.sort();
+    Ok(files)
+}
+
+/// Extracts the `.tar.gz` at `tarball` into `dir`.
+pub fn extract_tarball(tarball: &Path, dir: &Path) -> Result<(), String> {
+    let file =
+        File::open(tarball).map_err(|e| format!("fail to read {}: {}", tarball.display(), e))?;
+    tar::Archive::new(GzDecoder::new(file))
+        .unpack(dir)
+        .map_err(|e| format!("fail to extract {}: {}", tarball.display(), e))
+}
+
+/// The content of the file at `path` inside the `.tar.gz` at `tarball`.
+pub fn read_tarball_file(tarball: &Path, path: &Path) -> Result<String, String> {
+    let file =
+        File::open(tarball).map_err(|e| format!("fail to read {}: {}", tarball.display(), e))?;
+    let mut archive = tar::Archive::new(GzDecoder::new(file));
+    let entries = archive
+        .entries()
+        .map_err(|e| format!("fail to read {}: {}", tarball.display(), e))?;
+
+    for entry in entries {
+        let mut entry = entry.map_err(|e| format!("fail to read {}: {}", tarball.display(), e))?;
+        if entry.path().is_ok_and(|p| p == path) {
+            let mut content = String::new();
+            entry
+                .read_to_string(&mut content)
+                .map_err(|e| format!("fail to read {}: {}", path.display(), e))?;
+            return Ok(content);
+        }
+    }
+
+    Err(format!(
+        "{} not found in {}",
+        path.display(),
+        tarball.display()
+    ))
+}
+
+/// Writes the files of `pkg` into `<output>/package/<name>-<version>.tar.gz`,
+/// inside a `<name>-<version>` folder. The entries are sorted and have no
+/// timestamps, so the same files always give the same checksum. When
+/// `verify` is set, the tarball is extracted into a temporary folder and
+/// built from there, which catches files missing from it.
+pub fn create_tarball(
+    build: &BuildPkg,
+    pkg: &Package,
+    abs_path: &Path,
+    verify: bool,
+) -> Result<Tarball, String> {
+    verify_manifest(pkg)?;
+
+    let missing = [
+        ("description", pkg.description().is_none()),
+        ("license", pkg.license().is_none()),
+        ("authors", pkg.authors().is_empty()),
+        ("repository", pkg.repository().is_none()),
+    ]
+    .iter()
+    .filter(|(_, missing)| *missing)
+    .map(|(key, _)| format!("`{}`", key))
+    .collect::<Vec<String>>();
+    if !missing.is_empty() {
+        println!(
+            "{}: `{}` has no {}, shown by `tailor search` and `tailor info`",
+            warning(),
+            pkg.name(),
+            missing.join(", ")
+        );
+    }
+
+    let output_dir = build.output_dir(pkg, abs_path)?;
+    let files = package_files(abs_path, &output_dir)?;
+    let root = format!("{}-{}", pkg.name(), pkg.version());
+    println!(
+        "{} `{}` @ {} ({} files)",
+        success("Packaging"),
+        pkg.name(),
+        pkg.version(),
+        files.len()
+    );
+
+    let dir = output_dir.join("package");
+    std::fs::create_dir_all(&dir)
+        .map_err(|e| format!("fail to create directory {}: {}", dir.display(), e))?;
+    let path = dir.join(format!("{}.tar.gz", root));
+
+    let tarball =
+        File::create(&path).map_err(|e| format!("fail to create {}: {}", path.display(), e))?;
+    let mut archive = tar::Builder::new(GzEncoder::new(tarball, Compression::default()));
+    for file in &files {
+        let full_path = abs_path.join(file);
+        let content = std::fs::read(&full_path)
+            .map_err(|e| format!("fail to read file {}: {}", full_path.display(), e))?;
+
+        let mut header = tar::Header::new_gnu();
+        header.set_size(content.len() as u64);
+        header.set_mode(0o644);
+        header.set_mtime(0);
+        archive
+            .append_data(&mut header, Path::new(&root).join(file), content.as_slice())
+            .map_err(|e| format!("fail to write {}: {}", path.display(), e))?;
+    }
+    archive
+        .into_inner()
+        .and_then(|encoder| encoder.finish())
+        .map_err(|e| format!("fail to write {}: {}", path.display(), e))?;
+
+    if verify {
+        println!("{} `{}` @ {}", info("Verifying"), pkg.name(), pkg.version());
+        let temp = tempfile::tempdir()
+            .map_err(|e| format!("fail to create a temporary directory: {}", e))?;
+        extract_tarball(&path, temp.path())?;
+
+        let extracted = temp.path().join(&root);
+        build
+            .isolated(&extracted, &temp.path().join("build"))
+            .build_package(&extracted, None)
+            .map_err(|e| format!("fail to verify `{}`: {}", pkg.name(), e))?;
+    }
+
+    let content =
+        std::fs::read(&path).map_err(|e| format!("fail to read {}: {}", path.display(), e))?;
+    let checksum = sha256(&content);
+    let checksum_path = dir.join(format!("{}.tar.gz.sha256", root));
+    std::fs::write(&checksum_path, format!("{}  {}.tar.gz\n", checksum, root))
+        .map_err(|e| format!("fail to write {}: {}", checksum_path.display(), e))?;
+
+    println!(
+        "{} `{}` @ {} at {} (sha256 {})",
+        success("Packaged"),
+        pkg.name(),
+        pkg.version(),
+        path.display(),
+        checksum
+    );
+
+    Ok(Tarball { path, checksum })
+}
+
+impl Command for PackagePkg {
+    fn parse_args(&mut self, args: &[String]) -> Option<()> {
+        if args.is_empty() || args[0] != "package" {
+            return None;
+        }
+
+        let mut i = 1;
+        while i < args.len() {
+            if args[i] == "--no-verify" {
+                self.no_verify = true;
+                i += 1;
+                continue;
+            }
+            i += self.build.parse_arg(&args[i..])?;
+        }
+
+        self.build.finish_args()
+    }
+
+    fn execute(&self) -> Result<(), String> {
+        let (_, paths) = self.build.selected_packages()?;
+
+        for path in paths {
+            let pkg = Package::from_file(&path.join("Tailor.toml"))?;
+            if pkg.is_virtual() {
+                continue;
+            }
+            create_tarball(&self.build, &pkg, &path, !self.no_verify)?;
+        }
+
+        Ok(())
+    }
+}
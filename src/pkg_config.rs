@@ -0,0 +1,94 @@
+use std::process::{Command, Stdio};
+
+/// The flags of a system library, as reported by pkg-config.
+#[derive(Debug)]
+pub struct SystemLibrary {
+    pub includes: Vec<String>,
+    pub cflags: Vec<String>,
+    pub libs: Vec<String>,
+}
+
+/// The pkg-config query for `module`, with the `version` requirement, such as
+/// `>=1.2`, if any. A version without an operator is a minimum version.
+fn query(module: &str, version: Option<&str>) -> String {
+    let Some(version) = version.map(str::trim) else {
+        return module.to_string();
+    };
+
+    let operator_len = version
+        .find(|c: char| !matches!(c, '<' | '>' | '=' | '!'))
+        .unwrap_or(version.len());
+    let (operator, version) = version.split_at(operator_len);
+    let operator = match operator {
+        "" => ">=",
+        "==" => "=",
+        operator => operator,
+    };
+
+    format!("{} {} {}", module, operator, version.trim())
+}
+
+fn pkg_config(args: &[&str]) -> Result<std::process::Output, String> {
+    Command::new("pkg-config")
+        .args(args)
+        .stdin(Stdio::null())
+        .output()
+        .map_err(|e| format!("fail to execute pkg-config: {}", e))
+}
+
+fn flags(module: &str, flag: &str) -> Result<Vec<String>, String> {
+    let output = pkg_config(&[flag, module])?;
+    if !output.status.success() {
+        return Err(format!(
+            "pkg-config {} {} failed: {}",
+            flag,
+            module,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .split_whitespace()
+        .map(String::from)
+        .collect())
+}
+
+/// Looks up the system library `module` with pkg-config.
+pub fn probe(name: &str, module: &str, version: Option<&str>) -> Result<SystemLibrary, String> {
+    let query = query(module, version);
+
+    let output = pkg_config(&["--exists", "--print-errors", &query])?;
+    if !output.status.success() {
+        return Err(format!(
+            "system library `{}` (`{}`) was not found by pkg-config, install it or set \
+             PKG_CONFIG_PATH to the folder of its `.pc` file:\n{}",
+            name,
+            query,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+
+    Ok(SystemLibrary {
+        includes: flags(module, "--cflags-only-I")?
+            .iter()
+            .map(|flag| flag.trim_start_matches("-I").to_string())
+            .collect(),
+        cflags: flags(module, "--cflags-only-other")?,
+        libs: flags(module, "--libs")?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn version_requirements_become_queries() {
+        assert_eq!(query("zlib", None), "zlib");
+        assert_eq!(query("zlib", Some("1.2")), "zlib >= 1.2");
+        assert_eq!(query("zlib", Some(" >=1.2.11 ")), "zlib >= 1.2.11");
+        assert_eq!(query("zlib", Some("== 1.2")), "zlib = 1.2");
+        assert_eq!(query("openssl", Some("<3")), "openssl < 3");
+        assert_eq!(query("openssl", Some("!=3.0.1")), "openssl != 3.0.1");
+    }
+}
@@ -0,0 +1,55 @@
+use toml::Value;
+
+use crate::target::parse_command;
+
+/// Settings for a build mode, declared as `[profile.debug]` or
+/// `[profile.release]` in `Tailor.toml` or in the global config.
+#[derive(Debug, Clone)]
+pub struct Profile {
+    name: String,
+    runner: Option<Vec<String>>,
+}
+
+impl Profile {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn runner(&self) -> Option<&[String]> {
+        self.runner.as_deref()
+    }
+
+    pub fn from_content(name: &str, content: &Value) -> Result<Self, String> {
+        if name != "debug" && name != "release" {
+            return Err(format!(
+                "unknown profile '{}', expected 'debug' or 'release'",
+                name
+            ));
+        }
+
+        let table = content
+            .as_table()
+            .ok_or_else(|| format!("profile '{}' must be a table", name))?;
+
+        Ok(Self {
+            name: name.to_string(),
+            runner: table
+                .get("runner")
+                .map(|v| {
+                    parse_command(v).ok_or_else(|| format!("invalid runner for profile '{}'", name))
+                })
+                .transpose()?,
+        })
+    }
+}
+
+/// Parses the `[profile.*]` tables of a manifest or config file.
+pub fn parse_profiles(content: Option<&Value>) -> Result<Vec<Profile>, String> {
+    match content.and_then(|v| v.as_table()) {
+        Some(profiles) => profiles
+            .iter()
+            .map(|(k, v)| Profile::from_content(k, v))
+            .collect(),
+        None => Ok(vec![]),
+    }
+}
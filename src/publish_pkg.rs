@@ -0,0 +1,107 @@
+use crate::{
+    build_pkg::BuildPkg,
+    command::{Command, parse_flag_value},
+    fmt::Progress,
+    git::git_dirty_files,
+    package::Package,
+    package_pkg::{create_tarball, verify_manifest},
+    registry::Registry,
+};
+
+#[derive(Default)]
+pub struct PublishPkg {
+    build: BuildPkg,
+    no_verify: bool,
+    allow_dirty: bool,
+    token: Option<String>,
+    registry: Option<String>,
+}
+
+impl Command for PublishPkg {
+    fn parse_args(&mut self, args: &[String]) -> Option<()> {
+        if args.is_empty() || args[0] != "publish" {
+            return None;
+        }
+
+        let mut i = 1;
+        while i < args.len() {
+            if args[i] == "--no-verify" {
+                self.no_verify = true;
+                i += 1;
+                continue;
+            }
+            if args[i] == "--allow-dirty" {
+                self.allow_dirty = true;
+                i += 1;
+                continue;
+            }
+            if let Some((registry, consumed)) = parse_flag_value(&args[i..], &["--registry"]) {
+                self.registry = Some(registry.to_string());
+                i += consumed;
+                continue;
+            }
+            if let Some((token, consumed)) = parse_flag_value(&args[i..], &["--token"]) {
+                self.token = Some(token.to_string());
+                i += consumed;
+                continue;
+            }
+            i += self.build.parse_arg(&args[i..])?;
+        }
+
+        self.build.finish_args()
+    }
+
+    fn execute(&self) -> Result<(), String> {
+        let (_, paths) = self.build.selected_packages()?;
+        let registry = Registry::named(self.registry.as_deref())?;
+        let token = registry.token(self.token.as_deref())?;
+
+        for path in paths {
+            let pkg = Package::from_file(&path.join("Tailor.toml"))?;
+            if pkg.is_virtual() {
+                continue;
+            }
+            verify_manifest(&pkg)?;
+
+            let dirty = git_dirty_files(&path)?;
+            if !dirty.is_empty() && !self.allow_dirty {
+                return Err(format!(
+                    "`{}` has uncommitted changes, commit them or use `--allow-dirty`:\n\t- {}",
+                    pkg.name(),
+                    dirty.join("\n\t- ")
+                ));
+            }
+
+            if registry.is_published(pkg.name(), pkg.version())? {
+                return Err(format!(
+                    "`{}` @ {} is already published in {}",
+                    pkg.name(),
+                    pkg.version(),
+                    registry.url()
+                ));
+            }
+
+            let tarball = create_tarball(&self.build, &pkg, &path, !self.no_verify)?;
+            let content = std::fs::read(&tarball.path)
+                .map_err(|e| format!("fail to read {}: {}", tarball.path.display(), e))?;
+
+            let upload = Progress::new(
+                "Uploading",
+                format!("{} @ {} to {}", pkg.name(), pkg.version(), registry.url()),
+            );
+            registry.upload(
+                pkg.name(),
+                pkg.version(),
+                content,
+                &tarball.checksum,
+                &token,
+            )?;
+            upload.finish(
+                "Published",
+                format!("{} @ {} to {}", pkg.name(), pkg.version(), registry.url()),
+            );
+        }
+
+        Ok(())
+    }
+}
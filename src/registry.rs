@@ -0,0 +1,620 @@
+use std::{cell::OnceCell, path::PathBuf};
+
+use reqwest::{
+    StatusCode,
+    blocking::{RequestBuilder, Response},
+};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+
+use crate::{
+    config::Config,
+    credentials::{Credentials, helper_store, helper_token},
+    mirror::{RegistryMirror, registry_mirror},
+    network::{Network, describe},
+    version::{Version, VersionReq},
+};
+
+/// The registry used when neither `TAILOR_REGISTRY` nor the `registry` key of
+/// the config set one.
+const DEFAULT_REGISTRY: &str = "http://127.0.0.1:5000";
+
+/// A dependency of a published version, as listed by the index.
+#[derive(Debug, Clone)]
+pub struct IndexDependency {
+    pub name: String,
+    /// The version required, or `None` for system libraries.
+    pub version: Option<String>,
+    /// `normal`, `dev` or `build`.
+    pub kind: String,
+}
+
+/// A published version of a package: a line of its index file.
+#[derive(Debug, Clone)]
+pub struct IndexEntry {
+    name: String,
+    version: String,
+    checksum: String,
+    dependencies: Vec<IndexDependency>,
+    yanked: bool,
+}
+
+impl IndexEntry {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn version(&self) -> &str {
+        &self.version
+    }
+
+    /// The SHA-256 of the tarball, in hexadecimal.
+    pub fn checksum(&self) -> &str {
+        &self.checksum
+    }
+
+    pub fn dependencies(&self) -> &[IndexDependency] {
+        &self.dependencies
+    }
+
+    pub fn yanked(&self) -> bool {
+        self.yanked
+    }
+
+    fn from_json(line: &str) -> Option<Self> {
+        let json = serde_json::from_str::<Value>(line).ok()?;
+        let field = |key: &str| json.get(key).and_then(Value::as_str).map(String::from);
+
+        let dependencies = match json.get("dependencies") {
+            Some(dependencies) => dependencies
+                .as_array()?
+                .iter()
+                .map(|dep| {
+                    Some(IndexDependency {
+                        name: dep.get("name")?.as_str()?.to_string(),
+                        version: dep.get("version").and_then(Value::as_str).map(String::from),
+                        kind: dep
+                            .get("kind")
+                            .and_then(Value::as_str)
+                            .unwrap_or("normal")
+                            .to_string(),
+                    })
+                })
+                .collect::<Option<Vec<IndexDependency>>>()?,
+            None => vec![],
+        };
+
+        Some(Self {
+            name: field("name")?,
+            version: field("version")?,
+            checksum: field("checksum")?,
+            dependencies,
+            yanked: json.get("yanked").and_then(Value::as_bool).unwrap_or(false),
+        })
+    }
+}
+
+/// A package found by a search, at its latest version.
+#[derive(Debug, Clone)]
+pub struct SearchResult {
+    pub name: String,
+    pub version: String,
+    pub description: Option<String>,
+}
+
+/// The newest version among `entries` that wasn't yanked.
+pub fn latest(entries: &[IndexEntry]) -> Option<&IndexEntry> {
+    entries
+        .iter()
+        .filter(|e| !e.yanked)
+        .max_by_key(|e| Version::parse(&e.version))
+}
+
+/// The newest version among `entries` matching `requirement`: among the
+/// yanked ones when `yanked` is set, or else among the others.
+pub fn newest_matching<'a>(
+    entries: &'a [IndexEntry],
+    requirement: &VersionReq,
+    yanked: bool,
+) -> Option<&'a IndexEntry> {
+    entries
+        .iter()
+        .filter(|e| e.yanked == yanked)
+        .filter_map(|e| Some((e, Version::parse(&e.version)?)))
+        .filter(|(_, version)| requirement.matches(version))
+        .max_by(|(_, a), (_, b)| a.cmp(b))
+        .map(|(e, _)| e)
+}
+
+/// Parses an index file, one JSON entry per line.
+fn parse_index(name: &str, content: &str) -> Result<Vec<IndexEntry>, String> {
+    content
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| {
+            IndexEntry::from_json(line)
+                .ok_or_else(|| format!("invalid index entry of `{}`: {}", name, line))
+        })
+        .collect()
+}
+
+/// The SHA-256 of `content`, in hexadecimal.
+pub fn sha256(content: &[u8]) -> String {
+    Sha256::digest(content)
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// A package registry, reached by HTTP. It serves:
+///
+/// - `GET <url>/index/<name>`: the index of a package, a JSON line per
+///   published version with its checksum, dependencies and yanked status;
+/// - `GET <url>/api/v1/packages/<name>/<version>/download`: the tarball of a
+///   version;
+/// - `PUT <url>/api/v1/packages/<name>/<version>`: publishes a tarball;
+/// - `DELETE` and `PUT <url>/api/v1/packages/<name>/<version>/yank`: yanks a
+///   version and undoes it;
+/// - `GET`, `PUT` and `DELETE <url>/api/v1/packages/<name>/owners`: lists,
+///   adds and removes owners;
+/// - `GET <url>/api/v1/search?q=<query>&limit=<n>`: the packages matching a
+///   query, with their latest version and description.
+///
+/// The stored token of the registry, if any, is sent with every request, also
+/// to its mirror.
+pub struct Registry {
+    url: String,
+    /// Where the packages are fetched from instead of `url`, set by the
+    /// `[source]` table of the config.
+    mirror: Option<RegistryMirror>,
+    credential_helper: Option<String>,
+    token: OnceCell<Option<String>>,
+    network: Network,
+}
+
+impl Registry {
+    /// The registry set by `TAILOR_REGISTRY`, by the config or the default one,
+    /// fetched from its mirror if the `[source]` table replaces it.
+    pub fn from_config() -> Result<Self, String> {
+        let config = Config::load()?;
+        let mut registry = Self::with_config(&config, None)?;
+        let configured = config.registry().unwrap_or(DEFAULT_REGISTRY);
+        registry.mirror =
+            registry_mirror(&config, &registry.url, configured.trim_end_matches('/'))?;
+        Ok(registry)
+    }
+
+    /// The registry `name` of the `[registries]` table of the config, or the
+    /// default registry when there's no name.
+    pub fn named(name: Option<&str>) -> Result<Self, String> {
+        Self::with_config(&Config::load()?, name)
+    }
+
+    fn with_config(config: &Config, name: Option<&str>) -> Result<Self, String> {
+        let (url, credential_helper) = match name {
+            Some(name) => {
+                let registry = config.named_registry(name).ok_or_else(|| {
+                    format!(
+                        "registry `{}` is not defined in the [registries] table of the config",
+                        name
+                    )
+                })?;
+                (
+                    registry.url().to_string(),
+                    registry.credential_helper().or(config.credential_helper()),
+                )
+            }
+            None => (
+                std::env::var("TAILOR_REGISTRY")
+                    .unwrap_or_else(|_| config.registry().unwrap_or(DEFAULT_REGISTRY).to_string()),
+                config.credential_helper(),
+            ),
+        };
+
+        Ok(Self {
+            url: url.trim_end_matches('/').to_string(),
+            mirror: None,
+            credential_helper: credential_helper.map(String::from),
+            token: OnceCell::new(),
+            network: Network::new(config.http())?,
+        })
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// The URL the requests are sent to: the one of the mirror, if any. The
+    /// cache stays the one of the registry, so switching mirrors keeps it.
+    fn remote(&self) -> &str {
+        match &self.mirror {
+            Some(RegistryMirror::Url(url)) => url,
+            _ => &self.url,
+        }
+    }
+
+    fn package_url(&self, name: &str, version: &str) -> String {
+        format!("{}/api/v1/packages/{}/{}", self.remote(), name, version)
+    }
+
+    /// The folder caching the index files and the tarballs of this registry,
+    /// so they are downloaded once.
+    fn cache_dir(&self) -> PathBuf {
+        let host = self
+            .url
+            .split_once("://")
+            .map_or(self.url.as_str(), |(_, host)| host)
+            .replace(|c: char| !c.is_ascii_alphanumeric(), "-");
+        Config::dir().join("registry").join(host)
+    }
+
+    /// All the published versions of `name`, fetched from the registry. The
+    /// cached index is used when the registry can't be reached.
+    pub fn entries(&self, name: &str) -> Result<Vec<IndexEntry>, String> {
+        if let Some(RegistryMirror::Directory(dir)) = &self.mirror {
+            return match std::fs::read_to_string(dir.join("index").join(name)) {
+                Ok(content) => parse_index(name, &content),
+                Err(_) => Ok(vec![]),
+            };
+        }
+
+        let cached = self.cache_dir().join("index").join(name);
+
+        let res = match self
+            .network
+            .send(self.get(format!("{}/index/{}", self.remote(), name))?)
+        {
+            Ok(res) => res,
+            Err(e) => {
+                return match std::fs::read_to_string(&cached) {
+                    Ok(content) => parse_index(name, &content),
+                    Err(_) => Err(format!("fail to reach registry {}: {}", self.remote(), e)),
+                };
+            }
+        };
+        if res.status() == StatusCode::NOT_FOUND {
+            return Ok(vec![]);
+        }
+        if !res.status().is_success() {
+            return Err(self.failure(res));
+        }
+
+        let content = res
+            .text()
+            .map_err(|e| format!("fail to read the index of `{}`: {}", name, describe(&e)))?;
+        let entries = parse_index(name, &content)?;
+
+        if let Some(dir) = cached.parent() {
+            std::fs::create_dir_all(dir)
+                .map_err(|e| format!("fail to create directory {}: {}", dir.display(), e))?;
+        }
+        std::fs::write(&cached, content)
+            .map_err(|e| format!("fail to write {}: {}", cached.display(), e))?;
+
+        Ok(entries)
+    }
+
+    /// The index entry of `version` of `name`, from the cached index when it
+    /// has it, or else from the registry.
+    pub fn entry(&self, name: &str, version: &str) -> Result<Option<IndexEntry>, String> {
+        let cached = self.cache_dir().join("index").join(name);
+        if let Ok(content) = std::fs::read_to_string(&cached)
+            && let Some(entry) = parse_index(name, &content)?
+                .into_iter()
+                .find(|e| e.version() == version)
+        {
+            return Ok(Some(entry));
+        }
+
+        Ok(self
+            .entries(name)?
+            .into_iter()
+            .find(|e| e.version() == version))
+    }
+
+    /// The packages whose name, description or keywords match `query`, up to
+    /// `limit` of them.
+    pub fn search(&self, query: &str, limit: usize) -> Result<Vec<SearchResult>, String> {
+        let request = self
+            .get(format!("{}/api/v1/search", self.remote()))?
+            .query(&[("q", query), ("limit", &limit.to_string())]);
+        let res = self
+            .network
+            .send(request)
+            .map_err(|e| format!("fail to reach registry {}: {}", self.remote(), e))?;
+        if !res.status().is_success() {
+            return Err(self.failure(res));
+        }
+
+        let json = res
+            .text()
+            .ok()
+            .and_then(|content| serde_json::from_str::<Value>(&content).ok())
+            .ok_or_else(|| format!("invalid search results from {}", self.remote()))?;
+        json.get("packages")
+            .and_then(Value::as_array)
+            .and_then(|packages| {
+                packages
+                    .iter()
+                    .map(|package| {
+                        Some(SearchResult {
+                            name: package.get("name")?.as_str()?.to_string(),
+                            version: package.get("version")?.as_str()?.to_string(),
+                            description: package
+                                .get("description")
+                                .and_then(Value::as_str)
+                                .map(String::from),
+                        })
+                    })
+                    .collect::<Option<Vec<SearchResult>>>()
+            })
+            .ok_or_else(|| format!("invalid search results from {}", self.remote()))
+    }
+
+    /// Whether `version` of `name` was already published.
+    pub fn is_published(&self, name: &str, version: &str) -> Result<bool, String> {
+        Ok(self.entries(name)?.iter().any(|e| e.version() == version))
+    }
+
+    /// The tarball of `entry`, downloaded into the cache unless it's already
+    /// there. Its checksum must match the one in the index.
+    pub fn download(&self, entry: &IndexEntry) -> Result<PathBuf, String> {
+        let file = format!("{}-{}.tar.gz", entry.name(), entry.version());
+        let path = self.cache_dir().join("cache").join(&file);
+        if std::fs::read(&path).is_ok_and(|content| sha256(&content) == entry.checksum) {
+            return Ok(path);
+        }
+
+        if let Some(RegistryMirror::Directory(dir)) = &self.mirror {
+            let path = dir.join("cache").join(file);
+            let content = std::fs::read(&path)
+                .map_err(|e| format!("fail to read file {}: {}", path.display(), e))?;
+            self.check_checksum(entry, &content)?;
+            return Ok(path);
+        }
+
+        let url = format!("{}/download", self.package_url(&entry.name, &entry.version));
+        let res = self
+            .network
+            .send(self.get(url.clone())?)
+            .map_err(|e| format!("fail to reach registry {}: {}", self.remote(), e))?;
+        if !res.status().is_success() {
+            return Err(self.failure(res));
+        }
+        let content = res
+            .bytes()
+            .map_err(|e| format!("fail to download {}: {}", url, describe(&e)))?;
+
+        self.check_checksum(entry, &content)?;
+
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)
+                .map_err(|e| format!("fail to create directory {}: {}", dir.display(), e))?;
+        }
+        std::fs::write(&path, content)
+            .map_err(|e| format!("fail to write {}: {}", path.display(), e))?;
+
+        Ok(path)
+    }
+
+    /// Fails when `tarball` doesn't have the checksum of `entry` in the index.
+    fn check_checksum(&self, entry: &IndexEntry, tarball: &[u8]) -> Result<(), String> {
+        let checksum = sha256(tarball);
+        if checksum != entry.checksum {
+            return Err(format!(
+                "checksum of `{}` @ {} doesn't match the index, expected {} but got {}",
+                entry.name, entry.version, entry.checksum, checksum
+            ));
+        }
+        Ok(())
+    }
+
+    /// Sends `request` authenticated by `token`, failing when the registry
+    /// refuses the token.
+    fn send_authorized(&self, request: RequestBuilder, token: &str) -> Result<Response, String> {
+        let res = self
+            .network
+            .send(request.bearer_auth(token))
+            .map_err(|e| format!("fail to reach registry {}: {}", self.remote(), e))?;
+
+        match res.status() {
+            StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => Err(format!(
+                "registry {} refused the token: {}",
+                self.remote(),
+                res.text().unwrap_or_default().trim()
+            )),
+            _ => Ok(res),
+        }
+    }
+
+    /// The error of an unexpected answer of the registry.
+    fn failure(&self, res: Response) -> String {
+        let status = res.status();
+        if status == StatusCode::UNAUTHORIZED {
+            return format!(
+                "registry {} requires a token, run `tailor login` to save it",
+                self.remote()
+            );
+        }
+        format!(
+            "registry {} answered {}: {}",
+            self.remote(),
+            status,
+            res.text().unwrap_or_default().trim()
+        )
+    }
+
+    /// Uploads the `.tar.gz` of `version` of `name`, authenticated by `token`.
+    pub fn upload(
+        &self,
+        name: &str,
+        version: &str,
+        tarball: Vec<u8>,
+        checksum: &str,
+        token: &str,
+    ) -> Result<(), String> {
+        let request = self
+            .network
+            .client()
+            .put(self.package_url(name, version))
+            .header("Content-Type", "application/gzip")
+            .header("X-Checksum-Sha256", checksum)
+            .body(tarball);
+        let res = self.send_authorized(request, token)?;
+
+        match res.status() {
+            status if status.is_success() => Ok(()),
+            // A retry is refused when the registry stored the tarball but
+            // failed to answer the previous try, so the upload succeeded if
+            // the index has this tarball.
+            StatusCode::CONFLICT
+                if self
+                    .entries(name)?
+                    .iter()
+                    .any(|e| e.version() == version && e.checksum() == checksum) =>
+            {
+                Ok(())
+            }
+            StatusCode::CONFLICT => Err(format!("`{}` @ {} is already published", name, version)),
+            _ => Err(self.failure(res)),
+        }
+    }
+
+    /// Yanks `version` of `name`, so it's no longer used by packages that
+    /// don't have it in their lockfile, or undoes it when `undo` is set.
+    pub fn yank(&self, name: &str, version: &str, undo: bool, token: &str) -> Result<(), String> {
+        let url = format!("{}/yank", self.package_url(name, version));
+        let request = match undo {
+            true => self.network.client().put(url),
+            false => self.network.client().delete(url),
+        };
+        let res = self.send_authorized(request, token)?;
+
+        match res.status() {
+            status if status.is_success() => Ok(()),
+            StatusCode::NOT_FOUND => Err(format!(
+                "`{}` @ {} not found in registry {}",
+                name,
+                version,
+                self.remote()
+            )),
+            _ => Err(self.failure(res)),
+        }
+    }
+
+    fn owners_url(&self, name: &str) -> String {
+        format!("{}/api/v1/packages/{}/owners", self.remote(), name)
+    }
+
+    /// The users allowed to publish new versions of `name` and to yank them.
+    pub fn owners(&self, name: &str, token: &str) -> Result<Vec<String>, String> {
+        let res = self.send_authorized(self.network.client().get(self.owners_url(name)), token)?;
+        if res.status() == StatusCode::NOT_FOUND {
+            return Err(format!(
+                "`{}` not found in registry {}",
+                name,
+                self.remote()
+            ));
+        }
+        if !res.status().is_success() {
+            return Err(self.failure(res));
+        }
+
+        res.text()
+            .ok()
+            .and_then(|content| serde_json::from_str::<Value>(&content).ok())
+            .and_then(|json| {
+                json.get("users")?
+                    .as_array()?
+                    .iter()
+                    .map(|user| user.as_str().map(String::from))
+                    .collect()
+            })
+            .ok_or_else(|| format!("invalid owners from {}", self.remote()))
+    }
+
+    /// Adds `users` to the owners of `name`, or removes them when `add` isn't
+    /// set.
+    pub fn change_owners(
+        &self,
+        name: &str,
+        users: &[String],
+        add: bool,
+        token: &str,
+    ) -> Result<(), String> {
+        let request = match add {
+            true => self.network.client().put(self.owners_url(name)),
+            false => self.network.client().delete(self.owners_url(name)),
+        }
+        .header("Content-Type", "application/json")
+        .body(serde_json::json!({ "users": users }).to_string());
+        let res = self.send_authorized(request, token)?;
+
+        match res.status() {
+            status if status.is_success() => Ok(()),
+            StatusCode::NOT_FOUND => Err(format!(
+                "`{}` not found in registry {}",
+                name,
+                self.remote()
+            )),
+            _ => Err(self.failure(res)),
+        }
+    }
+
+    /// The token of the registry: from `TAILOR_REGISTRY_TOKEN`, from the
+    /// credential helper or from `credentials.toml`, in this order. It's
+    /// looked up by the URL of the registry, even when a mirror replaces it,
+    /// and only once.
+    fn stored_token(&self) -> Result<Option<String>, String> {
+        if let Some(token) = self.token.get() {
+            return Ok(token.clone());
+        }
+
+        let token = match std::env::var("TAILOR_REGISTRY_TOKEN") {
+            Ok(token) => Some(token),
+            Err(_) => match &self.credential_helper {
+                Some(helper) => helper_token(helper, &self.url)?,
+                None => Credentials::load()?
+                    .registry_token(&self.url)
+                    .map(String::from),
+            },
+        };
+
+        Ok(self.token.get_or_init(|| token).clone())
+    }
+
+    /// A `GET` request to `url`, with the stored token, if any.
+    fn get(&self, url: String) -> Result<RequestBuilder, String> {
+        let request = self.network.client().get(url);
+        Ok(match self.stored_token()? {
+            Some(token) => request.bearer_auth(token),
+            None => request,
+        })
+    }
+
+    /// The token given by `--token`, or else the stored one.
+    pub fn token(&self, given: Option<&str>) -> Result<String, String> {
+        match given {
+            Some(token) => Ok(token.to_string()),
+            None => self.stored_token()?.ok_or_else(|| {
+                format!(
+                    "no token for registry {}, run `tailor login` or give one with `--token`",
+                    self.url
+                )
+            }),
+        }
+    }
+
+    /// Stores `token` as the token of the registry, through the credential
+    /// helper if there's one.
+    pub fn login(&self, token: &str) -> Result<(), String> {
+        match &self.credential_helper {
+            Some(helper) => helper_store(helper, &self.url, token),
+            None => {
+                let mut credentials = Credentials::load()?;
+                credentials.set_registry_token(&self.url, token);
+                credentials.save()
+            }
+        }
+    }
+}
@@ -1,17 +1,69 @@
-use std::{path::PathBuf, process};
+use std::{path::Path, process};
 
 use crate::{
-    build_pkg::BuildPkg,
-    command::Command,
-    fmt::success,
-    mode::Mode,
-    package::{Package, PackageType},
+    build_pkg::BuildPkg, command::Command, config::Config, fmt::success, package::Package,
+    target::Target,
 };
 
 #[derive(Default)]
 pub struct RunPkg {
-    mode: Mode,
-    path: PathBuf,
+    build: BuildPkg,
+    runner: Option<Vec<String>>,
+}
+
+/// Parses the `--runner <cmd>` flag, returning how many arguments were consumed.
+pub fn parse_runner_arg(runner: &mut Option<Vec<String>>, args: &[String]) -> Option<usize> {
+    let (command, consumed) = match args.first()?.strip_prefix("--runner=") {
+        Some(command) => (command, 1),
+        None if args[0] == "--runner" => (args.get(1)?.as_str(), 2),
+        None => return None,
+    };
+
+    *runner = Some(command.split_whitespace().map(String::from).collect());
+    Some(consumed)
+}
+
+/// Picks the command used to execute a binary: the `--runner` flag, then the
+/// runner of the target for cross builds, or the runner of the profile (from
+/// `Tailor.toml`, then from the config) for host builds. An empty command runs
+/// the binary directly.
+pub fn resolve_runner(
+    runner: Option<&[String]>,
+    build: &BuildPkg,
+    pkg: &Package,
+    target: Option<&Target>,
+) -> Result<Vec<String>, String> {
+    if let Some(runner) = runner {
+        return Ok(runner.to_vec());
+    }
+
+    if let Some(target) = target {
+        return target.runner().map(<[String]>::to_vec).ok_or(format!(
+            "It's not possible run a package built for target `{}` without a runner",
+            target.name()
+        ));
+    }
+
+    if let Some(runner) = pkg.profile(build.mode()).and_then(|p| p.runner()) {
+        return Ok(runner.to_vec());
+    }
+
+    Ok(Config::load()?
+        .profile(build.mode())
+        .and_then(|p| p.runner())
+        .map_or(vec![], <[String]>::to_vec))
+}
+
+/// Creates the process that executes `binary` through `runner`.
+pub fn runner_command(runner: &[String], binary: &Path) -> process::Command {
+    match runner.split_first() {
+        Some((program, args)) => {
+            let mut command = process::Command::new(program);
+            command.args(args).arg(binary);
+            command
+        }
+        None => process::Command::new(binary),
+    }
 }
 
 impl Command for RunPkg {
@@ -20,79 +72,34 @@ impl Command for RunPkg {
             return None;
         }
 
-        match args.len() {
-            1 => {
-                self.mode = Mode::Debug;
-                self.path = std::env::current_dir().ok()?;
-
-                Some(())
-            }
-            2 => {
-                match args[1].as_str().try_into() {
-                    Ok(mode) => {
-                        self.mode = mode;
-                        self.path = std::env::current_dir().ok()?;
-                    }
-                    Err(_) => {
-                        self.mode = Mode::Debug;
-                        self.path = PathBuf::from(&args[1]);
-                    }
-                }
-
-                Some(())
-            }
-            3 => {
-                let mode = match args[1].as_str().try_into() {
-                    Ok(mode) => mode,
-                    Err(_) => return None,
-                };
-
-                self.mode = mode;
-                self.path = PathBuf::from(&args[2]);
-
-                Some(())
-            }
-            _ => None,
+        let mut i = 1;
+        while i < args.len() {
+            i += parse_runner_arg(&mut self.runner, &args[i..])
+                .or_else(|| self.build.parse_arg(&args[i..]))?;
         }
+
+        self.build.finish_args()
     }
 
     fn execute(&self) -> Result<(), String> {
-        let mode_name = self.mode.to_string();
-        let abs_path = self
-            .path
-            .canonicalize()
-            .map_err(|_| "fail to get absolute path")?;
-
-        let pkg = Package::from_file(&abs_path.join("Tailor.toml"))
-            .map_err(|_| "fail to load package")?;
-
-        match pkg.pkg_type() {
-            PackageType::Library => {
-                return Err("It's not possible run a library package".to_string());
-            }
-            PackageType::Binary => {
-                let mut build = BuildPkg::default();
-                build
-                    .parse_args(&[
-                        "build".to_string(),
-                        format!("--{}", mode_name),
-                        abs_path.to_string_lossy().to_string(),
-                    ])
-                    .ok_or("Failed to parse build arguments".to_string())?;
-                build.execute()?;
-
-                println!(
-                    "{} `{}` in {} mode",
-                    success("Running"),
-                    pkg.name(),
-                    mode_name
-                );
-
-                process::Command::new(abs_path.join("build").join(mode_name).join(pkg.name()))
-                    .status()
-                    .map_err(|_| "fail to execute binary")?;
-            }
-        }
+        let (workspace, abs_path, pkg) = self.build.selected_binary("run")?;
+
+        let target = self.build.target(&pkg, &abs_path)?;
+        let runner = resolve_runner(self.runner.as_deref(), &self.build, &pkg, target.as_ref())?;
+
+        self.build.build_package(&abs_path, workspace.as_ref())?;
+
+        println!(
+            "{} `{}` in {} mode",
+            success("Running"),
+            pkg.name(),
+            self.build.mode()
+        );
+
+        let binary = self.build.binary_path(&pkg, &abs_path)?;
+        runner_command(&runner, &binary)
+            .status()
+            .map_err(|_| "fail to execute binary")?;
 
         Ok(())
     }
@@ -0,0 +1,175 @@
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use toml::Value;
+
+use crate::cmake;
+
+/// The `[sdk]` table of an `sdk` package: how to build the package with the
+/// CMake structure of an SDK, such as Zephyr, ESP-IDF or the Pico SDK.
+#[derive(Debug, Clone)]
+pub struct Sdk {
+    entry: String,
+    env: Vec<String>,
+    init: Vec<String>,
+    target: Option<String>,
+    libraries: Vec<String>,
+    post: Vec<String>,
+    parameters: BTreeMap<String, String>,
+}
+
+impl Sdk {
+    /// The CMake target holding the package sources: the one created by the
+    /// SDK, such as `app` in Zephyr, or `default`, the package name.
+    pub fn target<'a>(&'a self, default: &'a str) -> &'a str {
+        self.target.as_deref().unwrap_or(default)
+    }
+
+    /// The CMake file of the SDK included before `project()`, with the
+    /// `${VAR}` environment variables expanded. Relative paths are relative
+    /// to the package folder.
+    pub fn entry(&self, abs_path: &Path) -> Result<PathBuf, String> {
+        for var in &self.env {
+            if std::env::var_os(var).is_none() {
+                return Err(format!(
+                    "the SDK requires the environment variable `{}`",
+                    var
+                ));
+            }
+        }
+
+        let mut entry = String::new();
+        let mut rest = self.entry.as_str();
+        while let Some(start) = rest.find("${") {
+            let end = rest[start..]
+                .find('}')
+                .ok_or_else(|| format!("unterminated variable in SDK entry `{}`", self.entry))?;
+            let var = &rest[start + 2..start + end];
+            let value = std::env::var(var).map_err(|_| {
+                format!(
+                    "environment variable `{}` of SDK entry `{}` is not set",
+                    var, self.entry
+                )
+            })?;
+
+            entry += &rest[..start];
+            entry += &value;
+            rest = &rest[start + end + 1..];
+        }
+        entry += rest;
+
+        let entry = abs_path.join(entry);
+        if !entry.exists() {
+            return Err(format!("SDK entry `{}` not found", entry.display()));
+        }
+        Ok(entry)
+    }
+
+    /// CMake definitions of the `[sdk.parameters]` table, such as the board.
+    pub fn cmake_args(&self) -> Vec<String> {
+        self.parameters
+            .iter()
+            .map(|(key, value)| format!("-D{}={}", key, value))
+            .collect()
+    }
+
+    /// The CMakeLists template of the package, with the SDK boilerplate around
+    /// the placeholders filled by the build.
+    pub fn cmake_lists(&self, abs_path: &Path, pkg_name: &str) -> Result<String, String> {
+        let target = self.target(pkg_name);
+        let lines = |commands: &[String]| {
+            commands
+                .iter()
+                .map(|c| format!("{}\n", c))
+                .collect::<String>()
+        };
+
+        let mut content = format!(
+            "cmake_minimum_required(VERSION 3.13)\n\
+             include({})\n\
+             project($pkg_name C CXX ASM)\n\
+             {}",
+            cmake::quote(&self.entry(abs_path)?.to_string_lossy()),
+            lines(&self.init)
+        );
+        content += "set(CMAKE_C_STANDARD $c_standard)\n$subdirectories\n";
+        if self.target.is_none() {
+            content += "add_executable($pkg_name)\n";
+        }
+        content += &format!(
+            "target_sources({target} PRIVATE$package_sources\n)\n\
+             target_include_directories({target} PRIVATE $package_include)\n\
+             $dependency_library\n\
+             $definitions\n\
+             $link\n"
+        );
+        if !self.libraries.is_empty() {
+            content += &format!(
+                "target_link_libraries({} PRIVATE {})\n",
+                target,
+                self.libraries.join(" ")
+            );
+        }
+        content += &lines(&self.post);
+
+        Ok(content)
+    }
+
+    pub fn from_content(content: &Value) -> Result<Self, String> {
+        let table = content.as_table().ok_or("sdk must be a table")?;
+
+        let strings = |key: &str| -> Result<Vec<String>, String> {
+            match table.get(key) {
+                None => Ok(vec![]),
+                Some(Value::String(value)) => Ok(vec![value.clone()]),
+                Some(Value::Array(values)) => values
+                    .iter()
+                    .map(|v| v.as_str().map(String::from))
+                    .collect::<Option<Vec<String>>>()
+                    .ok_or_else(|| format!("sdk.{} must be a list of strings", key)),
+                Some(_) => Err(format!("sdk.{} must be a list of strings", key)),
+            }
+        };
+
+        Ok(Self {
+            entry: table
+                .get("entry")
+                .and_then(Value::as_str)
+                .map(String::from)
+                .ok_or("sdk.entry must be the path of the SDK CMake file")?,
+            env: strings("env")?,
+            init: strings("init")?,
+            target: table
+                .get("target")
+                .map(|v| {
+                    v.as_str()
+                        .map(String::from)
+                        .ok_or("sdk.target must be a string")
+                })
+                .transpose()?,
+            libraries: strings("libraries")?,
+            post: strings("post")?,
+            parameters: match table.get("parameters") {
+                Some(parameters) => parameters
+                    .as_table()
+                    .ok_or("sdk.parameters must be a table")?
+                    .iter()
+                    .map(|(key, value)| {
+                        let value = match value {
+                            Value::String(value) => value.clone(),
+                            Value::Boolean(value) => {
+                                (if *value { "ON" } else { "OFF" }).to_string()
+                            }
+                            Value::Integer(value) => value.to_string(),
+                            _ => return Err(format!("invalid value of sdk parameter `{}`", key)),
+                        };
+                        Ok((key.clone(), value))
+                    })
+                    .collect::<Result<BTreeMap<String, String>, String>>()?,
+                None => BTreeMap::new(),
+            },
+        })
+    }
+}
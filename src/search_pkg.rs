@@ -0,0 +1,85 @@
+use crate::{
+    command::{Command, parse_flag_value},
+    registry::Registry,
+};
+
+pub struct SearchPkg {
+    query: String,
+    limit: usize,
+    registry: Option<String>,
+}
+
+impl Default for SearchPkg {
+    fn default() -> Self {
+        Self {
+            query: String::new(),
+            limit: 10,
+            registry: None,
+        }
+    }
+}
+
+impl Command for SearchPkg {
+    fn parse_args(&mut self, args: &[String]) -> Option<()> {
+        if args.is_empty() || args[0] != "search" {
+            return None;
+        }
+
+        let mut words = vec![];
+        let mut i = 1;
+        while i < args.len() {
+            if let Some((registry, consumed)) = parse_flag_value(&args[i..], &["--registry"]) {
+                self.registry = Some(registry.to_string());
+                i += consumed;
+                continue;
+            }
+            if let Some((limit, consumed)) = parse_flag_value(&args[i..], &["--limit"]) {
+                self.limit = limit.parse().ok()?;
+                i += consumed;
+                continue;
+            }
+            if args[i].starts_with('-') {
+                return None;
+            }
+            words.push(args[i].as_str());
+            i += 1;
+        }
+        if words.is_empty() {
+            return None;
+        }
+        self.query = words.join(" ");
+
+        Some(())
+    }
+
+    fn execute(&self) -> Result<(), String> {
+        let registry = Registry::named(self.registry.as_deref())?;
+        let results = registry.search(&self.query, self.limit)?;
+
+        if results.is_empty() {
+            println!(
+                "no packages matching `{}` in {}",
+                self.query,
+                registry.url()
+            );
+            return Ok(());
+        }
+
+        let width = results
+            .iter()
+            .map(|r| r.name.len() + r.version.len())
+            .max()
+            .unwrap_or_default();
+        for result in results {
+            let package = format!("{} = \"{}\"", result.name, result.version);
+            match result.description {
+                Some(description) => {
+                    println!("{:<width$} # {}", package, description, width = width + 5)
+                }
+                None => println!("{}", package),
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,73 @@
+use std::path::{Path, PathBuf};
+
+use glob::{MatchOptions, Pattern};
+
+const MATCH_OPTIONS: MatchOptions = MatchOptions {
+    case_sensitive: true,
+    require_literal_separator: true,
+    require_literal_leading_dot: false,
+};
+
+fn is_pattern(source: &str) -> bool {
+    source.contains(['*', '?', '['])
+}
+
+/// The glob pattern of `pattern` inside `root`. The root is escaped, so a
+/// folder named with `[`, `*` or `?` is matched as it is.
+pub fn rooted_pattern(root: &Path, pattern: &str) -> String {
+    Path::new(&Pattern::escape(&root.to_string_lossy()))
+        .join(pattern)
+        .to_string_lossy()
+        .to_string()
+}
+
+/// Expands the `src` entries of a manifest into the concrete list of files.
+///
+/// Entries are relative to `root` and can be single files or glob patterns
+/// (`**` matches any number of folders). Entries starting with `!` exclude the
+/// files matched by the remaining pattern. The result is sorted, so the same
+/// tree always produces the same list.
+pub fn expand_sources(root: &Path, sources: &[String]) -> Result<Vec<PathBuf>, String> {
+    let (excludes, includes): (Vec<&String>, Vec<&String>) =
+        sources.iter().partition(|s| s.starts_with('!'));
+
+    let excludes = excludes
+        .iter()
+        .map(|s| {
+            Pattern::new(&rooted_pattern(root, &s[1..]))
+                .map_err(|e| format!("invalid source pattern `{}`: {}", s, e))
+        })
+        .collect::<Result<Vec<Pattern>, String>>()?;
+
+    let mut files = vec![];
+    for source in includes {
+        let full_source = root.join(source);
+
+        if !is_pattern(source) {
+            if !full_source.is_file() {
+                return Err(format!("source file `{}` does not exist", source));
+            }
+            files.push(full_source);
+            continue;
+        }
+
+        let entries = glob::glob_with(&rooted_pattern(root, source), MATCH_OPTIONS)
+            .map_err(|e| format!("invalid source pattern `{}`: {}", source, e))?;
+        for entry in entries {
+            let entry = entry.map_err(|e| format!("fail to read source `{}`: {}", source, e))?;
+            if entry.is_file() {
+                files.push(entry);
+            }
+        }
+    }
+
+    files.retain(|file| {
+        !excludes
+            .iter()
+            .any(|exclude| exclude.matches_path_with(file, MATCH_OPTIONS))
+    });
+    files.sort();
+    files.dedup();
+
+    Ok(files)
+}
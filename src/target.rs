@@ -0,0 +1,136 @@
+use std::path::{Path, PathBuf};
+
+use sha2::{Digest, Sha256};
+use toml::Value;
+
+/// A cross-compilation target, declared as `[target.<name>]` in `Tailor.toml`
+/// or in the global config.
+#[derive(Debug, Clone)]
+pub struct Target {
+    name: String,
+    toolchain_file: Option<PathBuf>,
+    cc: Option<String>,
+    sysroot: Option<PathBuf>,
+    runner: Option<Vec<String>>,
+    os: Option<String>,
+    arch: Option<String>,
+    family: Option<String>,
+}
+
+impl Target {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn runner(&self) -> Option<&[String]> {
+        self.runner.as_deref()
+    }
+
+    /// The `os`, `arch` and `family` keys describe the platform the target
+    /// builds for, matched by `cfg(...)` dependency conditions.
+    pub fn os(&self) -> Option<&str> {
+        self.os.as_deref()
+    }
+
+    pub fn arch(&self) -> Option<&str> {
+        self.arch.as_deref()
+    }
+
+    pub fn family(&self) -> Option<&str> {
+        self.family.as_deref()
+    }
+
+    pub fn hash(&self) -> Vec<u8> {
+        let mut hasher = Sha256::new();
+        hasher.update(self.name.as_bytes());
+        if let Some(toolchain_file) = &self.toolchain_file {
+            hasher.update(toolchain_file.to_string_lossy().as_bytes());
+        }
+        if let Some(cc) = &self.cc {
+            hasher.update(cc.as_bytes());
+        }
+        if let Some(sysroot) = &self.sysroot {
+            hasher.update(sysroot.to_string_lossy().as_bytes());
+        }
+        hasher.finalize().to_vec()
+    }
+
+    /// Returns a copy of the target with its relative paths joined to `base`.
+    pub fn relative_to(&self, base: &Path) -> Self {
+        Self {
+            toolchain_file: self.toolchain_file.as_ref().map(|p| base.join(p)),
+            sysroot: self.sysroot.as_ref().map(|p| base.join(p)),
+            ..self.clone()
+        }
+    }
+
+    /// CMake definitions passed when the build directory is generated.
+    pub fn cmake_args(&self) -> Vec<String> {
+        let mut args = vec![];
+        if let Some(toolchain_file) = &self.toolchain_file {
+            args.push(format!(
+                "-DCMAKE_TOOLCHAIN_FILE={}",
+                toolchain_file.display()
+            ));
+        }
+        if let Some(cc) = &self.cc {
+            args.push(format!("-DCMAKE_C_COMPILER={}", cc));
+        }
+        if let Some(sysroot) = &self.sysroot {
+            args.push(format!("-DCMAKE_SYSROOT={}", sysroot.display()));
+        }
+        args
+    }
+
+    pub fn from_content(name: &str, content: &Value) -> Result<Self, String> {
+        let table = content
+            .as_table()
+            .ok_or_else(|| format!("target '{}' must be a table", name))?;
+
+        let string = |key: &str| -> Result<Option<String>, String> {
+            table
+                .get(key)
+                .map(|v| {
+                    v.as_str()
+                        .map(String::from)
+                        .ok_or_else(|| format!("'{}' of target '{}' must be a string", key, name))
+                })
+                .transpose()
+        };
+
+        Ok(Self {
+            name: name.to_string(),
+            toolchain_file: string("toolchain-file")?.map(PathBuf::from),
+            cc: string("cc")?,
+            sysroot: string("sysroot")?.map(PathBuf::from),
+            runner: table
+                .get("runner")
+                .map(|v| {
+                    parse_command(v).ok_or_else(|| format!("invalid runner for target '{}'", name))
+                })
+                .transpose()?,
+            os: string("os")?,
+            arch: string("arch")?,
+            family: string("family")?,
+        })
+    }
+}
+
+/// Parses a command given either as a list of arguments or as a single
+/// whitespace separated string.
+pub fn parse_command(content: &Value) -> Option<Vec<String>> {
+    let command = match content {
+        Value::String(command) => command.split_whitespace().map(String::from).collect(),
+        Value::Array(args) => args
+            .iter()
+            .map(|arg| arg.as_str().map(String::from))
+            .collect::<Option<Vec<String>>>()?,
+        _ => return None,
+    };
+
+    if command.is_empty() {
+        None
+    } else {
+        Some(command)
+    }
+}
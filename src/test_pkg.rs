@@ -0,0 +1,82 @@
+use crate::{
+    build_pkg::BuildPkg,
+    command::Command,
+    fmt::{error, info, success},
+    package::Package,
+    run_pkg::{parse_runner_arg, resolve_runner, runner_command},
+};
+
+#[derive(Default)]
+pub struct TestPkg {
+    build: BuildPkg,
+    runner: Option<Vec<String>>,
+}
+
+impl Command for TestPkg {
+    fn parse_args(&mut self, args: &[String]) -> Option<()> {
+        if args.is_empty() || args[0] != "test" {
+            return None;
+        }
+
+        let mut i = 1;
+        while i < args.len() {
+            i += parse_runner_arg(&mut self.runner, &args[i..])
+                .or_else(|| self.build.parse_arg(&args[i..]))?;
+        }
+
+        self.build.finish_args()
+    }
+
+    fn execute(&self) -> Result<(), String> {
+        let (workspace, paths) = self.build.selected_packages()?;
+
+        let mut passed = 0;
+        let mut failed = vec![];
+        for path in paths {
+            let pkg = Package::from_file(&path.join("Tailor.toml"))?;
+            if pkg.is_virtual() {
+                continue;
+            }
+
+            let tests = self.build.build_tests(&path, workspace.as_ref())?;
+            if tests.is_empty() {
+                continue;
+            }
+
+            let target = self.build.target(&pkg, &path)?;
+            let runner =
+                resolve_runner(self.runner.as_deref(), &self.build, &pkg, target.as_ref())?;
+
+            for test in tests {
+                let name = format!(
+                    "{}/{}",
+                    pkg.name(),
+                    test.file_name().unwrap_or_default().to_string_lossy()
+                );
+                println!("{} `{}`", info("Running"), name);
+
+                let status = runner_command(&runner, &test)
+                    .status()
+                    .map_err(|e| format!("fail to execute test `{}`: {}", name, e))?;
+                if status.success() {
+                    passed += 1;
+                } else {
+                    println!("{}: test `{}` failed ({})", error(), name, status);
+                    failed.push(name);
+                }
+            }
+        }
+
+        if !failed.is_empty() {
+            return Err(format!(
+                "{} passed, {} failed: {}",
+                passed,
+                failed.len(),
+                failed.join(", ")
+            ));
+        }
+
+        println!("{} {} passed", success("Finished"), passed);
+        Ok(())
+    }
+}
@@ -0,0 +1,357 @@
+use std::{cmp::Ordering, fmt::Display};
+
+/// A `<major>.<minor>.<patch>` version, with an optional pre-release such as
+/// `1.0.0-rc.1`. The build metadata, after `+`, is ignored.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Version {
+    numbers: [u64; 3],
+    pre: Option<String>,
+}
+
+impl Version {
+    pub fn parse(version: &str) -> Option<Self> {
+        let version = version.split('+').next()?;
+        let (release, pre) = match version.split_once('-') {
+            Some((release, pre)) => (release, Some(pre.to_string())),
+            None => (version, None),
+        };
+        let numbers = parse_numbers(release)?;
+        if numbers.len() != 3
+            || pre
+                .as_ref()
+                .is_some_and(|pre| pre.split('.').any(str::is_empty))
+        {
+            return None;
+        }
+
+        Some(Self {
+            numbers: [numbers[0], numbers[1], numbers[2]],
+            pre,
+        })
+    }
+}
+
+/// The sort key of an identifier of a pre-release: numbers come first, by
+/// value, then the other identifiers, as text.
+fn identifier_key(identifier: &str) -> (bool, u64, &str) {
+    match identifier.parse() {
+        Ok(number) => (false, number, ""),
+        Err(_) => (true, 0, identifier),
+    }
+}
+
+impl Ord for Version {
+    /// A pre-release comes before its release. Pre-releases compare their
+    /// dot-separated identifiers in order, as in semver, and one comes before
+    /// the longer ones starting with it: `alpha < alpha.1 < beta < rc.2 < rc.10`.
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.numbers
+            .cmp(&other.numbers)
+            .then_with(|| match (&self.pre, &other.pre) {
+                (None, None) => Ordering::Equal,
+                (None, Some(_)) => Ordering::Greater,
+                (Some(_), None) => Ordering::Less,
+                (Some(a), Some(b)) => a
+                    .split('.')
+                    .map(identifier_key)
+                    .cmp(b.split('.').map(identifier_key)),
+            })
+    }
+}
+
+impl PartialOrd for Version {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// The numbers of a version, `1`, `1.2` or `1.2.3`.
+fn parse_numbers(version: &str) -> Option<Vec<u64>> {
+    let numbers = version
+        .split('.')
+        .map(|number| number.trim().parse().ok())
+        .collect::<Option<Vec<u64>>>()?;
+    (1..=3).contains(&numbers.len()).then_some(numbers)
+}
+
+/// The version right after all the versions starting with `numbers`, such as
+/// `1.3.0` for `1.2`.
+fn bump(numbers: &[u64]) -> Version {
+    let mut bumped = [0; 3];
+    bumped[..numbers.len()].copy_from_slice(numbers);
+    bumped[numbers.len() - 1] += 1;
+    Version {
+        numbers: bumped,
+        pre: None,
+    }
+}
+
+/// A limit of the versions accepted by a requirement, which accepts the
+/// version itself when `inclusive` is set.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Bound {
+    version: Version,
+    inclusive: bool,
+}
+
+impl Bound {
+    fn inclusive(version: Version) -> Self {
+        Self {
+            version,
+            inclusive: true,
+        }
+    }
+
+    fn exclusive(version: Version) -> Self {
+        Self {
+            version,
+            inclusive: false,
+        }
+    }
+}
+
+/// The requirement on the version of a registry dependency, as in Cargo. A
+/// bare version, such as `1.2`, accepts the versions compatible with it, from
+/// `1.2.0` up to `2.0.0`, excluded. `~1.2` only accepts the patches of `1.2`,
+/// `=1.2.3` only that version, and `>=`, `>`, `<=` and `<` compare with the
+/// version. Requirements separated by commas must all match, and `*` matches
+/// any version. A pre-release, such as `1.3.0-rc.1`, is only accepted when
+/// the requirement has a pre-release of the same `<major>.<minor>.<patch>`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VersionReq {
+    requirement: String,
+    lower: Vec<Bound>,
+    upper: Vec<Bound>,
+    pre_releases: Vec<[u64; 3]>,
+}
+
+impl VersionReq {
+    pub fn parse(requirement: &str) -> Result<Self, String> {
+        let invalid = || {
+            format!(
+                "invalid version requirement `{}`, expected a version such as `1.2`, optionally after `^`, `~`, `=`, `>=`, `>`, `<=` or `<`",
+                requirement
+            )
+        };
+
+        let mut req = Self {
+            requirement: requirement.to_string(),
+            lower: vec![],
+            upper: vec![],
+            pre_releases: vec![],
+        };
+        if requirement.trim() == "*" {
+            return Ok(req);
+        }
+
+        for comparator in requirement.split(',').map(str::trim) {
+            let operator_len = comparator
+                .find(|c: char| c.is_ascii_digit())
+                .ok_or_else(invalid)?;
+            let (operator, version) = comparator.split_at(operator_len);
+            let (release, pre) = match version.split('+').next().and_then(|v| v.split_once('-')) {
+                Some((release, _)) => (release, Some(Version::parse(version).ok_or_else(invalid)?)),
+                None => (version.split('+').next().unwrap_or_default(), None),
+            };
+            let numbers = parse_numbers(release).ok_or_else(invalid)?;
+            let full = pre.clone().unwrap_or_else(|| {
+                let mut full = [0; 3];
+                full[..numbers.len()].copy_from_slice(&numbers);
+                Version {
+                    numbers: full,
+                    pre: None,
+                }
+            });
+            // A full version is a single version, and a partial one all the
+            // versions starting with it.
+            let is_full = numbers.len() == 3;
+
+            let (lower, upper) = match operator.trim() {
+                "" | "^" => {
+                    let significant = numbers
+                        .iter()
+                        .position(|&n| n > 0)
+                        .unwrap_or(numbers.len() - 1);
+                    (
+                        Some(Bound::inclusive(full.clone())),
+                        Some(Bound::exclusive(bump(&numbers[..=significant]))),
+                    )
+                }
+                "~" => (
+                    Some(Bound::inclusive(full.clone())),
+                    Some(Bound::exclusive(bump(&numbers[..numbers.len().min(2)]))),
+                ),
+                "=" if is_full => (
+                    Some(Bound::inclusive(full.clone())),
+                    Some(Bound::inclusive(full.clone())),
+                ),
+                "=" => (
+                    Some(Bound::inclusive(full.clone())),
+                    Some(Bound::exclusive(bump(&numbers))),
+                ),
+                ">=" => (Some(Bound::inclusive(full.clone())), None),
+                ">" if is_full => (Some(Bound::exclusive(full.clone())), None),
+                ">" => (Some(Bound::inclusive(bump(&numbers))), None),
+                "<" => (None, Some(Bound::exclusive(full.clone()))),
+                "<=" if is_full => (None, Some(Bound::inclusive(full.clone()))),
+                "<=" => (None, Some(Bound::exclusive(bump(&numbers)))),
+                _ => return Err(invalid()),
+            };
+
+            req.lower.extend(lower);
+            req.upper.extend(upper);
+            req.pre_releases.extend(pre.map(|pre| pre.numbers));
+        }
+
+        Ok(req)
+    }
+
+    pub fn matches(&self, version: &Version) -> bool {
+        if version.pre.is_some() && !self.pre_releases.contains(&version.numbers) {
+            return false;
+        }
+        self.lower
+            .iter()
+            .all(|bound| match version.cmp(&bound.version) {
+                Ordering::Greater => true,
+                Ordering::Equal => bound.inclusive,
+                Ordering::Less => false,
+            })
+            && self
+                .upper
+                .iter()
+                .all(|bound| match version.cmp(&bound.version) {
+                    Ordering::Less => true,
+                    Ordering::Equal => bound.inclusive,
+                    Ordering::Greater => false,
+                })
+    }
+}
+
+impl Display for VersionReq {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.requirement)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Whether `requirement` accepts `version`.
+    fn matches(requirement: &str, version: &str) -> bool {
+        VersionReq::parse(requirement)
+            .unwrap()
+            .matches(&Version::parse(version).unwrap())
+    }
+
+    #[test]
+    fn caret_accepts_compatible_versions() {
+        for requirement in ["1.2", "^1.2", "1.2.0", "^1.2.0"] {
+            assert!(matches(requirement, "1.2.0"));
+            assert!(matches(requirement, "1.9.3"));
+            assert!(!matches(requirement, "1.1.9"));
+            assert!(!matches(requirement, "2.0.0"));
+        }
+        assert!(matches("1.2.3", "1.2.3"));
+        assert!(!matches("1.2.3", "1.2.2"));
+
+        assert!(matches("0.2", "0.2.9"));
+        assert!(!matches("0.2", "0.3.0"));
+        assert!(matches("0.0.3", "0.0.3"));
+        assert!(!matches("0.0.3", "0.0.4"));
+        assert!(matches("0", "0.9.0"));
+        assert!(!matches("0", "1.0.0"));
+    }
+
+    #[test]
+    fn tilde_accepts_patches() {
+        assert!(matches("~1.2", "1.2.7"));
+        assert!(matches("~1.2.3", "1.2.3"));
+        assert!(matches("~1.2.3", "1.2.9"));
+        assert!(!matches("~1.2.3", "1.2.2"));
+        assert!(!matches("~1.2.3", "1.3.0"));
+        assert!(matches("~1", "1.9.0"));
+        assert!(!matches("~1", "2.0.0"));
+    }
+
+    #[test]
+    fn exact_and_comparisons() {
+        assert!(matches("=1.2.3", "1.2.3"));
+        assert!(!matches("=1.2.3", "1.2.4"));
+        assert!(matches("=1.2", "1.2.9"));
+        assert!(!matches("=1.2", "1.3.0"));
+
+        assert!(matches(">1.2.3", "1.2.4"));
+        assert!(!matches(">1.2.3", "1.2.3"));
+        assert!(matches(">1.2", "1.3.0"));
+        assert!(!matches(">1.2", "1.2.9"));
+        assert!(matches("<=1.2.3", "1.2.3"));
+        assert!(!matches("<=1.2.3", "1.2.4"));
+        assert!(matches("<=1.2", "1.2.9"));
+        assert!(!matches("<1.2", "1.2.0"));
+
+        assert!(matches(">=1.2, <1.5", "1.4.9"));
+        assert!(!matches(">=1.2, <1.5", "1.5.0"));
+        assert!(matches("*", "0.0.1"));
+    }
+
+    #[test]
+    fn pre_releases_are_only_accepted_when_required() {
+        assert!(matches("=1.2.3-rc.1", "1.2.3-rc.1"));
+        assert!(!matches("=1.2.3-rc.1", "1.2.3"));
+        assert!(!matches("=1.2.3-rc.1", "1.2.3-rc.2"));
+
+        assert!(matches("1.2.3-rc.1", "1.2.3-rc.2"));
+        assert!(matches("1.2.3-rc.1", "1.2.3"));
+        assert!(matches("1.2.3-rc.1", "1.4.0"));
+        assert!(!matches("1.2.3-rc.1", "1.2.3-beta"));
+        assert!(!matches("1.2.3-rc.1", "1.4.0-rc.1"));
+
+        assert!(!matches("1.2", "1.3.0-rc.1"));
+        assert!(!matches("*", "1.0.0-alpha"));
+        assert!(!matches("<1.2.3", "1.2.3-rc.1"));
+    }
+
+    #[test]
+    fn pre_releases_compare_their_identifiers() {
+        let mut versions = [
+            "1.0.0",
+            "1.0.0-rc.10",
+            "1.0.0-beta",
+            "1.0.0-alpha.1",
+            "1.0.0-rc.2",
+            "1.0.0-alpha",
+            "1.0.0-alpha.beta",
+            "1.0.0-beta.11",
+            "1.0.0-beta.2",
+        ]
+        .map(|version| Version::parse(version).unwrap());
+        versions.sort();
+
+        assert_eq!(
+            versions.map(|version| match version.pre {
+                Some(pre) => pre,
+                None => "release".to_string(),
+            }),
+            [
+                "alpha",
+                "alpha.1",
+                "alpha.beta",
+                "beta",
+                "beta.2",
+                "beta.11",
+                "rc.2",
+                "rc.10",
+                "release",
+            ]
+        );
+    }
+
+    #[test]
+    fn invalid_requirements() {
+        for requirement in ["", "latest", "1.x", "!=1.0", "1.2.3.4", "1.0.0-"] {
+            assert!(VersionReq::parse(requirement).is_err(), "{}", requirement);
+        }
+    }
+}
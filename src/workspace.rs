@@ -0,0 +1,112 @@
+use std::path::{Path, PathBuf};
+
+use crate::{package::Package, sources::rooted_pattern};
+
+/// A set of packages sharing one lockfile and one output directory, declared
+/// by a root `Tailor.toml` with a `[workspace]` table.
+#[derive(Debug)]
+pub struct Workspace {
+    root: PathBuf,
+    members: Vec<(PathBuf, Package)>,
+}
+
+impl Workspace {
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// The packages of the workspace and their absolute folders, including
+    /// the root package when the root manifest isn't a virtual one.
+    pub fn members(&self) -> &[(PathBuf, Package)] {
+        &self.members
+    }
+
+    pub fn member(&self, name: &str) -> Option<&(PathBuf, Package)> {
+        self.members.iter().find(|(_, pkg)| pkg.name() == name)
+    }
+
+    pub fn lockfile_path(&self) -> PathBuf {
+        self.root.join("Tailor.lock")
+    }
+
+    /// Loads the workspace declared by the manifest at `root`, if any.
+    pub fn from_root(root: &Path) -> Result<Option<Self>, String> {
+        let root_pkg = Package::from_file(&root.join("Tailor.toml"))?;
+        let Some(patterns) = root_pkg.workspace_members() else {
+            return Ok(None);
+        };
+
+        let mut paths = vec![];
+        for pattern in patterns {
+            let entries = glob::glob(&rooted_pattern(root, pattern))
+                .map_err(|e| format!("invalid workspace member `{}`: {}", pattern, e))?;
+            for entry in entries {
+                let entry =
+                    entry.map_err(|e| format!("fail to read member `{}`: {}", pattern, e))?;
+                if entry.join("Tailor.toml").is_file() {
+                    paths.push(entry);
+                }
+            }
+        }
+        paths.sort();
+        paths.dedup();
+
+        let mut members = vec![];
+        if !root_pkg.is_virtual() {
+            members.push((root.to_path_buf(), root_pkg));
+        }
+        for path in paths {
+            if path == root {
+                continue;
+            }
+            let pkg = Package::from_file(&path.join("Tailor.toml"))?;
+            if pkg.workspace_members().is_some() {
+                return Err(format!(
+                    "workspace member `{}` can't be a workspace",
+                    path.display()
+                ));
+            }
+            members.push((path, pkg));
+        }
+
+        for (i, (path, pkg)) in members.iter().enumerate() {
+            if let Some((other, _)) = members[..i].iter().find(|(_, p)| p.name() == pkg.name()) {
+                return Err(format!(
+                    "two workspace members are named `{}`: `{}` and `{}`",
+                    pkg.name(),
+                    other.display(),
+                    path.display()
+                ));
+            }
+        }
+
+        Ok(Some(Self {
+            root: root.to_path_buf(),
+            members,
+        }))
+    }
+
+    /// Finds the workspace containing the package at `path` (an absolute
+    /// folder), looking for a workspace root in the folder and its parents.
+    pub fn find(path: &Path) -> Result<Option<Self>, String> {
+        for dir in path.ancestors() {
+            let is_root = dir
+                .join("Tailor.toml")
+                .is_file()
+                .then(|| Package::from_file(&dir.join("Tailor.toml")))
+                .and_then(Result::ok)
+                .is_some_and(|pkg| pkg.workspace_members().is_some());
+            if !is_root {
+                continue;
+            }
+
+            if let Some(workspace) = Self::from_root(dir)?
+                && (dir == path || workspace.members.iter().any(|(p, _)| p == path))
+            {
+                return Ok(Some(workspace));
+            }
+        }
+
+        Ok(None)
+    }
+}
@@ -0,0 +1,67 @@
+use crate::{
+    command::{Command, parse_flag_value},
+    fmt::success,
+    registry::Registry,
+};
+
+#[derive(Default)]
+pub struct YankPkg {
+    name: String,
+    version: String,
+    undo: bool,
+    token: Option<String>,
+    registry: Option<String>,
+}
+
+impl Command for YankPkg {
+    fn parse_args(&mut self, args: &[String]) -> Option<()> {
+        if args.is_empty() || args[0] != "yank" {
+            return None;
+        }
+
+        let mut i = 1;
+        while i < args.len() {
+            if args[i] == "--undo" {
+                self.undo = true;
+                i += 1;
+                continue;
+            }
+            if let Some((registry, consumed)) = parse_flag_value(&args[i..], &["--registry"]) {
+                self.registry = Some(registry.to_string());
+                i += consumed;
+                continue;
+            }
+            if let Some((token, consumed)) = parse_flag_value(&args[i..], &["--token"]) {
+                self.token = Some(token.to_string());
+                i += consumed;
+                continue;
+            }
+            if args[i].starts_with('-') || !self.name.is_empty() {
+                return None;
+            }
+            let (name, version) = args[i].split_once('@')?;
+            self.name = name.to_string();
+            self.version = version.to_string();
+            i += 1;
+        }
+
+        (!self.name.is_empty()).then_some(())
+    }
+
+    fn execute(&self) -> Result<(), String> {
+        let registry = Registry::named(self.registry.as_deref())?;
+        let token = registry.token(self.token.as_deref())?;
+
+        registry.yank(&self.name, &self.version, self.undo, &token)?;
+
+        println!(
+            "{} `{}` @ {} in {}",
+            success(if self.undo { "Unyanked" } else { "Yanked" }),
+            self.name,
+            self.version,
+            registry.url()
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,103 @@
+#![cfg(unix)]
+
+mod common;
+
+use common::Sandbox;
+
+/// Writes the package `app`, depending on `cjson` with `dependency`.
+fn app(sandbox: &Sandbox, dependency: &str) {
+    sandbox.write(
+        "app/Tailor.toml",
+        &format!(
+            "name = \"app\"\nversion = \"0.1.0\"\n\n[dependencies]\ncjson = {}\n",
+            dependency
+        ),
+    );
+    sandbox.write("app/src/main.c", "int main(void) { return 0; }\n");
+}
+
+/// Writes the plain CMake project `cjson`, without a `Tailor.toml`.
+fn cjson(sandbox: &Sandbox) {
+    sandbox.write(
+        "cjson/CMakeLists.txt",
+        "add_library(cjson cJSON.c)\nadd_library(cjson_utils cJSON_Utils.c)\n",
+    );
+    sandbox.write("cjson/cJSON.c", "");
+    sandbox.write("cjson/cJSON_Utils.c", "");
+    sandbox.write("cjson/cJSON.h", "");
+}
+
+const CMAKE_LISTS: &str = "app/build/debug/CMakeLists.txt";
+
+#[test]
+fn cmake_project_is_added_and_linked_through_its_targets() {
+    let sandbox = Sandbox::new();
+    cjson(&sandbox);
+    app(
+        &sandbox,
+        "{ path = \"../cjson\", build = \"cmake\", targets = [\"cjson\", \"cjson_utils\"] }",
+    );
+
+    let output = sandbox.tailor("app", &["build"]);
+    assert_eq!(output.error(), None);
+    let cmake_lists = sandbox.read(CMAKE_LISTS);
+    assert!(
+        cmake_lists.contains(&format!(
+            "add_subdirectory(\"{}\" \"deps/cjson\" EXCLUDE_FROM_ALL)\n",
+            sandbox.path("app/../cjson").display()
+        )),
+        "{}",
+        cmake_lists
+    );
+    assert!(cmake_lists.contains("target_link_libraries(app PRIVATE cjson cjson_utils)\n"));
+    // The sources of the project are only compiled by its own CMakeLists.txt.
+    assert!(!cmake_lists.contains("cJSON.c"));
+
+    // The target is named after the dependency by default.
+    app(&sandbox, "{ path = \"../cjson\", build = \"cmake\" }");
+    let output = sandbox.tailor("app", &["build"]);
+    assert_eq!(output.error(), None);
+    assert!(
+        sandbox
+            .read(CMAKE_LISTS)
+            .contains("target_link_libraries(app PRIVATE cjson)\n")
+    );
+
+    app(&sandbox, "{ path = \"../cjson\", build = \"make\" }");
+    let output = sandbox.tailor("app", &["build"]);
+    assert_eq!(
+        output.error(),
+        Some(
+            "fail to parse some dependencies of package 'app':\n\t- build of 'cjson' must be \"tailor\" or \"cmake\""
+        )
+    );
+}
+
+#[test]
+fn overlay_manifest_compiles_a_project_as_a_package() {
+    let sandbox = Sandbox::new();
+    cjson(&sandbox);
+    app(
+        &sandbox,
+        "{ path = \"../cjson\", manifest = \"overlays/cjson.toml\" }",
+    );
+    sandbox.write(
+        "app/overlays/cjson.toml",
+        "name = \"cjson\"\nversion = \"1.7.18\"\ntype = \"lib\"\nsrc = [\"cJSON.c\"]\ninclude = [\".\"]\n",
+    );
+
+    let output = sandbox.tailor("app", &["build"]);
+    assert_eq!(output.error(), None);
+    let cmake_lists = sandbox.read(CMAKE_LISTS);
+    // The sources and the includes are relative to the project, not to the
+    // overlay manifest.
+    let cjson = sandbox.path("app/../cjson");
+    assert!(
+        cmake_lists.contains(&format!("\"{}/cJSON.c\"", cjson.display())),
+        "{}",
+        cmake_lists
+    );
+    assert!(cmake_lists.contains(&format!("\"{}/.\"", cjson.display())));
+    assert!(!cmake_lists.contains("cJSON_Utils.c"));
+    assert!(!cmake_lists.contains("add_subdirectory"));
+}
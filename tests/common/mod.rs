@@ -0,0 +1,172 @@
+//! A sandbox running the `tailor` binary with its own config folder and a
+//! `cmake` that only records how it's called, so the tests don't need a C
+//! toolchain.
+
+#![allow(dead_code)]
+
+pub mod registry;
+
+use std::{
+    os::unix::fs::PermissionsExt,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use tempfile::TempDir;
+
+pub struct Sandbox {
+    dir: TempDir,
+    env: Vec<(String, String)>,
+}
+
+/// What a `tailor` command printed.
+pub struct Output {
+    pub stdout: String,
+    pub stderr: String,
+    pub success: bool,
+}
+
+impl Output {
+    /// The error printed by `tailor`, if any. `tailor` exits with a failure
+    /// exactly when it prints one.
+    pub fn error(&self) -> Option<&str> {
+        let error = self
+            .stderr
+            .split_once("error\x1B[0m: ")
+            .map(|(_, error)| error.trim());
+        assert_eq!(error.is_none(), self.success, "{}", self.stderr);
+        error
+    }
+}
+
+impl Sandbox {
+    pub fn new() -> Self {
+        let dir = tempfile::tempdir().expect("fail to create the sandbox");
+        let bin = dir.path().join("bin");
+        std::fs::create_dir_all(&bin).unwrap();
+        let cmake = bin.join("cmake");
+        std::fs::write(
+            &cmake,
+            format!(
+                "#!/bin/sh\necho \"$*\" >> \"{}\"\n[ -f \"{}\" ] && exit 1\nexit 0\n",
+                dir.path().join("cmake.log").display(),
+                dir.path().join("cmake.fail").display()
+            ),
+        )
+        .unwrap();
+        std::fs::set_permissions(&cmake, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        Self { dir, env: vec![] }
+    }
+
+    pub fn path(&self, path: &str) -> PathBuf {
+        self.dir.path().join(path)
+    }
+
+    /// Writes `content` to `path`, creating its folders.
+    pub fn write(&self, path: &str, content: &str) {
+        let path = self.path(path);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, content).unwrap();
+    }
+
+    pub fn read(&self, path: &str) -> String {
+        std::fs::read_to_string(self.path(path)).unwrap_or_default()
+    }
+
+    /// Sets an environment variable of the next `tailor` commands.
+    pub fn env(&mut self, key: &str, value: &str) {
+        self.env.retain(|(k, _)| k != key);
+        self.env.push((key.to_string(), value.to_string()));
+    }
+
+    /// Unsets an environment variable set by `env`.
+    pub fn env_remove(&mut self, key: &str) {
+        self.env.retain(|(k, _)| k != key);
+    }
+
+    /// Runs `tailor` with `args` in the folder `cwd` of the sandbox.
+    pub fn tailor(&self, cwd: &str, args: &[&str]) -> Output {
+        let path = format!(
+            "{}:{}",
+            self.path("bin").display(),
+            std::env::var("PATH").unwrap_or_default()
+        );
+        let output = Command::new(env!("CARGO_BIN_EXE_tailor"))
+            .args(args)
+            .current_dir(self.path(cwd))
+            .env("PATH", path)
+            .env("XDG_CONFIG_HOME", self.path("config"))
+            .env("HOME", self.dir.path())
+            .env_remove("TAILOR_REGISTRY")
+            .env_remove("TAILOR_REGISTRY_TOKEN")
+            .env_remove("TAILOR_TARGET_DIR")
+            .envs(self.env.iter().map(|(k, v)| (k, v)))
+            .output()
+            .expect("fail to run tailor");
+
+        Output {
+            stdout: String::from_utf8_lossy(&output.stdout).to_string(),
+            stderr: String::from_utf8_lossy(&output.stderr).to_string(),
+            success: output.status.success(),
+        }
+    }
+
+    /// The calls of `cmake` since the last time, one per line.
+    pub fn cmake_calls(&self) -> Vec<String> {
+        let log = self.path("cmake.log");
+        let calls = std::fs::read_to_string(&log).unwrap_or_default();
+        let _ = std::fs::remove_file(&log);
+        calls.lines().map(String::from).collect()
+    }
+
+    /// Makes the next calls of `cmake` fail, as with a package that doesn't
+    /// compile.
+    pub fn fail_cmake(&self) {
+        self.write("cmake.fail", "");
+    }
+
+    /// The calls of `cmake` generating a build folder.
+    pub fn cmake_generations(&self) -> Vec<String> {
+        self.cmake_calls()
+            .into_iter()
+            .filter(|call| call.starts_with("-S "))
+            .collect()
+    }
+
+    /// Writes a library `name` at the folder `name`, ready to be published.
+    pub fn library(&self, name: &str, version: &str) {
+        self.write(
+            &format!("{}/Tailor.toml", name),
+            &format!(
+                "name = \"{}\"\nversion = \"{}\"\ntype = \"lib\"\ndescription = \"A library\"\nlicense = \"MIT\"\nrepository = \"https://example.com/{}\"\n",
+                name, version, name
+            ),
+        );
+        self.write(
+            &format!("{}/src/{}.c", name, name),
+            "int answer(void) { return 42; }\n",
+        );
+        self.write(
+            &format!("{}/include/{}/{}.h", name, name, name),
+            "int answer(void);\n",
+        );
+    }
+
+    /// The files of the `.tar.gz` at `path`.
+    pub fn tarball_files(&self, path: &str) -> Vec<String> {
+        let tarball = std::fs::File::open(self.path(path)).expect("no tarball");
+        let mut archive = tar::Archive::new(flate2::read::GzDecoder::new(tarball));
+        let mut files = archive
+            .entries()
+            .unwrap()
+            .map(|entry| entry.unwrap().path().unwrap().display().to_string())
+            .collect::<Vec<String>>();
+        files.sort();
+        files
+    }
+
+    pub fn root(&self) -> &Path {
+        self.dir.path()
+    }
+}
@@ -0,0 +1,453 @@
+//! A registry in a thread of the tests, serving the API described in the
+//! README from memory. The token `secret` is the user `ada`, and `other` is
+//! the user `bob`.
+
+use std::{
+    collections::BTreeMap,
+    io::{BufRead, BufReader, Read, Write},
+    net::{TcpListener, TcpStream},
+    sync::{Arc, Mutex},
+};
+
+use flate2::{Compression, read::GzDecoder, write::GzEncoder};
+use serde_json::{Value, json};
+use sha2::{Digest, Sha256};
+
+const USERS: &[(&str, &str)] = &[("secret", "ada"), ("other", "bob")];
+
+#[derive(Default)]
+struct State {
+    /// The index entries of each package, in publishing order.
+    index: BTreeMap<String, Vec<Value>>,
+    tarballs: BTreeMap<(String, String), Vec<u8>>,
+    owners: BTreeMap<String, Vec<String>>,
+    /// The manifest of the last version published of each package.
+    manifests: BTreeMap<String, toml::Table>,
+    /// The method and the path of each request received.
+    requests: Vec<String>,
+    /// The token of each request received.
+    tokens: Vec<Option<String>>,
+    /// The number of next requests answered `503 Service Unavailable`.
+    failures: usize,
+    /// The method of the next request handled, but answered `503 Service
+    /// Unavailable` as if the answer was lost.
+    lost_answer: Option<String>,
+}
+
+pub struct Registry {
+    url: String,
+    state: Arc<Mutex<State>>,
+}
+
+struct Request {
+    method: String,
+    path: String,
+    token: Option<String>,
+    checksum: Option<String>,
+    body: Vec<u8>,
+}
+
+impl Registry {
+    pub fn start() -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("fail to start the registry");
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let state = Arc::new(Mutex::new(State::default()));
+
+        let shared = state.clone();
+        std::thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                serve(stream, &shared);
+            }
+        });
+
+        Self { url, state }
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// The index entries of `name`.
+    pub fn index(&self, name: &str) -> Vec<Value> {
+        let state = self.state.lock().unwrap();
+        state.index.get(name).cloned().unwrap_or_default()
+    }
+
+    pub fn owners(&self, name: &str) -> Vec<String> {
+        let state = self.state.lock().unwrap();
+        state.owners.get(name).cloned().unwrap_or_default()
+    }
+
+    /// The requests received since the last time, as `<method> <path>`.
+    pub fn requests(&self) -> Vec<String> {
+        std::mem::take(&mut self.state.lock().unwrap().requests)
+    }
+
+    /// The tokens of the requests received since the last time.
+    pub fn tokens(&self) -> Vec<Option<String>> {
+        std::mem::take(&mut self.state.lock().unwrap().tokens)
+    }
+
+    /// Answers the next `count` requests with a server error.
+    pub fn fail_next(&self, count: usize) {
+        self.state.lock().unwrap().failures = count;
+    }
+
+    /// Handles the next request with `method`, but answers it with a server
+    /// error, as if the answer was lost.
+    pub fn lose_next_answer(&self, method: &str) {
+        self.state.lock().unwrap().lost_answer = Some(method.to_string());
+    }
+
+    /// Publishes `version` of `name` as `ada`, with `manifest` as the only
+    /// file, without going through `tailor publish`.
+    pub fn add(&self, name: &str, version: &str, manifest: &str) {
+        let mut archive = tar::Builder::new(GzEncoder::new(vec![], Compression::default()));
+        let mut header = tar::Header::new_gnu();
+        header.set_size(manifest.len() as u64);
+        header.set_mode(0o644);
+        archive
+            .append_data(
+                &mut header,
+                format!("{}-{}/Tailor.toml", name, version),
+                manifest.as_bytes(),
+            )
+            .unwrap();
+        let tarball = archive.into_inner().unwrap().finish().unwrap();
+
+        let request = Request {
+            method: "PUT".to_string(),
+            path: format!("/api/v1/packages/{}/{}", name, version),
+            token: Some("secret".to_string()),
+            checksum: Some(sha256(&tarball)),
+            body: tarball,
+        };
+        let mut state = self.state.lock().unwrap();
+        assert_eq!(handle(&mut state, &request).0, 200);
+    }
+}
+
+fn serve(stream: TcpStream, state: &Mutex<State>) {
+    let mut reader = BufReader::new(&stream);
+    let Some(request) = read_request(&mut reader) else {
+        return;
+    };
+
+    let (status, body) = {
+        let mut state = state.lock().unwrap();
+        state
+            .requests
+            .push(format!("{} {}", request.method, request.path));
+        state.tokens.push(request.token.clone());
+        if state.failures > 0 {
+            state.failures -= 1;
+            (503, b"try again later".to_vec())
+        } else if state.lost_answer.as_ref() == Some(&request.method) {
+            state.lost_answer = None;
+            handle(&mut state, &request);
+            (503, b"try again later".to_vec())
+        } else {
+            handle(&mut state, &request)
+        }
+    };
+
+    let mut stream = &stream;
+    let _ = write!(
+        stream,
+        "HTTP/1.1 {} Registry\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        status,
+        body.len()
+    );
+    let _ = stream.write_all(&body);
+}
+
+fn read_request(reader: &mut impl BufRead) -> Option<Request> {
+    let mut line = String::new();
+    reader.read_line(&mut line).ok()?;
+    let mut parts = line.split_whitespace();
+    let method = parts.next()?.to_string();
+    let path = parts.next()?.to_string();
+
+    let (mut length, mut token, mut checksum) = (0, None, None);
+    loop {
+        let mut header = String::new();
+        reader.read_line(&mut header).ok()?;
+        let Some((key, value)) = header.trim_end().split_once(':') else {
+            break;
+        };
+        let value = value.trim();
+        match key.to_ascii_lowercase().as_str() {
+            "content-length" => length = value.parse().ok()?,
+            "authorization" => token = value.strip_prefix("Bearer ").map(String::from),
+            "x-checksum-sha256" => checksum = Some(value.to_string()),
+            _ => {}
+        }
+    }
+
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body).ok()?;
+    Some(Request {
+        method,
+        path,
+        token,
+        checksum,
+        body,
+    })
+}
+
+fn handle(state: &mut State, request: &Request) -> (u16, Vec<u8>) {
+    let user = request
+        .token
+        .as_deref()
+        .and_then(|token| USERS.iter().find(|(t, _)| *t == token))
+        .map(|(_, user)| user.to_string());
+    let (path, query) = request.path.split_once('?').unwrap_or((&request.path, ""));
+    let parts = path.trim_matches('/').split('/').collect::<Vec<&str>>();
+
+    match (request.method.as_str(), parts.as_slice()) {
+        ("GET", ["index", name]) => match state.index.get(*name) {
+            Some(entries) => (
+                200,
+                entries
+                    .iter()
+                    .map(|entry| format!("{}\n", entry))
+                    .collect::<String>()
+                    .into_bytes(),
+            ),
+            None => (404, vec![]),
+        },
+        ("GET", ["api", "v1", "search"]) => search(state, query),
+        ("GET", ["api", "v1", "packages", name, version, "download"]) => {
+            match state.tarballs.get(&(name.to_string(), version.to_string())) {
+                Some(tarball) => (200, tarball.clone()),
+                None => (404, vec![]),
+            }
+        }
+        (method, ["api", "v1", "packages", name, version, "yank"]) => {
+            if let Err(answer) = check_owner(state, user.as_deref(), name) {
+                return answer;
+            }
+            let Some(entry) = state
+                .index
+                .get_mut(*name)
+                .and_then(|entries| entries.iter_mut().find(|e| e["version"] == *version))
+            else {
+                return (404, vec![]);
+            };
+            entry["yanked"] = json!(method == "DELETE");
+            (200, vec![])
+        }
+        ("GET", ["api", "v1", "packages", name, "owners"]) => {
+            if user.is_none() {
+                return (401, b"missing token".to_vec());
+            }
+            match state.owners.get(*name) {
+                Some(owners) => (200, json!({ "users": owners }).to_string().into_bytes()),
+                None => (404, vec![]),
+            }
+        }
+        (method, ["api", "v1", "packages", name, "owners"]) => {
+            if let Err(answer) = check_owner(state, user.as_deref(), name) {
+                return answer;
+            }
+            let users = serde_json::from_slice::<Value>(&request.body)
+                .ok()
+                .and_then(|json| json["users"].as_array().cloned())
+                .unwrap_or_default();
+            let owners = state.owners.entry(name.to_string()).or_default();
+            for user in users.iter().filter_map(Value::as_str) {
+                owners.retain(|owner| owner != user);
+                if method == "PUT" {
+                    owners.push(user.to_string());
+                }
+            }
+            (200, vec![])
+        }
+        ("PUT", ["api", "v1", "packages", name, version]) => {
+            publish(state, user, name, version, request)
+        }
+        _ => (404, vec![]),
+    }
+}
+
+/// Fails with the answer of the registry when `user` isn't an owner of `name`.
+fn check_owner(state: &State, user: Option<&str>, name: &str) -> Result<(), (u16, Vec<u8>)> {
+    let Some(user) = user else {
+        return Err((401, b"missing token".to_vec()));
+    };
+    match state.owners.get(name) {
+        None => Err((404, vec![])),
+        Some(owners) if !owners.iter().any(|owner| owner == user) => Err((
+            403,
+            format!("{} is not an owner of {}", user, name).into_bytes(),
+        )),
+        Some(_) => Ok(()),
+    }
+}
+
+fn publish(
+    state: &mut State,
+    user: Option<String>,
+    name: &str,
+    version: &str,
+    request: &Request,
+) -> (u16, Vec<u8>) {
+    let Some(user) = user else {
+        return (401, b"missing token".to_vec());
+    };
+    if state
+        .owners
+        .get(name)
+        .is_some_and(|owners| !owners.contains(&user))
+    {
+        return (
+            403,
+            format!("{} is not an owner of {}", user, name).into_bytes(),
+        );
+    }
+
+    let checksum = sha256(&request.body);
+    if request.checksum.as_ref() != Some(&checksum) {
+        return (400, b"wrong checksum".to_vec());
+    }
+    if state
+        .index
+        .get(name)
+        .is_some_and(|entries| entries.iter().any(|e| e["version"] == version))
+    {
+        return (409, vec![]);
+    }
+    let Some(manifest) = manifest(&request.body, &format!("{}-{}", name, version)) else {
+        return (400, b"no Tailor.toml in the tarball".to_vec());
+    };
+
+    let mut dependencies = vec![];
+    for (table, kind) in [
+        ("dependencies", "normal"),
+        ("dev-dependencies", "dev"),
+        ("build-dependencies", "build"),
+    ] {
+        for (dep, value) in manifest
+            .get(table)
+            .and_then(|t| t.as_table())
+            .into_iter()
+            .flatten()
+        {
+            let version = match value {
+                toml::Value::String(version) => Some(version.as_str()),
+                value => value.get("version").and_then(|v| v.as_str()),
+            };
+            dependencies.push(json!({ "name": dep, "version": version, "kind": kind }));
+        }
+    }
+
+    state
+        .index
+        .entry(name.to_string())
+        .or_default()
+        .push(json!({
+            "name": name,
+            "version": version,
+            "checksum": checksum,
+            "dependencies": dependencies,
+            "yanked": false,
+        }));
+    state.tarballs.insert(
+        (name.to_string(), version.to_string()),
+        request.body.clone(),
+    );
+    state
+        .owners
+        .entry(name.to_string())
+        .or_insert_with(|| vec![user]);
+    state.manifests.insert(name.to_string(), manifest);
+    (200, vec![])
+}
+
+/// The packages whose name, description or keywords contain the `q` of
+/// `query`, up to its `limit`.
+fn search(state: &State, query: &str) -> (u16, Vec<u8>) {
+    let parameter = |key: &str| {
+        query
+            .split('&')
+            .filter_map(|pair| pair.split_once('='))
+            .find(|(k, _)| *k == key)
+            .map(|(_, value)| decode(value))
+    };
+    let Some(words) = parameter("q") else {
+        return (400, b"missing q".to_vec());
+    };
+    let limit = parameter("limit")
+        .and_then(|limit| limit.parse().ok())
+        .unwrap_or(10);
+
+    let words = words.to_lowercase();
+    let packages = state
+        .manifests
+        .iter()
+        .filter(|(name, manifest)| {
+            let keywords = manifest
+                .get("keywords")
+                .and_then(|k| k.as_array())
+                .into_iter()
+                .flatten()
+                .filter_map(|k| k.as_str());
+            std::iter::once(name.as_str())
+                .chain(manifest.get("description").and_then(|d| d.as_str()))
+                .chain(keywords)
+                .any(|text| text.to_lowercase().contains(&words))
+        })
+        .take(limit)
+        .map(|(name, manifest)| {
+            json!({
+                "name": name,
+                "version": manifest["version"].as_str(),
+                "description": manifest.get("description").and_then(|d| d.as_str()),
+            })
+        })
+        .collect::<Vec<Value>>();
+    (
+        200,
+        json!({ "packages": packages }).to_string().into_bytes(),
+    )
+}
+
+/// Decodes a value of a query string, where spaces are `+` and other
+/// characters may be `%XX`.
+fn decode(value: &str) -> String {
+    let mut bytes = vec![];
+    let mut chars = value.bytes();
+    while let Some(byte) = chars.next() {
+        match byte {
+            b'+' => bytes.push(b' '),
+            b'%' => {
+                let hex = [chars.next().unwrap_or(b'0'), chars.next().unwrap_or(b'0')];
+                let hex = std::str::from_utf8(&hex).unwrap_or("00");
+                bytes.push(u8::from_str_radix(hex, 16).unwrap_or(b'?'));
+            }
+            byte => bytes.push(byte),
+        }
+    }
+    String::from_utf8_lossy(&bytes).to_string()
+}
+
+fn sha256(content: &[u8]) -> String {
+    Sha256::digest(content)
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// The `Tailor.toml` in the `root` folder of `tarball`.
+fn manifest(tarball: &[u8], root: &str) -> Option<toml::Table> {
+    let mut archive = tar::Archive::new(GzDecoder::new(tarball));
+    for entry in archive.entries().ok()? {
+        let mut entry = entry.ok()?;
+        if entry.path().ok()? == std::path::Path::new(root).join("Tailor.toml") {
+            let mut content = String::new();
+            entry.read_to_string(&mut content).ok()?;
+            return content.parse().ok();
+        }
+    }
+    None
+}
@@ -0,0 +1,74 @@
+#![cfg(unix)]
+
+mod common;
+
+use common::Sandbox;
+
+/// Writes the library `mylib`, with the `[config]` options `log_level` and
+/// `transport`, and the package `app` depending on it with `config`.
+fn packages(sandbox: &Sandbox, config: &str) {
+    sandbox.write(
+        "mylib/Tailor.toml",
+        "name = \"mylib\"\nversion = \"0.1.0\"\ntype = \"lib\"\n\n\
+         [config.log_level]\ntype = \"int\"\ndefault = 2\nrange = [0, 4]\n\n\
+         [config.transport]\ntype = \"enum\"\nvalues = [\"tcp\", \"udp\"]\ndefault = \"udp\"\n",
+    );
+    sandbox.write("mylib/src/mylib.c", "");
+    sandbox.write(
+        "app/Tailor.toml",
+        &format!(
+            "name = \"app\"\nversion = \"0.1.0\"\n\n[dependencies]\nmylib = {{ path = \"../mylib\", config = {} }}\n",
+            config
+        ),
+    );
+    sandbox.write("app/src/main.c", "int main(void) { return 0; }\n");
+}
+
+#[test]
+fn dependent_package_sets_the_options_of_its_dependency() {
+    let sandbox = Sandbox::new();
+    packages(&sandbox, "{ transport = \"tcp\" }");
+
+    let output = sandbox.tailor("app", &["build"]);
+    assert_eq!(output.error(), None);
+    let header = sandbox.read("app/build/debug/generated/mylib_config.h");
+    assert!(
+        header.contains("#define CONFIG_MYLIB_LOG_LEVEL 2\n#define CONFIG_MYLIB_TRANSPORT_TCP 1\n"),
+        "{}",
+        header
+    );
+    assert!(
+        sandbox.read("app/build/debug/CMakeLists.txt").contains(
+            &sandbox
+                .path("app/build/debug/generated")
+                .display()
+                .to_string()
+        )
+    );
+
+    packages(&sandbox, "{ log_level = 7 }");
+    let output = sandbox.tailor("app", &["build"]);
+    assert_eq!(
+        output.error(),
+        Some("config option `log_level` of `mylib` must be between 0 and 4")
+    );
+}
+
+#[test]
+fn config_options_are_exported_as_kconfig() {
+    let sandbox = Sandbox::new();
+    packages(&sandbox, "{}");
+
+    let output = sandbox.tailor("mylib", &["config", "--export-kconfig", "Kconfig.mylib"]);
+    assert_eq!(output.error(), None);
+    let kconfig = sandbox.read("mylib/Kconfig.mylib");
+    assert!(kconfig.starts_with("# Generated by Tailor from the [config] of `mylib`\n"));
+    assert!(kconfig.contains("config MYLIB_LOG_LEVEL\n"));
+    assert!(kconfig.contains("choice MYLIB_TRANSPORT\n"));
+
+    let output = sandbox.tailor("app", &["config", "--export-kconfig", "Kconfig.app"]);
+    assert_eq!(
+        output.error(),
+        Some("no [config] table found in the selected packages")
+    );
+}
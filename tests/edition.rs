@@ -0,0 +1,88 @@
+#![cfg(unix)]
+
+mod common;
+
+use common::Sandbox;
+
+/// Writes the package `app` with the manifest keys `keys`, and a source in a
+/// subfolder of `src/`.
+fn app(sandbox: &Sandbox, keys: &str) {
+    sandbox.write(
+        "app/Tailor.toml",
+        &format!("name = \"app\"\nversion = \"0.1.0\"\n{}", keys),
+    );
+    sandbox.write("app/src/main.c", "int main(void) { return 0; }\n");
+    sandbox.write("app/src/net/socket.c", "");
+}
+
+const CMAKE_LISTS: &str = "app/build/debug/CMakeLists.txt";
+
+#[test]
+fn defaults_follow_the_edition_of_the_manifest() {
+    let sandbox = Sandbox::new();
+
+    // Without `edition`, the manifest is of the first edition.
+    app(&sandbox, "");
+    let output = sandbox.tailor("app", &["build"]);
+    assert_eq!(output.error(), None);
+    let cmake_lists = sandbox.read(CMAKE_LISTS);
+    assert!(cmake_lists.contains("set(CMAKE_C_STANDARD 99)\n"));
+    assert!(cmake_lists.contains("src/main.c"));
+    assert!(!cmake_lists.contains("src/net/socket.c"));
+
+    app(&sandbox, "edition = \"2025.2\"\n");
+    let output = sandbox.tailor("app", &["build"]);
+    assert_eq!(output.error(), None);
+    let cmake_lists = sandbox.read(CMAKE_LISTS);
+    assert!(cmake_lists.contains("set(CMAKE_C_STANDARD 11)\n"));
+    assert!(cmake_lists.contains("src/net/socket.c"));
+}
+
+#[test]
+fn lib_key_is_only_accepted_before_2025_2() {
+    let sandbox = Sandbox::new();
+    app(&sandbox, "edition = \"2025.1\"\nlib = true\n");
+    let output = sandbox.tailor("app", &["build"]);
+    assert_eq!(output.error(), None);
+
+    app(&sandbox, "edition = \"2025.2\"\nlib = true\n");
+    let output = sandbox.tailor("app", &["build"]);
+    assert_eq!(
+        output.error(),
+        Some("the `lib` key was replaced by `type = \"lib\"` in edition `2025.2`")
+    );
+}
+
+#[test]
+fn newer_or_invalid_edition_is_an_error() {
+    let sandbox = Sandbox::new();
+    app(&sandbox, "edition = \"2099.1\"\n");
+    let output = sandbox.tailor("app", &["build"]);
+    assert_eq!(
+        output.error(),
+        Some("edition `2099.1` requires a newer Tailor, this one supports editions up to `2025.2`")
+    );
+
+    app(&sandbox, "edition = 2025\n");
+    let output = sandbox.tailor("app", &["build"]);
+    assert_eq!(output.error(), Some("edition must be a string"));
+}
+
+#[test]
+fn new_packages_get_the_latest_edition() {
+    let sandbox = Sandbox::new();
+    for args in [["new", "app"].as_slice(), &["new", "--lib", "mylib"]] {
+        let output = sandbox.tailor("", args);
+        assert_eq!(output.error(), None);
+    }
+
+    for manifest in ["app/Tailor.toml", "mylib/Tailor.toml"] {
+        assert!(
+            sandbox.read(manifest).contains("edition = \"2025.2\"\n"),
+            "{}",
+            sandbox.read(manifest)
+        );
+    }
+    let output = sandbox.tailor("app", &["build"]);
+    assert_eq!(output.error(), None);
+}
@@ -0,0 +1,192 @@
+#![cfg(unix)]
+
+mod common;
+
+use common::Sandbox;
+
+/// Writes the library `name` at the folder `path`, with the `[features]`
+/// table `features`.
+fn library(sandbox: &Sandbox, path: &str, name: &str, features: &str) {
+    sandbox.write(
+        &format!("{}/Tailor.toml", path),
+        &format!(
+            "name = \"{}\"\nversion = \"0.1.0\"\ntype = \"lib\"\n\n[features]\n{}",
+            name, features
+        ),
+    );
+    sandbox.write(&format!("{}/src/{}.c", path, name), "");
+}
+
+/// Writes the package `app`, depending on the libraries `mylib` and the
+/// optional `extra`, with the features `log` (by default), `fast` and `tls`.
+fn app(sandbox: &Sandbox) {
+    library(
+        sandbox,
+        "mylib",
+        "mylib",
+        "default = [\"small\"]\nsmall = []\ntls = []\n",
+    );
+    library(sandbox, "extra", "extra", "");
+    sandbox.write(
+        "app/Tailor.toml",
+        r#"name = "app"
+version = "0.1.0"
+
+[dependencies]
+mylib = { path = "../mylib" }
+extra = { path = "../extra", optional = true }
+
+[features]
+default = ["log"]
+log = []
+fast = []
+tls = ["dep:extra", "mylib/tls"]
+"#,
+    );
+    sandbox.write("app/src/main.c", "int main(void) { return 0; }\n");
+}
+
+/// The definitions set on the source ending with `source` in the generated
+/// `CMakeLists.txt` at `path`.
+fn definitions(sandbox: &Sandbox, path: &str, source: &str) -> Vec<String> {
+    let source = format!("{}\" APPEND PROPERTY COMPILE_DEFINITIONS ", source);
+    sandbox
+        .read(path)
+        .lines()
+        .find_map(|line| line.split_once(&source))
+        .map_or(vec![], |(_, definitions)| {
+            definitions
+                .trim_end_matches(')')
+                .split_whitespace()
+                .map(String::from)
+                .collect()
+        })
+}
+
+const CMAKE_LISTS: &str = "app/build/debug/CMakeLists.txt";
+
+#[test]
+fn features_follow_their_implications() {
+    let sandbox = Sandbox::new();
+    app(&sandbox);
+
+    let output = sandbox.tailor("app", &["build"]);
+    assert_eq!(output.error(), None);
+    assert_eq!(
+        definitions(&sandbox, CMAKE_LISTS, "app/src/main.c"),
+        [
+            "TAILOR_FEATURE_APP_DEFAULT",
+            "TAILOR_FEATURE_APP_LOG",
+            "TAILOR_FEATURE_MYLIB_DEFAULT",
+            "TAILOR_FEATURE_MYLIB_SMALL",
+        ]
+    );
+    assert!(!sandbox.read(CMAKE_LISTS).contains("extra.c"));
+
+    let output = sandbox.tailor("app", &["build", "-F", "tls", "--no-default-features"]);
+    assert_eq!(output.error(), None);
+    assert_eq!(
+        definitions(&sandbox, CMAKE_LISTS, "app/src/main.c"),
+        [
+            "TAILOR_FEATURE_APP_TLS",
+            "TAILOR_FEATURE_MYLIB_DEFAULT",
+            "TAILOR_FEATURE_MYLIB_SMALL",
+            "TAILOR_FEATURE_MYLIB_TLS",
+        ]
+    );
+    // A dependency only sees its own features.
+    assert_eq!(
+        definitions(&sandbox, CMAKE_LISTS, "mylib/src/mylib.c"),
+        [
+            "TAILOR_FEATURE_MYLIB_DEFAULT",
+            "TAILOR_FEATURE_MYLIB_SMALL",
+            "TAILOR_FEATURE_MYLIB_TLS",
+        ]
+    );
+    assert!(sandbox.read(CMAKE_LISTS).contains("extra/src/extra.c"));
+
+    let output = sandbox.tailor("app", &["build", "--all-features"]);
+    assert_eq!(output.error(), None);
+    assert!(
+        definitions(&sandbox, CMAKE_LISTS, "app/src/main.c").starts_with(&[
+            "TAILOR_FEATURE_APP_DEFAULT".to_string(),
+            "TAILOR_FEATURE_APP_FAST".to_string(),
+            "TAILOR_FEATURE_APP_LOG".to_string(),
+            "TAILOR_FEATURE_APP_TLS".to_string(),
+        ])
+    );
+
+    let output = sandbox.tailor("app", &["build", "-F", "nope"]);
+    assert_eq!(
+        output.error(),
+        Some("package `app` does not have the feature `nope`")
+    );
+}
+
+#[test]
+fn dependency_features_are_unified_over_the_workspace() {
+    let sandbox = Sandbox::new();
+    sandbox.write(
+        "Tailor.toml",
+        "[workspace]\nmembers = [\"first\", \"second\"]\n",
+    );
+    library(
+        &sandbox,
+        "shared",
+        "shared",
+        "default = [\"std\"]\nstd = []\nx = []\ny = []\n",
+    );
+    for (member, dependency) in [
+        (
+            "first",
+            "{ path = \"../shared\", features = [\"x\"], default-features = false }",
+        ),
+        (
+            "second",
+            "{ path = \"../shared\", features = [\"y\"], default-features = false }",
+        ),
+    ] {
+        sandbox.write(
+            &format!("{}/Tailor.toml", member),
+            &format!(
+                "name = \"{}\"\nversion = \"0.1.0\"\n\n[dependencies]\nshared = {}\n",
+                member, dependency
+            ),
+        );
+        sandbox.write(
+            &format!("{}/src/main.c", member),
+            "int main(void) { return 0; }\n",
+        );
+    }
+
+    let output = sandbox.tailor("first", &["build"]);
+    assert_eq!(output.error(), None);
+    assert_eq!(
+        definitions(
+            &sandbox,
+            "build/first/debug/CMakeLists.txt",
+            "shared/src/shared.c"
+        ),
+        ["TAILOR_FEATURE_SHARED_X", "TAILOR_FEATURE_SHARED_Y"]
+    );
+
+    // The default features of `shared` are only off when no member wants them.
+    sandbox.write(
+        "second/Tailor.toml",
+        "name = \"second\"\nversion = \"0.1.0\"\n\n[dependencies]\nshared = { path = \"../shared\" }\n",
+    );
+    let output = sandbox.tailor("first", &["build"]);
+    assert_eq!(output.error(), None);
+    assert_eq!(
+        definitions(
+            &sandbox,
+            "build/first/debug/CMakeLists.txt",
+            "shared/src/shared.c"
+        ),
+        [
+            "TAILOR_FEATURE_SHARED_DEFAULT",
+            "TAILOR_FEATURE_SHARED_STD",
+            "TAILOR_FEATURE_SHARED_X",
+        ]
+    );
+}
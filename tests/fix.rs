@@ -0,0 +1,52 @@
+#![cfg(unix)]
+
+mod common;
+
+use common::Sandbox;
+
+const MANIFEST: &str = r#"# The old way
+name = "mylib"
+version = "0.1.0"
+lib = true # library marker
+# Sources
+include = ["include"]
+"#;
+
+#[test]
+fn fix_migrates_the_manifest_keeping_its_comments() {
+    let sandbox = Sandbox::new();
+    sandbox.write("mylib/Tailor.toml", MANIFEST);
+
+    let output = sandbox.tailor("mylib", &["fix", "--edition", "2025.2"]);
+    assert_eq!(output.error(), None);
+    assert!(output.stdout.contains("warning"));
+    assert_eq!(
+        sandbox.read("mylib/Tailor.toml"),
+        r#"# The old way
+name = "mylib"
+version = "0.1.0"
+type = "lib" # library marker
+edition = "2025.2"
+src = ["src/*.c"]
+# Sources
+include = ["include"]
+"#
+    );
+
+    let output = sandbox.tailor("mylib", &["fix", "--edition", "2025.2"]);
+    assert_eq!(output.error(), None);
+    assert!(output.stdout.contains("is already on edition `2025.2`"));
+}
+
+#[test]
+fn dry_run_leaves_the_manifest_untouched() {
+    let sandbox = Sandbox::new();
+    sandbox.write("mylib/Tailor.toml", MANIFEST);
+
+    let output = sandbox.tailor("mylib", &["fix", "--edition", "2025.2", "--dry-run"]);
+    assert_eq!(output.error(), None);
+    assert!(output.stdout.contains("+ type = \"lib\" # library marker"));
+    assert!(output.stdout.contains("- lib = true # library marker"));
+    assert!(!output.stdout.contains("Fixed"));
+    assert_eq!(sandbox.read("mylib/Tailor.toml"), MANIFEST);
+}
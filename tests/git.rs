@@ -0,0 +1,153 @@
+#![cfg(unix)]
+
+mod common;
+
+use std::{os::unix::fs::PermissionsExt, process::Command};
+
+use common::Sandbox;
+
+/// Runs `git` with `args` in the folder `cwd` of the sandbox.
+fn git(sandbox: &Sandbox, cwd: &str, args: &[&str]) {
+    let status = Command::new("git")
+        .args(["-c", "user.name=Ada", "-c", "user.email=ada@example.com"])
+        .args(args)
+        .current_dir(sandbox.path(cwd))
+        .output()
+        .unwrap()
+        .status;
+    assert!(status.success(), "git {:?} failed", args);
+}
+
+/// A Git repository at `path` with the library `mylib` on `main`.
+fn repository(sandbox: &Sandbox, path: &str) {
+    sandbox.write(
+        &format!("{}/Tailor.toml", path),
+        "name = \"mylib\"\nversion = \"0.1.0\"\ntype = \"lib\"\n",
+    );
+    sandbox.write(
+        &format!("{}/src/mylib.c", path),
+        "int answer(void) { return 42; }\n",
+    );
+    git(sandbox, path, &["init", "-q", "-b", "main"]);
+    git(sandbox, path, &["add", "."]);
+    git(sandbox, path, &["commit", "-q", "-m", "Add mylib"]);
+}
+
+/// Writes the package `app`, depending on the repository at `url`.
+fn app(sandbox: &Sandbox, url: &str) {
+    sandbox.write(
+        "app/Tailor.toml",
+        &format!(
+            "name = \"app\"\nversion = \"0.1.0\"\n\n[dependencies]\nmylib = {{ git = \"{}\" }}\n",
+            url
+        ),
+    );
+    sandbox.write("app/src/main.c", "int main(void) { return 0; }\n");
+}
+
+/// Replaces the Git repositories under `prefix` with the folder `mirror`.
+fn mirror(sandbox: &Sandbox, prefix: &str) {
+    sandbox.write(
+        "config/tailor/config.toml",
+        &format!(
+            "[source.upstream]\ngit = \"{}\"\nreplace-with = \"mirror\"\n\n[source.mirror]\ndirectory = \"{}\"\n",
+            prefix,
+            sandbox.path("mirror").display()
+        ),
+    );
+}
+
+#[test]
+fn failed_clone_is_an_error() {
+    let sandbox = Sandbox::new();
+    let url = sandbox.path("missing.git").display().to_string();
+    app(&sandbox, &url);
+
+    let output = sandbox.tailor("app", &["build"]);
+    assert_eq!(
+        output.error(),
+        Some(format!("fail to clone repository `{}`", url).as_str())
+    );
+}
+
+#[test]
+fn mirror_prefix_without_trailing_slash() {
+    let sandbox = Sandbox::new();
+    repository(&sandbox, "mirror/mylib.git");
+    mirror(&sandbox, "https://git.example.com/org");
+    app(&sandbox, "https://git.example.com/org/mylib.git");
+
+    let output = sandbox.tailor("app", &["build"]);
+    assert_eq!(output.error(), None);
+}
+
+#[test]
+fn mirror_prefix_matches_whole_path_segments() {
+    let sandbox = Sandbox::new();
+    repository(&sandbox, "upstream/organization/mylib.git");
+    std::fs::create_dir_all(sandbox.path("mirror")).unwrap();
+    mirror(
+        &sandbox,
+        &sandbox.path("upstream/org").display().to_string(),
+    );
+    app(
+        &sandbox,
+        &sandbox
+            .path("upstream/organization/mylib.git")
+            .display()
+            .to_string(),
+    );
+
+    let output = sandbox.tailor("app", &["build"]);
+    assert_eq!(output.error(), None);
+}
+
+#[test]
+fn git_trusts_the_certificate_authorities_of_the_config() {
+    let mut sandbox = Sandbox::new();
+    repository(&sandbox, "mylib.git");
+    app(&sandbox, &sandbox.path("mylib.git").display().to_string());
+    sandbox.write("system.pem", "system authority");
+    sandbox.env(
+        "SSL_CERT_FILE",
+        &sandbox.path("system.pem").display().to_string(),
+    );
+    sandbox.write(
+        "config/tailor/config.toml",
+        "[http]\ncainfo = [\"corporate-ca.pem\", \"lab-ca.pem\"]\n",
+    );
+    sandbox.write("config/tailor/corporate-ca.pem", "corporate authority\n");
+    sandbox.write("config/tailor/lab-ca.pem", "lab authority\n");
+
+    // Records the bundle given to `git` before running it.
+    let git = String::from_utf8(Command::new("which").arg("git").output().unwrap().stdout).unwrap();
+    sandbox.write(
+        "bin/git",
+        &format!(
+            "#!/bin/sh\necho \"$GIT_SSL_CAINFO\" >> \"{}\"\nexec {} \"$@\"\n",
+            sandbox.path("git.log").display(),
+            git.trim()
+        ),
+    );
+    let mut permissions = std::fs::metadata(sandbox.path("bin/git"))
+        .unwrap()
+        .permissions();
+    permissions.set_mode(0o755);
+    std::fs::set_permissions(sandbox.path("bin/git"), permissions).unwrap();
+
+    let output = sandbox.tailor("app", &["build"]);
+    assert_eq!(output.error(), None);
+    let bundles = sandbox.read("git.log");
+    let bundle = bundles.lines().next().unwrap();
+    assert_eq!(
+        bundle,
+        sandbox
+            .path("config/tailor/git-cainfo.pem")
+            .display()
+            .to_string()
+    );
+    assert_eq!(
+        std::fs::read_to_string(bundle).unwrap(),
+        "system authority\ncorporate authority\nlab authority\n"
+    );
+}
@@ -0,0 +1,199 @@
+use tailor_rs::kconfig::{Kconfig, Values, parse_config};
+
+#[test]
+fn sourcing_a_file_twice_is_fine() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        dir.path().join("Kconfig"),
+        "source \"common\"\nmenu \"More\"\nsource \"common\"\nendmenu\n",
+    )
+    .unwrap();
+    std::fs::write(dir.path().join("common"), "comment \"Common\"\n").unwrap();
+
+    assert!(Kconfig::from_file(&dir.path().join("Kconfig")).is_ok());
+}
+
+#[test]
+fn recursive_source_is_an_error() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("Kconfig"), "source \"drivers\"\n").unwrap();
+    std::fs::write(
+        dir.path().join("drivers"),
+        "config DRIVER\n\tbool \"Driver\"\nrsource \"Kconfig\"\n",
+    )
+    .unwrap();
+
+    let error = Kconfig::from_file(&dir.path().join("Kconfig"))
+        .err()
+        .unwrap();
+    assert!(error.contains("recursive source"), "{}", error);
+}
+
+/// Parses `content` as a Kconfig file.
+fn kconfig(content: &str) -> Kconfig {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("Kconfig"), content).unwrap();
+    Kconfig::from_file(&dir.path().join("Kconfig")).unwrap()
+}
+
+/// Resolves `kconfig` with the `.config` content `config`.
+fn resolve(kconfig: &Kconfig, config: &str) -> Values {
+    kconfig.resolve(&parse_config(config).unwrap())
+}
+
+fn value<'a>(values: &'a Values, name: &str) -> Option<&'a str> {
+    values.get(name).map(String::as_str)
+}
+
+#[test]
+fn depends_on_hides_symbols() {
+    let kconfig = kconfig(
+        "config NET\n\tbool \"Network\"\n\
+         config TCP\n\tbool \"TCP\"\n\tdefault y\n\tdepends on NET\n\
+         if TCP\nconfig TCP_PORT\n\tint \"Port\"\n\tdefault 80\nendif\n",
+    );
+
+    let values = resolve(&kconfig, "");
+    assert_eq!(value(&values, "NET"), Some("n"));
+    assert_eq!(value(&values, "TCP"), None);
+    assert_eq!(value(&values, "TCP_PORT"), None);
+
+    let values = resolve(&kconfig, "CONFIG_NET=y\n");
+    assert_eq!(value(&values, "TCP"), Some("y"));
+    assert_eq!(value(&values, "TCP_PORT"), Some("80"));
+
+    // A user value isn't applied when the dependencies aren't met.
+    let values = resolve(&kconfig, "CONFIG_TCP=y\nCONFIG_TCP_PORT=8080\n");
+    assert_eq!(value(&values, "TCP"), None);
+    assert_eq!(value(&values, "TCP_PORT"), None);
+}
+
+#[test]
+fn redefinition_adds_its_dependencies() {
+    let kconfig = kconfig(
+        "config A\n\tbool \"A\"\nconfig B\n\tbool \"B\"\n\
+         config FEATURE\n\tbool \"Feature\"\n\tdefault y\n\tdepends on A\n\
+         config FEATURE\n\tbool\n\tdepends on B\n",
+    );
+
+    assert_eq!(value(&resolve(&kconfig, ""), "FEATURE"), None);
+    let values = resolve(&kconfig, "CONFIG_A=y\n");
+    assert_eq!(value(&values, "FEATURE"), Some("y"));
+    // The default and the prompt come from the definition depending on `A`.
+    let values = resolve(&kconfig, "CONFIG_B=y\nCONFIG_FEATURE=y\n");
+    assert_eq!(value(&values, "FEATURE"), Some("n"));
+
+    let kconfig = self::kconfig(
+        "config A\n\tbool \"A\"\n\
+         config LEVEL\n\tint \"Level\"\n\tdefault 1\n\
+         if A\nconfig LEVEL\n\tint\n\tdefault 5\nendif\n",
+    );
+    assert_eq!(value(&resolve(&kconfig, ""), "LEVEL"), Some("1"));
+}
+
+#[test]
+fn select_forces_and_imply_suggests() {
+    let kconfig = kconfig(
+        "config DRIVER\n\tbool \"Driver\"\n\tselect BUS\n\timply LOG if DEBUG\n\
+         config BUS\n\tbool \"Bus\"\n\
+         config LOG\n\tbool \"Log\"\n\
+         config DEBUG\n\tbool \"Debug\"\n\tdefault y\n",
+    );
+
+    let values = resolve(&kconfig, "CONFIG_DRIVER=y\n# CONFIG_BUS is not set\n");
+    assert_eq!(value(&values, "BUS"), Some("y"));
+    assert_eq!(value(&values, "LOG"), Some("y"));
+
+    // The user can turn off an implied symbol, but not a selected one.
+    let values = resolve(
+        &kconfig,
+        "CONFIG_DRIVER=y\n# CONFIG_BUS is not set\n# CONFIG_LOG is not set\n",
+    );
+    assert_eq!(value(&values, "BUS"), Some("y"));
+    assert_eq!(value(&values, "LOG"), Some("n"));
+
+    let values = resolve(&kconfig, "CONFIG_DRIVER=y\n# CONFIG_DEBUG is not set\n");
+    assert_eq!(value(&values, "LOG"), Some("n"));
+}
+
+#[test]
+fn choice_selects_one_member() {
+    let kconfig = kconfig(
+        "config FAST\n\tbool \"Fast\"\n\
+         choice\n\tprompt \"Optimization\"\n\tdefault OPT_SIZE\n\
+         config OPT_NONE\n\tbool \"None\"\n\
+         comment \"Optimized builds\"\n\
+         config OPT_SIZE\n\tbool \"Size\"\n\
+         if FAST\nconfig OPT_SPEED\n\tbool \"Speed\"\nendif\n\
+         endchoice\n",
+    );
+
+    let values = resolve(&kconfig, "");
+    assert_eq!(value(&values, "OPT_NONE"), Some("n"));
+    assert_eq!(value(&values, "OPT_SIZE"), Some("y"));
+    assert_eq!(value(&values, "OPT_SPEED"), None);
+
+    let values = resolve(&kconfig, "CONFIG_OPT_NONE=y\n");
+    assert_eq!(value(&values, "OPT_NONE"), Some("y"));
+    assert_eq!(value(&values, "OPT_SIZE"), Some("n"));
+
+    // A member hidden by its `if` can't be chosen.
+    let values = resolve(&kconfig, "CONFIG_OPT_SPEED=y\n");
+    assert_eq!(value(&values, "OPT_SIZE"), Some("y"));
+    let values = resolve(&kconfig, "CONFIG_FAST=y\nCONFIG_OPT_SPEED=y\n");
+    assert_eq!(value(&values, "OPT_SPEED"), Some("y"));
+    assert_eq!(value(&values, "OPT_SIZE"), Some("n"));
+}
+
+#[test]
+fn conditional_defaults_and_ranges() {
+    let kconfig = kconfig(
+        "config BIG\n\tbool \"Big\"\n\
+         config SIZE\n\tint \"Size\"\n\tdefault 1024 if BIG\n\tdefault 64\n\trange 16 512\n\
+         config BASE\n\thex \"Base\"\n\tdefault 0x1000\n\trange 0x100 0x2000\n",
+    );
+
+    let values = resolve(&kconfig, "");
+    assert_eq!(value(&values, "SIZE"), Some("64"));
+    assert_eq!(value(&values, "BASE"), Some("0x1000"));
+
+    let values = resolve(&kconfig, "CONFIG_BIG=y\n");
+    assert_eq!(value(&values, "SIZE"), Some("512"));
+
+    let values = resolve(&kconfig, "CONFIG_SIZE=2\nCONFIG_BASE=0x4000\n");
+    assert_eq!(value(&values, "SIZE"), Some("16"));
+    assert_eq!(value(&values, "BASE"), Some("0x2000"));
+}
+
+#[test]
+fn config_file_and_header_hold_the_values() {
+    let kconfig = kconfig(
+        "mainmenu \"Demo\"\n\
+         config ENABLED\n\tbool \"Enabled\"\n\tdefault y\n\
+         config DISABLED\n\tbool \"Disabled\"\n\
+         config DRIVER\n\ttristate \"Driver\"\n\tdefault m\n\
+         config NAME\n\tstring \"Name\"\n\tdefault \"say \\\"hi\\\"\"\n\
+         config COUNT\n\tint \"Count\"\n\tdefault 3\n\
+         config ADDRESS\n\thex \"Address\"\n\tdefault 0x20\n",
+    );
+    let values = resolve(&kconfig, "");
+
+    assert_eq!(
+        kconfig.config_file(&values),
+        "# Automatically generated by Tailor\n# Demo\n\
+         CONFIG_ENABLED=y\n# CONFIG_DISABLED is not set\nCONFIG_DRIVER=m\n\
+         CONFIG_NAME=\"say \\\"hi\\\"\"\nCONFIG_COUNT=3\nCONFIG_ADDRESS=0x20\n"
+    );
+    assert_eq!(
+        kconfig.header(&values),
+        "/* Automatically generated by Tailor, do not edit */\n\
+         #ifndef AUTOCONF_H\n#define AUTOCONF_H\n\n\
+         #define CONFIG_ENABLED 1\n#define CONFIG_DRIVER_MODULE 1\n\
+         #define CONFIG_NAME \"say \\\"hi\\\"\"\n#define CONFIG_COUNT 3\n\
+         #define CONFIG_ADDRESS 0x20\n\n#endif\n"
+    );
+
+    // The `.config` file reads back to the same values.
+    let config = kconfig.config_file(&values);
+    assert_eq!(resolve(&kconfig, &config), values);
+}
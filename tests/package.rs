@@ -0,0 +1,228 @@
+#![cfg(unix)]
+
+mod common;
+
+use std::process::Command;
+
+use common::{Sandbox, registry::Registry};
+
+#[test]
+fn packaging_leaves_out_hidden_and_build_files() {
+    let sandbox = Sandbox::new();
+    sandbox.library("mylib", "0.1.0");
+    sandbox.write("mylib/.config", "CONFIG_A=y\n");
+    sandbox.write("mylib/build/debug/libmylib.a", "");
+
+    let output = sandbox.tailor("mylib", &["package", "--no-verify"]);
+    assert_eq!(output.error(), None);
+    assert_eq!(
+        sandbox.tarball_files("mylib/build/package/mylib-0.1.0.tar.gz"),
+        [
+            "mylib-0.1.0/Tailor.toml",
+            "mylib-0.1.0/include/mylib/mylib.h",
+            "mylib-0.1.0/src/mylib.c",
+        ]
+    );
+}
+
+#[test]
+fn packaging_honours_gitignore() {
+    let sandbox = Sandbox::new();
+    sandbox.library("mylib", "0.1.0");
+    sandbox.write(".gitignore", "*.log\n");
+    sandbox.write("mylib/.gitignore", "generated/\n");
+    sandbox.write("mylib/debug.log", "");
+    sandbox.write("mylib/generated/table.c", "");
+    sandbox.write("mylib/notes.txt", "");
+    let status = Command::new("git")
+        .args(["init", "-q"])
+        .current_dir(sandbox.root())
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let output = sandbox.tailor("mylib", &["package", "--no-verify"]);
+    assert_eq!(output.error(), None);
+    assert_eq!(
+        sandbox.tarball_files("mylib/build/package/mylib-0.1.0.tar.gz"),
+        [
+            "mylib-0.1.0/Tailor.toml",
+            "mylib-0.1.0/include/mylib/mylib.h",
+            "mylib-0.1.0/notes.txt",
+            "mylib-0.1.0/src/mylib.c",
+        ]
+    );
+}
+
+#[test]
+fn published_library_is_used_from_the_registry() {
+    let registry = Registry::start();
+    let mut sandbox = Sandbox::new();
+    sandbox.env("TAILOR_REGISTRY", registry.url());
+    sandbox.library("mylib", "0.1.0");
+
+    let output = sandbox.tailor("mylib", &["publish", "--token", "secret"]);
+    assert_eq!(output.error(), None);
+    let index = registry.index("mylib");
+    assert_eq!(index.len(), 1);
+    assert_eq!(registry.owners("mylib"), ["ada"]);
+
+    let output = sandbox.tailor("mylib", &["publish", "--token", "secret"]);
+    assert!(output.error().unwrap().contains("is already published"));
+
+    sandbox.write(
+        "app/Tailor.toml",
+        "name = \"app\"\nversion = \"0.1.0\"\n\n[dependencies]\nmylib = \"0.1.0\"\n",
+    );
+    sandbox.write("app/src/main.c", "int main(void) { return 0; }\n");
+    let output = sandbox.tailor("app", &["build"]);
+    assert_eq!(output.error(), None);
+    let lockfile = sandbox.read("app/Tailor.lock");
+    assert!(lockfile.contains(index[0]["checksum"].as_str().unwrap()));
+}
+
+#[test]
+fn failed_verification_stops_packaging_and_publishing() {
+    let registry = Registry::start();
+    let mut sandbox = Sandbox::new();
+    sandbox.env("TAILOR_REGISTRY", registry.url());
+    sandbox.library("mylib", "0.1.0");
+    sandbox.fail_cmake();
+
+    let output = sandbox.tailor("mylib", &["package"]);
+    assert!(
+        output
+            .error()
+            .unwrap()
+            .starts_with("fail to verify `mylib`: fail to generate the build files")
+    );
+    assert!(!output.stdout.contains("Packaged"));
+    assert!(
+        !sandbox
+            .path("mylib/build/package/mylib-0.1.0.tar.gz.sha256")
+            .exists()
+    );
+
+    let output = sandbox.tailor("mylib", &["publish", "--token", "secret"]);
+    assert!(
+        output
+            .error()
+            .unwrap()
+            .starts_with("fail to verify `mylib`")
+    );
+    assert!(registry.index("mylib").is_empty());
+}
+
+#[test]
+fn keywords_must_be_single_words() {
+    let sandbox = Sandbox::new();
+    sandbox.library("mylib", "0.1.0");
+    let manifest = sandbox.read("mylib/Tailor.toml");
+    sandbox.write(
+        "mylib/Tailor.toml",
+        &format!("{}keywords = [\"math\", \"fast math\"]\n", manifest),
+    );
+
+    let output = sandbox.tailor("mylib", &["package", "--no-verify"]);
+    assert_eq!(
+        output.error(),
+        Some("keyword `fast math` of `mylib` must be a single word")
+    );
+
+    sandbox.write(
+        "mylib/Tailor.toml",
+        &format!("{}keywords = [\"math\", \"fast\"]\n", manifest),
+    );
+    let output = sandbox.tailor("mylib", &["package", "--no-verify"]);
+    assert_eq!(output.error(), None);
+    assert!(output.stdout.contains("`mylib` has no `authors`"));
+}
+
+/// Writes the binary package `app` with the `src` entries `sources`, and
+/// the files `files`.
+fn sources_app(sandbox: &Sandbox, sources: &str, files: &[&str]) {
+    sandbox.write(
+        "app/Tailor.toml",
+        &format!("name = \"app\"\nversion = \"0.1.0\"\nsrc = {}\n", sources),
+    );
+    for file in files {
+        sandbox.write(&format!("app/{}", file), "");
+    }
+}
+
+/// The sources of `app` in its generated `CMakeLists.txt`, relative to it.
+fn listed_sources(sandbox: &Sandbox) -> Vec<String> {
+    let root = format!("\"{}/", sandbox.path("app").display());
+    sandbox
+        .read("app/build/debug/CMakeLists.txt")
+        .lines()
+        .filter_map(|line| line.trim().strip_prefix(&root))
+        .filter_map(|line| line.strip_suffix(".c\""))
+        .map(|source| format!("{}.c", source))
+        .collect()
+}
+
+#[test]
+fn source_patterns_are_expanded_sorted_without_the_excluded_files() {
+    let sandbox = Sandbox::new();
+    sources_app(
+        &sandbox,
+        r#"["src/**/*.c", "!src/platform/*.c", "lib/extra.c", "src/main.c"]"#,
+        &[
+            "src/main.c",
+            "src/zeta.c",
+            "src/net/tcp/socket.c",
+            "src/platform/win.c",
+            "src/platform/posix/posix.c",
+            "src/notes.txt",
+            "lib/extra.c",
+            "lib/unused.c",
+        ],
+    );
+
+    let output = sandbox.tailor("app", &["build"]);
+    assert_eq!(output.error(), None);
+    assert_eq!(
+        listed_sources(&sandbox),
+        [
+            "lib/extra.c",
+            "src/main.c",
+            "src/net/tcp/socket.c",
+            "src/platform/posix/posix.c",
+            "src/zeta.c",
+        ]
+    );
+}
+
+#[test]
+fn missing_source_file_is_an_error() {
+    let sandbox = Sandbox::new();
+    sources_app(&sandbox, r#"["src/main.c", "src/gone.c"]"#, &["src/main.c"]);
+
+    let output = sandbox.tailor("app", &["build"]);
+    assert_eq!(
+        output.error(),
+        Some("source file `src/gone.c` does not exist")
+    );
+}
+
+#[test]
+fn adding_a_matching_source_regenerates_the_build() {
+    let sandbox = Sandbox::new();
+    sources_app(&sandbox, r#"["src/**/*.c"]"#, &["src/main.c"]);
+    sandbox.tailor("app", &["build"]);
+    assert_eq!(sandbox.cmake_generations().len(), 1);
+
+    let output = sandbox.tailor("app", &["build"]);
+    assert_eq!(output.error(), None);
+    assert!(sandbox.cmake_generations().is_empty());
+
+    sandbox.write("app/src/util/strings.c", "");
+    let output = sandbox.tailor("app", &["build"]);
+    assert_eq!(output.error(), None);
+    assert_eq!(sandbox.cmake_generations().len(), 1);
+    assert_eq!(
+        listed_sources(&sandbox),
+        ["src/main.c", "src/util/strings.c"]
+    );
+}
@@ -0,0 +1,436 @@
+#![cfg(unix)]
+
+mod common;
+
+use std::os::unix::fs::PermissionsExt;
+
+use common::{Sandbox, registry::Registry};
+
+/// A sandbox using `registry`, where `mylib` was published at `versions`.
+fn published(registry: &Registry, versions: &[&str]) -> Sandbox {
+    let mut sandbox = Sandbox::new();
+    sandbox.env("TAILOR_REGISTRY", registry.url());
+    for version in versions {
+        sandbox.library("mylib", version);
+        let output = sandbox.tailor("mylib", &["publish", "--no-verify", "--token", "secret"]);
+        assert_eq!(output.error(), None);
+    }
+    sandbox
+}
+
+/// Writes the package `app`, depending on `mylib` with `requirement`.
+fn app(sandbox: &Sandbox, requirement: &str) {
+    sandbox.write(
+        "app/Tailor.toml",
+        &format!(
+            "name = \"app\"\nversion = \"0.1.0\"\n\n[dependencies]\nmylib = {}\n",
+            requirement
+        ),
+    );
+    sandbox.write("app/src/main.c", "int main(void) { return 0; }\n");
+}
+
+/// The version of `mylib` locked by `app`.
+fn locked_version(sandbox: &Sandbox) -> String {
+    let lockfile = sandbox
+        .read("app/Tailor.lock")
+        .parse::<toml::Table>()
+        .unwrap();
+    lockfile["dependency"][0]["version"]
+        .as_str()
+        .unwrap()
+        .to_string()
+}
+
+#[test]
+fn requirement_selects_the_newest_matching_version() {
+    let registry = Registry::start();
+    let sandbox = published(&registry, &["1.0.0", "1.1.0", "1.2.0", "2.0.0"]);
+    let output = sandbox.tailor("mylib", &["yank", "mylib@1.2.0", "--token", "secret"]);
+    assert_eq!(output.error(), None);
+
+    app(&sandbox, "\"1.0\"");
+    let output = sandbox.tailor("app", &["build"]);
+    assert_eq!(output.error(), None);
+    assert_eq!(locked_version(&sandbox), "1.1.0");
+    assert!(
+        sandbox
+            .read("app/build/debug/CMakeLists.txt")
+            .contains("mylib@1.1.0")
+    );
+
+    app(&sandbox, "{ version = \">=1.0, <1.1\" }");
+    let output = sandbox.tailor("app", &["build"]);
+    assert_eq!(output.error(), None);
+    assert_eq!(locked_version(&sandbox), "1.0.0");
+
+    app(&sandbox, "\"=2.0.0\"");
+    let output = sandbox.tailor("app", &["build"]);
+    assert_eq!(output.error(), None);
+    assert_eq!(locked_version(&sandbox), "2.0.0");
+}
+
+#[test]
+fn locked_version_is_kept_when_a_newer_one_is_published() {
+    let registry = Registry::start();
+    let sandbox = published(&registry, &["0.1.0"]);
+    app(&sandbox, "\"0.1\"");
+    sandbox.tailor("app", &["build"]);
+    assert_eq!(locked_version(&sandbox), "0.1.0");
+
+    sandbox.library("mylib", "0.1.1");
+    sandbox.tailor("mylib", &["publish", "--no-verify", "--token", "secret"]);
+    let output = sandbox.tailor("app", &["build"]);
+    assert_eq!(output.error(), None);
+    assert_eq!(locked_version(&sandbox), "0.1.0");
+
+    std::fs::remove_file(sandbox.path("app/Tailor.lock")).unwrap();
+    sandbox.tailor("app", &["build"]);
+    assert_eq!(locked_version(&sandbox), "0.1.1");
+}
+
+#[test]
+fn unmatched_and_invalid_requirements_are_errors() {
+    let registry = Registry::start();
+    let sandbox = published(&registry, &["1.0.0"]);
+
+    app(&sandbox, "\"1.1\"");
+    let output = sandbox.tailor("app", &["build"]);
+    assert_eq!(
+        output.error(),
+        Some(
+            format!(
+                "no version of `mylib` matching `1.1` found in registry {}",
+                registry.url()
+            )
+            .as_str()
+        )
+    );
+
+    app(&sandbox, "\"latest\"");
+    let output = sandbox.tailor("app", &["build"]);
+    assert!(
+        output
+            .error()
+            .unwrap()
+            .contains("invalid version requirement `latest`")
+    );
+}
+
+#[test]
+fn info_shows_versions_of_newer_editions() {
+    let registry = Registry::start();
+    let sandbox = published(&registry, &["1.0.0"]);
+    registry.add(
+        "mylib",
+        "2.0.0",
+        r#"name = "mylib"
+version = "2.0.0"
+edition = "2099.1"
+type = "lib"
+description = "A library from the future"
+keywords = ["math"]
+future-key = { anything = true }
+
+[features]
+fast = []
+"#,
+    );
+
+    let output = sandbox.tailor("mylib", &["info", "mylib"]);
+    assert_eq!(output.error(), None);
+    assert!(
+        output
+            .stdout
+            .starts_with("mylib @ 2.0.0\nA library from the future\n")
+    );
+    assert!(output.stdout.contains("edition:     2099.1\n"));
+    assert!(output.stdout.contains("keywords:    math\n"));
+    assert!(output.stdout.contains("versions:    1.0.0, 2.0.0\n"));
+    assert!(output.stdout.contains("  fast = []\n"));
+}
+
+#[test]
+fn yanked_versions_are_skipped_unless_locked() {
+    let registry = Registry::start();
+    let sandbox = published(&registry, &["1.0.0", "1.1.0"]);
+    app(&sandbox, "\"1.0\"");
+    sandbox.tailor("app", &["build"]);
+    assert_eq!(locked_version(&sandbox), "1.1.0");
+
+    let output = sandbox.tailor("mylib", &["yank", "mylib@1.1.0", "--token", "secret"]);
+    assert_eq!(output.error(), None);
+    // The locked version is downloaded again, as on another machine.
+    std::fs::remove_dir_all(sandbox.path("config/tailor/packages")).unwrap();
+    std::fs::remove_dir_all(sandbox.path("config/tailor/registry")).unwrap();
+    let output = sandbox.tailor("app", &["build"]);
+    assert_eq!(output.error(), None);
+    assert!(
+        output
+            .stdout
+            .contains("`mylib` @ 1.1.0 was yanked, but it's still used")
+    );
+    assert_eq!(locked_version(&sandbox), "1.1.0");
+
+    std::fs::remove_file(sandbox.path("app/Tailor.lock")).unwrap();
+    let output = sandbox.tailor("app", &["build"]);
+    assert_eq!(output.error(), None);
+    assert_eq!(locked_version(&sandbox), "1.0.0");
+
+    sandbox.tailor("mylib", &["yank", "mylib@1.0.0", "--token", "secret"]);
+    std::fs::remove_file(sandbox.path("app/Tailor.lock")).unwrap();
+    let output = sandbox.tailor("app", &["build"]);
+    assert_eq!(
+        output.error(),
+        Some(
+            format!(
+                "`mylib` @ 1.1.0 was yanked from registry {}",
+                registry.url()
+            )
+            .as_str()
+        )
+    );
+
+    let output = sandbox.tailor(
+        "mylib",
+        &["yank", "mylib@1.1.0", "--undo", "--token", "secret"],
+    );
+    assert_eq!(output.error(), None);
+    let output = sandbox.tailor("app", &["build"]);
+    assert_eq!(output.error(), None);
+    assert_eq!(locked_version(&sandbox), "1.1.0");
+}
+
+#[test]
+fn only_owners_change_a_package() {
+    let registry = Registry::start();
+    let sandbox = published(&registry, &["1.0.0"]);
+    assert_eq!(registry.owners("mylib"), ["ada"]);
+
+    let output = sandbox.tailor("mylib", &["yank", "mylib@1.0.0", "--token", "other"]);
+    assert!(output.error().unwrap().contains("refused the token"));
+    sandbox.library("mylib", "1.1.0");
+    let output = sandbox.tailor("mylib", &["publish", "--no-verify", "--token", "other"]);
+    assert!(output.error().unwrap().contains("refused the token"));
+
+    let output = sandbox.tailor("mylib", &["owner", "--add", "bob", "--token", "secret"]);
+    assert_eq!(output.error(), None);
+    let output = sandbox.tailor("mylib", &["owner", "--list", "--token", "other"]);
+    assert_eq!(output.stdout, "ada\nbob\n");
+    let output = sandbox.tailor("mylib", &["publish", "--no-verify", "--token", "other"]);
+    assert_eq!(output.error(), None);
+
+    let output = sandbox.tailor(
+        "mylib",
+        &["owner", "--remove", "ada", "mylib", "--token", "other"],
+    );
+    assert_eq!(output.error(), None);
+    assert_eq!(registry.owners("mylib"), ["bob"]);
+    let output = sandbox.tailor("mylib", &["yank", "mylib@1.1.0", "--token", "secret"]);
+    assert!(output.error().unwrap().contains("refused the token"));
+}
+
+#[test]
+fn publish_succeeds_when_its_answer_is_lost() {
+    let registry = Registry::start();
+    let sandbox = published(&registry, &[]);
+    sandbox.library("mylib", "1.0.0");
+
+    registry.lose_next_answer("PUT");
+    let output = sandbox.tailor("mylib", &["publish", "--no-verify", "--token", "secret"]);
+    assert_eq!(output.error(), None);
+    let uploads = registry
+        .requests()
+        .into_iter()
+        .filter(|request| request.starts_with("PUT "))
+        .count();
+    assert_eq!(uploads, 2);
+    assert_eq!(registry.index("mylib").len(), 1);
+
+    sandbox.library("mylib", "1.1.0");
+    registry.fail_next(1);
+    let output = sandbox.tailor("mylib", &["publish", "--no-verify", "--token", "secret"]);
+    assert_eq!(output.error(), None);
+    assert_eq!(registry.index("mylib").len(), 2);
+}
+
+#[test]
+fn search_matches_names_descriptions_and_keywords() {
+    let registry = Registry::start();
+    let sandbox = published(&registry, &["1.0.0", "1.1.0"]);
+    sandbox.library("fastmath", "0.2.0");
+    sandbox.write(
+        "fastmath/Tailor.toml",
+        "name = \"fastmath\"\nversion = \"0.2.0\"\ntype = \"lib\"\ndescription = \"Quick arithmetic\"\nauthors = [\"Ada\"]\nkeywords = [\"numeric\", \"simd\"]\n",
+    );
+    let output = sandbox.tailor("fastmath", &["publish", "--no-verify", "--token", "secret"]);
+    assert_eq!(output.error(), None);
+
+    let output = sandbox.tailor("mylib", &["search", "lib"]);
+    assert_eq!(output.error(), None);
+    assert_eq!(output.stdout, "mylib = \"1.1.0\" # A library\n");
+
+    let output = sandbox.tailor("mylib", &["search", "simd"]);
+    assert_eq!(output.stdout, "fastmath = \"0.2.0\" # Quick arithmetic\n");
+
+    let output = sandbox.tailor("mylib", &["search", "quick", "arithmetic"]);
+    assert_eq!(output.stdout, "fastmath = \"0.2.0\" # Quick arithmetic\n");
+
+    let output = sandbox.tailor("mylib", &["search", "a"]);
+    assert_eq!(
+        output.stdout,
+        "fastmath = \"0.2.0\" # Quick arithmetic\nmylib = \"1.1.0\"    # A library\n"
+    );
+    let output = sandbox.tailor("mylib", &["search", "a", "--limit", "1"]);
+    assert_eq!(output.stdout.lines().count(), 1);
+
+    let output = sandbox.tailor("mylib", &["search", "graphics"]);
+    assert_eq!(
+        output.stdout,
+        format!("no packages matching `graphics` in {}\n", registry.url())
+    );
+}
+
+#[test]
+fn saved_token_is_private_and_never_printed() {
+    let registry = Registry::start();
+    let mut sandbox = Sandbox::new();
+    sandbox.env("TAILOR_REGISTRY", registry.url());
+    sandbox.library("mylib", "1.0.0");
+
+    let mut outputs = vec![sandbox.tailor(".", &["login", "secret"])];
+    let credentials = sandbox.path("config/tailor/credentials.toml");
+    let mode = std::fs::metadata(&credentials)
+        .unwrap()
+        .permissions()
+        .mode();
+    assert_eq!(mode & 0o777, 0o600);
+    assert!(
+        sandbox
+            .read("config/tailor/credentials.toml")
+            .contains("secret")
+    );
+
+    outputs.push(sandbox.tailor("mylib", &["publish", "--no-verify"]));
+    assert_eq!(outputs[1].error(), None);
+    outputs.push(sandbox.tailor("mylib", &["publish", "--no-verify"]));
+    assert!(outputs[2].error().unwrap().contains("is already published"));
+    outputs.push(sandbox.tailor("mylib", &["yank", "mylib@9.9.9"]));
+    assert!(outputs[3].error().is_some());
+
+    sandbox.tailor(".", &["login", "not-a-user-token"]);
+    let output = sandbox.tailor("mylib", &["owner", "--list"]);
+    assert!(output.error().unwrap().contains("refused the token"));
+    assert!(!output.stderr.contains("not-a-user-token"));
+    assert!(!output.stdout.contains("not-a-user-token"));
+
+    for output in outputs {
+        assert!(!output.stdout.contains("secret"), "{}", output.stdout);
+        assert!(!output.stderr.contains("secret"), "{}", output.stderr);
+    }
+}
+
+#[test]
+fn saved_token_is_sent_to_the_mirror_of_the_registry() {
+    let mirror = Registry::start();
+    let registry = Registry::start();
+    let mut sandbox = published(&mirror, &["1.0.0"]);
+    sandbox.env("TAILOR_REGISTRY", registry.url());
+    let output = sandbox.tailor(".", &["login", "secret"]);
+    assert_eq!(output.error(), None);
+    sandbox.write(
+        "config/tailor/config.toml",
+        &format!(
+            "[source.main]\nregistry = \"{}\"\nreplace-with = \"mirror\"\n\n[source.mirror]\nregistry = \"{}\"\n",
+            registry.url(),
+            mirror.url()
+        ),
+    );
+    mirror.tokens();
+
+    app(&sandbox, "\"1.0\"");
+    let output = sandbox.tailor("app", &["build"]);
+    assert_eq!(output.error(), None);
+    let tokens = mirror.tokens();
+    assert!(!tokens.is_empty());
+    assert!(
+        tokens
+            .iter()
+            .all(|token| token.as_deref() == Some("secret"))
+    );
+    assert!(registry.requests().is_empty());
+}
+
+/// Writes the global config `config`, with `[source.wrong]`, an empty folder
+/// replacing the registries matched by mistake, and `[source.mirror]`, the
+/// registry at `mirror`.
+fn sources(sandbox: &Sandbox, config: &str, mirror: &Registry) {
+    sandbox.write(
+        "config/tailor/config.toml",
+        &format!(
+            "{}\n[source.wrong]\ndirectory = \"{}\"\n\n[source.mirror]\nregistry = \"{}\"\n",
+            config,
+            sandbox.path("empty").display(),
+            mirror.url()
+        ),
+    );
+}
+
+#[test]
+fn default_source_only_replaces_the_configured_registry() {
+    let registry = Registry::start();
+    let mirror = Registry::start();
+    let mut sandbox = published(&mirror, &["1.0.0"]);
+    app(&sandbox, "\"1.0\"");
+
+    // `TAILOR_REGISTRY` isn't the default registry.
+    mirror.requests();
+    sandbox.env("TAILOR_REGISTRY", registry.url());
+    sources(
+        &sandbox,
+        "[source.default]\nreplace-with = \"mirror\"\n",
+        &mirror,
+    );
+    let output = sandbox.tailor("app", &["build"]);
+    assert!(
+        output
+            .error()
+            .unwrap()
+            .starts_with("no version of `mylib` matching `1.0` found in registry http://127.0.0.1")
+    );
+    assert!(mirror.requests().is_empty());
+    registry.requests();
+
+    sandbox.env_remove("TAILOR_REGISTRY");
+    sources(
+        &sandbox,
+        &format!(
+            "registry = \"{}\"\n\n[source.default]\nreplace-with = \"mirror\"\n",
+            registry.url()
+        ),
+        &mirror,
+    );
+    let output = sandbox.tailor("app", &["build"]);
+    assert_eq!(output.error(), None);
+    assert!(!mirror.requests().is_empty());
+    assert!(registry.requests().is_empty());
+}
+
+#[test]
+fn longest_registry_prefix_picks_the_mirror() {
+    let mirror = Registry::start();
+    let mut sandbox = published(&mirror, &["1.0.0"]);
+    app(&sandbox, "\"1.0\"");
+    sandbox.env("TAILOR_REGISTRY", "https://registry.example.com/team/app");
+    sources(
+        &sandbox,
+        "[source.company]\nregistry = \"https://registry.example.com\"\nreplace-with = \"wrong\"\n\n\
+         [source.team]\nregistry = \"https://registry.example.com/team/\"\nreplace-with = \"mirror\"\n\n\
+         [source.other]\nregistry = \"https://registry.example.com/tea\"\nreplace-with = \"wrong\"\n",
+        &mirror,
+    );
+
+    let output = sandbox.tailor("app", &["build"]);
+    assert_eq!(output.error(), None);
+    assert_eq!(locked_version(&sandbox), "1.0.0");
+}
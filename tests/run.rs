@@ -0,0 +1,165 @@
+#![cfg(unix)]
+
+mod common;
+
+use std::os::unix::fs::PermissionsExt;
+
+use common::Sandbox;
+
+/// Writes the binary package `app`, with the tests `tests/<name>.c` of
+/// `tests`.
+fn app(sandbox: &Sandbox, tests: &[&str]) {
+    sandbox.write("app/Tailor.toml", "name = \"app\"\nversion = \"0.1.0\"\n");
+    sandbox.write("app/src/main.c", "int main(void) { return 0; }\n");
+    for test in tests {
+        sandbox.write(&format!("app/tests/{}.c", test), "");
+    }
+}
+
+/// Writes a shell script at `path`, standing for a binary built by CMake.
+fn script(sandbox: &Sandbox, path: &str, content: &str) {
+    sandbox.write(path, &format!("#!/bin/sh\n{}\n", content));
+    std::fs::set_permissions(sandbox.path(path), std::fs::Permissions::from_mode(0o755)).unwrap();
+}
+
+#[test]
+fn failing_test_fails_the_command() {
+    let sandbox = Sandbox::new();
+    app(&sandbox, &["good", "bad"]);
+    script(&sandbox, "app/build/debug/tests/good", "exit 0");
+    script(&sandbox, "app/build/debug/tests/bad", "exit 1");
+
+    let output = sandbox.tailor("app", &["test"]);
+    assert!(!output.success);
+    assert_eq!(output.error(), Some("1 passed, 1 failed: app/bad"));
+
+    script(&sandbox, "app/build/debug/tests/bad", "exit 0");
+    let output = sandbox.tailor("app", &["test"]);
+    assert_eq!(output.error(), None);
+    assert!(output.stdout.contains("2 passed"));
+}
+
+/// Writes the script `bin/<name>`, logging its arguments to `<name>.log`,
+/// and returns its path.
+fn logging_script(sandbox: &Sandbox, name: &str) -> String {
+    let log = sandbox.path(&format!("{}.log", name));
+    script(
+        sandbox,
+        &format!("bin/{}", name),
+        &format!("echo \"$*\" >> \"{}\"", log.display()),
+    );
+    sandbox.path(&format!("bin/{}", name)).display().to_string()
+}
+
+/// The arguments of the calls of the script `name` since the last time.
+fn logged_calls(sandbox: &Sandbox, name: &str) -> Vec<String> {
+    let log = format!("{}.log", name);
+    let calls = sandbox.read(&log);
+    let _ = std::fs::remove_file(sandbox.path(&log));
+    calls.lines().map(String::from).collect()
+}
+
+#[test]
+fn runner_is_picked_per_profile_and_overridden_by_the_flag() {
+    let sandbox = Sandbox::new();
+    app(&sandbox, &[]);
+    let runner = logging_script(&sandbox, "runner");
+    sandbox.write(
+        "app/Tailor.toml",
+        &format!(
+            "name = \"app\"\nversion = \"0.1.0\"\n\n[profile.debug]\nrunner = [\"{}\", \"--manifest\"]\n",
+            runner
+        ),
+    );
+    sandbox.write(
+        "config/tailor/config.toml",
+        &format!(
+            "[profile.debug]\nrunner = [\"{}\", \"--ignored\"]\n\n[profile.release]\nrunner = [\"{}\", \"--config\"]\n",
+            runner, runner
+        ),
+    );
+    let binary = |mode: &str| sandbox.path(&format!("app/build/{}/app", mode));
+
+    let output = sandbox.tailor("app", &["run"]);
+    assert_eq!(output.error(), None);
+    let output = sandbox.tailor("app", &["run", "--release"]);
+    assert_eq!(output.error(), None);
+    let output = sandbox.tailor(
+        "app",
+        &["run", "--runner", &format!("{} --flag -v", runner)],
+    );
+    assert_eq!(output.error(), None);
+    assert_eq!(
+        logged_calls(&sandbox, "runner"),
+        [
+            format!("--manifest {}", binary("debug").display()),
+            format!("--config {}", binary("release").display()),
+            format!("--flag -v {}", binary("debug").display()),
+        ]
+    );
+
+    // Tests run through the same runner.
+    app(&sandbox, &["unit"]);
+    sandbox.write(
+        "app/Tailor.toml",
+        &format!(
+            "name = \"app\"\nversion = \"0.1.0\"\n\n[profile.debug]\nrunner = [\"{}\"]\n",
+            runner
+        ),
+    );
+    let output = sandbox.tailor("app", &["test"]);
+    assert_eq!(output.error(), None);
+    assert_eq!(
+        logged_calls(&sandbox, "runner"),
+        [sandbox
+            .path("app/build/debug/tests/unit")
+            .display()
+            .to_string()]
+    );
+}
+
+#[test]
+fn debugger_gets_the_source_folders_and_the_arguments() {
+    let sandbox = Sandbox::new();
+    app(&sandbox, &[]);
+    sandbox.write(
+        "mylib/Tailor.toml",
+        "name = \"mylib\"\nversion = \"0.1.0\"\ntype = \"lib\"\n",
+    );
+    sandbox.write("mylib/src/mylib.c", "");
+    sandbox.write(
+        "app/Tailor.toml",
+        "name = \"app\"\nversion = \"0.1.0\"\n\n[dependencies]\nmylib = { path = \"../mylib\" }\n",
+    );
+    logging_script(&sandbox, "gdb");
+    logging_script(&sandbox, "lldb");
+    let binary = sandbox.path("app/build/debug/app");
+
+    let output = sandbox.tailor("app", &["debug", "--", "-n", "3"]);
+    assert_eq!(output.error(), None);
+    assert_eq!(
+        logged_calls(&sandbox, "gdb"),
+        [format!(
+            "--directory={} --directory={} --args {} -n 3",
+            sandbox.path("app").display(),
+            sandbox.path("app/../mylib").display(),
+            binary.display()
+        )]
+    );
+
+    let output = sandbox.tailor("app", &["debug", "--debugger", "lldb", "--", "-n", "3"]);
+    assert_eq!(output.error(), None);
+    assert_eq!(
+        logged_calls(&sandbox, "lldb"),
+        [format!("-- {} -n 3", binary.display())]
+    );
+
+    sandbox.write("config/tailor/config.toml", "debugger = \"lldb\"\n");
+    let output = sandbox.tailor("app", &["debug"]);
+    assert_eq!(output.error(), None);
+    assert_eq!(
+        logged_calls(&sandbox, "lldb"),
+        [format!("-- {}", binary.display())]
+    );
+    assert!(logged_calls(&sandbox, "gdb").is_empty());
+}
@@ -0,0 +1,109 @@
+#![cfg(unix)]
+
+mod common;
+
+use common::Sandbox;
+
+const MANIFEST: &str = r#"name = "blink"
+version = "0.1.0"
+type = "sdk"
+
+[sdk]
+entry = "${PICO_SDK_PATH}/pico_sdk_import.cmake"
+env = ["PICO_SDK_PATH"]
+init = ["pico_sdk_init()"]
+post = ["pico_add_extra_outputs($pkg_name)"]
+
+[sdk.parameters]
+PICO_BOARD = "$board"
+"#;
+
+/// A package of the mock SDK at `sdk/<name>`, built for `board`.
+fn sdk_package(name: &str) -> Sandbox {
+    let mut sandbox = Sandbox::new();
+    sandbox.write(&format!("sdk/{}/pico_sdk_import.cmake", name), "");
+    sandbox.write("blink/Tailor.toml", &MANIFEST.replace("$board", "pico"));
+    sandbox.write("blink/src/main.c", "int main(void) { return 0; }\n");
+    let sdk = sandbox.path(&format!("sdk/{}", name));
+    sandbox.env("PICO_SDK_PATH", &sdk.to_string_lossy());
+    sandbox
+}
+
+#[test]
+fn sdk_boilerplate_surrounds_the_package() {
+    let sandbox = sdk_package("pico");
+
+    let output = sandbox.tailor("blink", &["build"]);
+    assert_eq!(output.error(), None);
+
+    let cmake_lists = sandbox.read("blink/build/debug/CMakeLists.txt");
+    let include = format!(
+        "include(\"{}\")",
+        sandbox.path("sdk/pico/pico_sdk_import.cmake").display()
+    );
+    let include_at = cmake_lists.find(&include).expect("the SDK isn't included");
+    let project_at = cmake_lists.find("project(blink C CXX ASM)").unwrap();
+    let init_at = cmake_lists.find("pico_sdk_init()").unwrap();
+    assert!(include_at < project_at && project_at < init_at);
+    assert!(cmake_lists.contains("add_executable(blink)"));
+    assert!(cmake_lists.contains("pico_add_extra_outputs(blink)"));
+
+    let generations = sandbox.cmake_generations();
+    assert_eq!(generations.len(), 1);
+    assert!(generations[0].contains("-DPICO_BOARD=pico"));
+}
+
+#[test]
+fn changing_a_parameter_regenerates_the_build() {
+    let sandbox = sdk_package("pico");
+    sandbox.tailor("blink", &["build"]);
+    sandbox.cmake_calls();
+
+    sandbox.tailor("blink", &["build"]);
+    assert!(sandbox.cmake_generations().is_empty());
+
+    sandbox.write("blink/Tailor.toml", &MANIFEST.replace("$board", "pico_w"));
+    sandbox.tailor("blink", &["build"]);
+    let generations = sandbox.cmake_generations();
+    assert_eq!(generations.len(), 1);
+    assert!(generations[0].contains("-DPICO_BOARD=pico_w"));
+}
+
+#[test]
+fn changing_the_sdk_regenerates_the_build() {
+    let mut sandbox = sdk_package("pico");
+    sandbox.tailor("blink", &["build"]);
+    sandbox.cmake_calls();
+
+    sandbox.write("sdk/pico2/pico_sdk_import.cmake", "");
+    let sdk = sandbox.path("sdk/pico2");
+    sandbox.env("PICO_SDK_PATH", &sdk.to_string_lossy());
+    sandbox.tailor("blink", &["build"]);
+
+    assert_eq!(sandbox.cmake_generations().len(), 1);
+    assert!(
+        sandbox
+            .read("blink/build/debug/CMakeLists.txt")
+            .contains("sdk/pico2/pico_sdk_import.cmake")
+    );
+}
+
+#[test]
+fn missing_sdk_environment_is_an_error() {
+    let mut sandbox = sdk_package("pico");
+    sandbox.env("PICO_SDK_PATH", "");
+    let output = sandbox.tailor("blink", &["build"]);
+    assert!(output.error().is_some());
+
+    let sandbox = {
+        let sandbox = Sandbox::new();
+        sandbox.write("blink/Tailor.toml", &MANIFEST.replace("$board", "pico"));
+        sandbox.write("blink/src/main.c", "");
+        sandbox
+    };
+    let output = sandbox.tailor("blink", &["build"]);
+    assert_eq!(
+        output.error(),
+        Some("the SDK requires the environment variable `PICO_SDK_PATH`")
+    );
+}
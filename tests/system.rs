@@ -0,0 +1,95 @@
+#![cfg(unix)]
+
+mod common;
+
+use std::os::unix::fs::PermissionsExt;
+
+use common::Sandbox;
+
+/// Writes a `pkg-config` knowing only `zlib 1.2.13`, logging its arguments to
+/// `pkg-config.log`.
+fn pkg_config(sandbox: &Sandbox) {
+    sandbox.write(
+        "bin/pkg-config",
+        &format!(
+            r#"#!/bin/sh
+echo "$*" >> "{}"
+case "$1" in
+--exists)
+    case "$3" in
+    "zlib >= 1.2" | "zlib != 1.3") exit 0 ;;
+    esac
+    echo "Package '$3' was not found" >&2
+    exit 1 ;;
+--cflags-only-I) echo "-I/opt/zlib/include -I/opt/zlib/include/zlib" ;;
+--cflags-only-other) echo "-DZLIB_CONST -pthread" ;;
+--libs) echo "-L/opt/zlib/lib -lz" ;;
+esac
+"#,
+            sandbox.path("pkg-config.log").display()
+        ),
+    );
+    std::fs::set_permissions(
+        sandbox.path("bin/pkg-config"),
+        std::fs::Permissions::from_mode(0o755),
+    )
+    .unwrap();
+}
+
+/// Writes the package `app`, depending on `zlib` with `dependency`.
+fn app(sandbox: &Sandbox, dependency: &str) {
+    sandbox.write(
+        "app/Tailor.toml",
+        &format!(
+            "name = \"app\"\nversion = \"0.1.0\"\n\n[dependencies]\nzlib = {}\n",
+            dependency
+        ),
+    );
+    sandbox.write("app/src/main.c", "int main(void) { return 0; }\n");
+}
+
+#[test]
+fn system_library_flags_are_added_to_the_package() {
+    let sandbox = Sandbox::new();
+    pkg_config(&sandbox);
+    app(&sandbox, "{ pkg-config = \"zlib\", version = \"1.2\" }");
+
+    let output = sandbox.tailor("app", &["build"]);
+    assert_eq!(output.error(), None);
+    assert_eq!(
+        sandbox.read("pkg-config.log"),
+        "--exists --print-errors zlib >= 1.2\n--cflags-only-I zlib\n\
+         --cflags-only-other zlib\n--libs zlib\n"
+    );
+    let cmake_lists = sandbox.read("app/build/debug/CMakeLists.txt");
+    assert!(
+        cmake_lists.contains("\"/opt/zlib/include\" \"/opt/zlib/include/zlib\""),
+        "{}",
+        cmake_lists
+    );
+    assert!(cmake_lists.contains("target_compile_options(app PRIVATE -DZLIB_CONST -pthread)\n"));
+    assert!(cmake_lists.contains("target_link_libraries(app PRIVATE -L/opt/zlib/lib -lz)\n"));
+    // System libraries aren't locked.
+    assert!(!sandbox.read("app/Tailor.lock").contains("zlib"));
+
+    app(&sandbox, "{ pkg-config = \"zlib\", version = \"!= 1.3\" }");
+    let output = sandbox.tailor("app", &["build"]);
+    assert_eq!(output.error(), None);
+}
+
+#[test]
+fn missing_system_library_is_an_error() {
+    let sandbox = Sandbox::new();
+    pkg_config(&sandbox);
+    app(&sandbox, "{ pkg-config = \"zlib\", version = \">=1.3\" }");
+
+    let output = sandbox.tailor("app", &["build"]);
+    assert_eq!(
+        output.error(),
+        Some(
+            "system library `zlib` (`zlib >= 1.3`) was not found by pkg-config, install it or set \
+             PKG_CONFIG_PATH to the folder of its `.pc` file:\nPackage 'zlib >= 1.3' was not found"
+        )
+    );
+    assert!(sandbox.cmake_generations().is_empty());
+}
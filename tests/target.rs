@@ -0,0 +1,150 @@
+#![cfg(unix)]
+
+mod common;
+
+use std::os::unix::fs::PermissionsExt;
+
+use common::Sandbox;
+
+/// Writes the binary package `app`, declaring the targets `targets`.
+fn app(sandbox: &Sandbox, targets: &str) {
+    sandbox.write(
+        "app/Tailor.toml",
+        &format!("name = \"app\"\nversion = \"0.1.0\"\n\n{}", targets),
+    );
+    sandbox.write("app/src/main.c", "int main(void) { return 0; }\n");
+}
+
+/// Writes the script `bin/<name>`, logging its arguments to `<name>.log`.
+fn logging_script(sandbox: &Sandbox, name: &str) {
+    let path = format!("bin/{}", name);
+    sandbox.write(
+        &path,
+        &format!(
+            "#!/bin/sh\necho \"$*\" >> \"{}\"\n",
+            sandbox.path(&format!("{}.log", name)).display()
+        ),
+    );
+    std::fs::set_permissions(sandbox.path(&path), std::fs::Permissions::from_mode(0o755)).unwrap();
+}
+
+#[test]
+fn target_build_passes_the_toolchain_to_cmake() {
+    let sandbox = Sandbox::new();
+    app(
+        &sandbox,
+        "[target.cortex-m4]\ntoolchain-file = \"cmake/arm.cmake\"\ncc = \"arm-none-eabi-gcc\"\nsysroot = \"sysroot\"\n",
+    );
+    sandbox.write(
+        "config/tailor/config.toml",
+        "[target.riscv]\ntoolchain-file = \"riscv.cmake\"\n",
+    );
+
+    let output = sandbox.tailor("app", &["build", "--target", "cortex-m4"]);
+    assert_eq!(output.error(), None);
+    let build_dir = sandbox.path("app/build/cortex-m4/debug");
+    assert_eq!(
+        sandbox.cmake_generations(),
+        [format!(
+            "-S {0} -B {0} -DCMAKE_BUILD_TYPE=debug -DCMAKE_TOOLCHAIN_FILE={1} -DCMAKE_C_COMPILER=arm-none-eabi-gcc -DCMAKE_SYSROOT={2}",
+            build_dir.display(),
+            sandbox.path("app/cmake/arm.cmake").display(),
+            sandbox.path("app/sysroot").display()
+        )]
+    );
+
+    // A target of the config has its paths relative to the config.
+    let output = sandbox.tailor("app", &["build", "--release", "--target", "riscv"]);
+    assert_eq!(output.error(), None);
+    assert_eq!(
+        sandbox.cmake_generations(),
+        [format!(
+            "-S {0} -B {0} -DCMAKE_BUILD_TYPE=release -DCMAKE_TOOLCHAIN_FILE={1}",
+            sandbox.path("app/build/riscv/release").display(),
+            sandbox.path("config/tailor/riscv.cmake").display()
+        )]
+    );
+
+    let output = sandbox.tailor("app", &["build", "--target", "x86"]);
+    assert_eq!(
+        output.error(),
+        Some("target `x86` is not defined in Tailor.toml or in the config")
+    );
+}
+
+#[test]
+fn target_binary_only_runs_through_a_runner() {
+    let sandbox = Sandbox::new();
+    logging_script(&sandbox, "qemu-arm");
+    logging_script(&sandbox, "other");
+    app(
+        &sandbox,
+        "[target.bare]\ncc = \"arm-none-eabi-gcc\"\n\n\
+         [target.cortex-m4]\nrunner = [\"qemu-arm\", \"-cpu\", \"cortex-m4\"]\n",
+    );
+
+    let output = sandbox.tailor("app", &["run", "--target", "bare"]);
+    assert_eq!(
+        output.error(),
+        Some("It's not possible run a package built for target `bare` without a runner")
+    );
+
+    let output = sandbox.tailor("app", &["run", "--target", "cortex-m4"]);
+    assert_eq!(output.error(), None);
+    assert_eq!(
+        sandbox.read("qemu-arm.log"),
+        format!(
+            "-cpu cortex-m4 {}\n",
+            sandbox.path("app/build/cortex-m4/debug/app").display()
+        )
+    );
+
+    // The flag has precedence over the runner of the target.
+    let output = sandbox.tailor("app", &["run", "--target", "bare", "--runner", "other"]);
+    assert_eq!(output.error(), None);
+    assert_eq!(
+        sandbox.read("other.log"),
+        format!("{}\n", sandbox.path("app/build/bare/debug/app").display())
+    );
+}
+
+#[test]
+fn conditional_dependencies_follow_the_platform() {
+    let sandbox = Sandbox::new();
+    for name in ["posix", "hal", "cmsis"] {
+        sandbox.write(
+            &format!("{}/Tailor.toml", name),
+            &format!("name = \"{}\"\nversion = \"0.1.0\"\ntype = \"lib\"\n", name),
+        );
+        sandbox.write(&format!("{}/src/{}.c", name, name), "");
+    }
+    app(
+        &sandbox,
+        "[target.cortex-m4]\narch = \"arm\"\n\n\
+         [target.'cfg(unix)'.dependencies]\nposix = { path = \"../posix\" }\n\n\
+         [target.'cfg(all(target_os = \"none\", target_arch = \"arm\"))'.dependencies]\n\
+         hal = { path = \"../hal\" }\n\n\
+         [target.cortex-m4.dependencies]\ncmsis = { path = \"../cmsis\" }\n",
+    );
+    let sources = |cmake_lists: &str| {
+        ["posix", "hal", "cmsis"]
+            .into_iter()
+            .filter(|name| {
+                sandbox
+                    .read(cmake_lists)
+                    .contains(&format!("{0}/src/{0}.c", name))
+            })
+            .collect::<Vec<&str>>()
+    };
+
+    let output = sandbox.tailor("app", &["build"]);
+    assert_eq!(output.error(), None);
+    assert_eq!(sources("app/build/debug/CMakeLists.txt"), ["posix"]);
+
+    let output = sandbox.tailor("app", &["build", "--target", "cortex-m4"]);
+    assert_eq!(output.error(), None);
+    assert_eq!(
+        sources("app/build/cortex-m4/debug/CMakeLists.txt"),
+        ["hal", "cmsis"]
+    );
+}
@@ -0,0 +1,108 @@
+#![cfg(unix)]
+
+mod common;
+
+use std::os::unix::fs::PermissionsExt;
+
+use common::Sandbox;
+
+/// Writes the binary package `name`.
+fn package(sandbox: &Sandbox, name: &str) {
+    sandbox.write(
+        &format!("{}/Tailor.toml", name),
+        &format!("name = \"{}\"\nversion = \"0.1.0\"\n", name),
+    );
+    sandbox.write(
+        &format!("{}/src/main.c", name),
+        "int main(void) { return 0; }\n",
+    );
+}
+
+/// The folder where the build files of the last build were generated.
+fn generated_dir(sandbox: &Sandbox) -> String {
+    let generations = sandbox.cmake_generations();
+    assert_eq!(generations.len(), 1, "{:?}", generations);
+    generations[0]
+        .strip_prefix("-S ")
+        .and_then(|call| call.split(' ').next())
+        .unwrap()
+        .to_string()
+}
+
+#[test]
+fn target_dir_is_shared_by_the_packages() {
+    let sandbox = Sandbox::new();
+    package(&sandbox, "app");
+    package(&sandbox, "tool");
+
+    let output = sandbox.tailor("app", &["build", "--target-dir", "../out"]);
+    assert_eq!(output.error(), None);
+    assert_eq!(
+        generated_dir(&sandbox),
+        sandbox.path("app/../out/app/debug").display().to_string()
+    );
+    let output = sandbox.tailor("tool", &["build", "--release", "--target-dir", "../out"]);
+    assert_eq!(output.error(), None);
+    assert_eq!(
+        generated_dir(&sandbox),
+        sandbox
+            .path("tool/../out/tool/release")
+            .display()
+            .to_string()
+    );
+    assert!(!sandbox.path("app/build").exists());
+    assert!(!sandbox.path("tool/build").exists());
+}
+
+#[test]
+fn flag_has_precedence_over_the_environment_and_the_config() {
+    let mut sandbox = Sandbox::new();
+    package(&sandbox, "app");
+    sandbox.write("config/tailor/config.toml", "target-dir = \"shared\"\n");
+
+    let output = sandbox.tailor("app", &["build"]);
+    assert_eq!(output.error(), None);
+    assert_eq!(
+        generated_dir(&sandbox),
+        sandbox
+            .path("config/tailor/shared/app/debug")
+            .display()
+            .to_string()
+    );
+
+    let env = sandbox.path("env").display().to_string();
+    sandbox.env("TAILOR_TARGET_DIR", &env);
+    let output = sandbox.tailor("app", &["build"]);
+    assert_eq!(output.error(), None);
+    assert_eq!(generated_dir(&sandbox), format!("{}/app/debug", env));
+
+    let flag = sandbox.path("flag").display().to_string();
+    let output = sandbox.tailor("app", &["build", "--target-dir", &flag]);
+    assert_eq!(output.error(), None);
+    assert_eq!(generated_dir(&sandbox), format!("{}/app/debug", flag));
+}
+
+#[test]
+fn run_finds_the_binary_in_the_target_dir() {
+    let mut sandbox = Sandbox::new();
+    package(&sandbox, "app");
+    let target_dir = sandbox.path("out").display().to_string();
+    sandbox.env("TAILOR_TARGET_DIR", &target_dir);
+    // A script stands for the binary built by CMake.
+    sandbox.write(
+        "out/app/debug/app",
+        &format!(
+            "#!/bin/sh\necho ran > \"{}\"\n",
+            sandbox.path("ran").display()
+        ),
+    );
+    std::fs::set_permissions(
+        sandbox.path("out/app/debug/app"),
+        std::fs::Permissions::from_mode(0o755),
+    )
+    .unwrap();
+
+    let output = sandbox.tailor("app", &["run"]);
+    assert_eq!(output.error(), None);
+    assert_eq!(sandbox.read("ran"), "ran\n");
+}
@@ -0,0 +1,232 @@
+#![cfg(unix)]
+
+mod common;
+
+use common::{Sandbox, registry::Registry};
+
+/// Writes a workspace at the folder `root`, with the libraries `libs/mylib`
+/// and `libs/extra`, and the binary `apps/app` depending on `mylib` with
+/// `requirement`.
+fn workspace(sandbox: &Sandbox, root: &str, requirement: &str) {
+    sandbox.write(
+        &format!("{}/Tailor.toml", root),
+        "[workspace]\nmembers = [\"libs/*\", \"apps/app\"]\n",
+    );
+    sandbox.write(
+        &format!("{}/libs/mylib/src/mylib.c", root),
+        "int answer(void) { return 42; }\n",
+    );
+    sandbox.write(
+        &format!("{}/libs/mylib/Tailor.toml", root),
+        "name = \"mylib\"\nversion = \"0.1.0\"\ntype = \"lib\"\n",
+    );
+    sandbox.write(
+        &format!("{}/libs/extra/Tailor.toml", root),
+        "name = \"extra\"\nversion = \"0.1.0\"\ntype = \"lib\"\n",
+    );
+    sandbox.write(&format!("{}/libs/extra/src/extra.c", root), "");
+    // Not a member, as it has no manifest.
+    sandbox.write(&format!("{}/libs/notes/README", root), "");
+    sandbox.write(
+        &format!("{}/apps/app/Tailor.toml", root),
+        &format!(
+            "name = \"app\"\nversion = \"0.1.0\"\n\n[dependencies]\nmylib = {}\n",
+            requirement
+        ),
+    );
+    sandbox.write(
+        &format!("{}/apps/app/src/main.c", root),
+        "int main(void) { return 0; }\n",
+    );
+}
+
+#[test]
+fn members_are_matched_inside_a_root_with_glob_characters() {
+    let sandbox = Sandbox::new();
+    workspace(&sandbox, "ws[1]", "\"*\"");
+
+    let output = sandbox.tailor("ws[1]", &["build"]);
+    assert_eq!(output.error(), None);
+    let root = sandbox.path("ws[1]");
+    assert_eq!(
+        sandbox.cmake_generations(),
+        ["app", "extra", "mylib"]
+            .iter()
+            .map(|pkg| {
+                let dir = root.join("build").join(pkg).join("debug");
+                format!(
+                    "-S {} -B {} -DCMAKE_BUILD_TYPE=debug",
+                    dir.display(),
+                    dir.display()
+                )
+            })
+            .collect::<Vec<String>>()
+    );
+
+    // A member depending on another one by name uses its folder.
+    let cmake_lists = sandbox.read("ws[1]/build/app/debug/CMakeLists.txt");
+    assert!(cmake_lists.contains(&format!("\"{}/libs/mylib/src/mylib.c\"", root.display())));
+}
+
+#[test]
+fn members_share_the_output_directory_of_the_root() {
+    let sandbox = Sandbox::new();
+    workspace(&sandbox, "ws", "\"*\"");
+
+    let output = sandbox.tailor("ws/apps/app", &["build"]);
+    assert_eq!(output.error(), None);
+    assert!(sandbox.path("ws/build/app/debug/CMakeLists.txt").is_file());
+    assert!(!sandbox.path("ws/apps/app/build").exists());
+    assert!(!sandbox.path("ws/build/extra").exists());
+
+    let output = sandbox.tailor("ws/apps/app", &["build", "-p", "extra"]);
+    assert_eq!(output.error(), None);
+    assert!(
+        sandbox
+            .path("ws/build/extra/debug/CMakeLists.txt")
+            .is_file()
+    );
+}
+
+#[test]
+fn members_share_the_lockfile_of_the_root() {
+    let registry = Registry::start();
+    let mut sandbox = Sandbox::new();
+    sandbox.env("TAILOR_REGISTRY", registry.url());
+    sandbox.library("published", "1.0.0");
+    let output = sandbox.tailor(
+        "published",
+        &["publish", "--no-verify", "--token", "secret"],
+    );
+    assert_eq!(output.error(), None);
+
+    workspace(&sandbox, "ws", "\"*\"");
+    sandbox.write(
+        "ws/libs/extra/Tailor.toml",
+        "name = \"extra\"\nversion = \"0.1.0\"\ntype = \"lib\"\n\n[dependencies]\npublished = \"1.0\"\n",
+    );
+
+    let output = sandbox.tailor("ws/libs/extra", &["build"]);
+    assert_eq!(output.error(), None);
+    assert!(
+        sandbox
+            .read("ws/Tailor.lock")
+            .contains("name = \"published\"")
+    );
+    assert!(!sandbox.path("ws/libs/extra/Tailor.lock").exists());
+
+    let lockfile = sandbox.read("ws/Tailor.lock");
+    let output = sandbox.tailor("ws", &["build"]);
+    assert_eq!(output.error(), None);
+    assert_eq!(sandbox.read("ws/Tailor.lock"), lockfile);
+    assert!(!sandbox.path("ws/apps/app/Tailor.lock").exists());
+}
+
+#[test]
+fn versioned_dependency_on_a_member_name_comes_from_the_registry() {
+    let registry = Registry::start();
+    let mut sandbox = Sandbox::new();
+    sandbox.env("TAILOR_REGISTRY", registry.url());
+    sandbox.library("mylib", "1.0.0");
+    let output = sandbox.tailor("mylib", &["publish", "--no-verify", "--token", "secret"]);
+    assert_eq!(output.error(), None);
+
+    workspace(&sandbox, "ws", "\"1.0\"");
+    let output = sandbox.tailor("ws", &["build", "-p", "app"]);
+    assert_eq!(output.error(), None);
+    let cmake_lists = sandbox.read("ws/build/app/debug/CMakeLists.txt");
+    assert!(cmake_lists.contains("mylib@1.0.0"));
+    assert!(!cmake_lists.contains("libs/mylib"));
+
+    workspace(&sandbox, "ws", "{ path = \"../../libs/mylib\" }");
+    let output = sandbox.tailor("ws", &["build", "-p", "app"]);
+    assert_eq!(output.error(), None);
+    let cmake_lists = sandbox.read("ws/build/app/debug/CMakeLists.txt");
+    assert!(cmake_lists.contains("libs/mylib/src/mylib.c"));
+}
+
+/// The packages whose build files were generated since the last time, from
+/// their folder inside `ws/build`.
+fn generated_packages(sandbox: &Sandbox) -> Vec<String> {
+    let build = format!("-S {}/", sandbox.path("ws/build").display());
+    sandbox
+        .cmake_generations()
+        .iter()
+        .filter_map(|call| call.strip_prefix(&build))
+        .filter_map(|call| call.split('/').next())
+        .map(String::from)
+        .collect()
+}
+
+#[test]
+fn packages_are_selected_by_name_from_any_folder() {
+    let sandbox = Sandbox::new();
+    workspace(&sandbox, "ws", "\"*\"");
+
+    let output = sandbox.tailor("ws", &["build"]);
+    assert_eq!(output.error(), None);
+    assert_eq!(generated_packages(&sandbox), ["app", "extra", "mylib"]);
+
+    let output = sandbox.tailor("ws/libs/extra", &["build"]);
+    assert_eq!(output.error(), None);
+    assert!(generated_packages(&sandbox).is_empty());
+    sandbox.tailor("ws", &["clean"]);
+
+    let output = sandbox.tailor(
+        "ws/libs/extra",
+        &["build", "-p", "app", "--package", "mylib"],
+    );
+    assert_eq!(output.error(), None);
+    assert_eq!(generated_packages(&sandbox), ["app", "mylib"]);
+
+    let output = sandbox.tailor("ws/apps/app", &["build", "--workspace", "--exclude", "app"]);
+    assert_eq!(output.error(), None);
+    assert_eq!(generated_packages(&sandbox), ["extra"]);
+
+    let output = sandbox.tailor("ws", &["clean", "-p", "mylib"]);
+    assert_eq!(output.error(), None);
+    assert!(!sandbox.path("ws/build/mylib").exists());
+    assert!(sandbox.path("ws/build/extra").exists());
+    assert!(sandbox.path("ws/build/app").exists());
+}
+
+#[test]
+fn invalid_package_selections_are_errors() {
+    let sandbox = Sandbox::new();
+    workspace(&sandbox, "ws", "\"*\"");
+    let error = |cwd: &str, args: &[&str]| sandbox.tailor(cwd, args).error().map(String::from);
+
+    assert_eq!(
+        error("ws", &["build", "-p", "missing"]).as_deref(),
+        Some("package `missing` is not a member of the workspace")
+    );
+    assert_eq!(
+        error("ws", &["build", "-p", "app", "--workspace"]).as_deref(),
+        Some("`--package` and `--workspace` can't be used together")
+    );
+    assert_eq!(
+        error("ws/apps/app", &["build", "--exclude", "mylib"]).as_deref(),
+        Some("`--exclude` can only be used together with `--workspace`")
+    );
+    assert_eq!(
+        error("ws", &["build", "--workspace", "--exclude", "missing"]).as_deref(),
+        Some("excluded package `missing` is not a member of the workspace")
+    );
+    assert_eq!(
+        error("ws", &["run", "-p", "mylib"]).as_deref(),
+        Some("It's not possible run a library package, an sdk package or a virtual workspace")
+    );
+
+    // Outside a workspace, the only package is the one of the folder.
+    sandbox.write("solo/Tailor.toml", "name = \"solo\"\nversion = \"0.1.0\"\n");
+    sandbox.write("solo/src/main.c", "int main(void) { return 0; }\n");
+    assert_eq!(error("solo", &["build", "-p", "solo"]), None);
+    assert_eq!(
+        error("solo", &["build", "-p", "mylib"]).as_deref(),
+        Some("package `mylib` not found")
+    );
+    assert_eq!(
+        error("solo", &["build", "--workspace"]).as_deref(),
+        Some("`--workspace` can only be used inside a workspace")
+    );
+}